@@ -0,0 +1,11 @@
+-- Postgres mirror of migrations/sqlite. Keep both sets in step: every schema change
+-- gets a file with the same number in each.
+CREATE TABLE users (
+    id BIGSERIAL PRIMARY KEY,
+    username TEXT NOT NULL,
+    discord_id TEXT NOT NULL UNIQUE,
+    country TEXT,
+    day_created BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT
+);
+
+CREATE INDEX idx_discord_id ON users(discord_id);
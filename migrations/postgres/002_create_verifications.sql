@@ -0,0 +1,13 @@
+CREATE TABLE verifications (
+    id BIGSERIAL PRIMARY KEY,
+    discord_id TEXT NOT NULL,
+    krunker_username TEXT NOT NULL,
+    code TEXT NOT NULL UNIQUE,
+    created_at BIGINT NOT NULL DEFAULT EXTRACT(EPOCH FROM NOW())::BIGINT,
+    expires_at BIGINT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_verification_code ON verifications(code);
+CREATE INDEX idx_verification_discord_id ON verifications(discord_id);
+CREATE INDEX idx_verification_expires ON verifications(expires_at);
@@ -0,0 +1,6 @@
+-- Allow several Krunker accounts per Discord user, with exactly one marked primary
+ALTER TABLE users DROP CONSTRAINT users_discord_id_key;
+ALTER TABLE users ADD COLUMN is_primary BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE users SET is_primary = TRUE;
+
+CREATE UNIQUE INDEX idx_users_primary ON users(discord_id) WHERE is_primary;
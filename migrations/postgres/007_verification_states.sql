@@ -0,0 +1,46 @@
+-- Verifications are no longer deleted when they finish. They move from pending to
+-- verified, expired, failed or cancelled, and every move is logged.
+ALTER TABLE verifications ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+ALTER TABLE verifications ADD COLUMN updated_at BIGINT;
+
+UPDATE verifications SET status = 'expired', updated_at = EXTRACT(EPOCH FROM NOW())::BIGINT
+WHERE expires_at < EXTRACT(EPOCH FROM NOW())::BIGINT;
+
+CREATE UNIQUE INDEX idx_verifications_one_pending ON verifications(discord_id) WHERE status = 'pending';
+CREATE INDEX idx_verification_status ON verifications(status);
+
+CREATE TABLE verification_status_history (
+    id BIGSERIAL PRIMARY KEY,
+    verification_id BIGINT NOT NULL REFERENCES verifications(id) ON DELETE CASCADE,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    reason TEXT,
+    changed_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_status_history_verification ON verification_status_history(verification_id);
+
+INSERT INTO verification_status_history (verification_id, from_status, to_status, reason, changed_at)
+SELECT id, NULL, 'pending', 'backfilled', created_at FROM verifications;
+
+-- A Krunker account can only be linked once, whatever the casing of its name.
+-- Where older links clash the first one stays. The others are logged to
+-- link_events before they go, and database::migrate reports them at startup.
+INSERT INTO link_events (discord_id, krunker_username, event, actor_id, details)
+SELECT dup.discord_id, dup.username, 'removed_by_migration', 'system',
+       'duplicate of ' || kept.username || ' linked to ' || kept.discord_id || ', removed by migration 007'
+FROM users dup
+JOIN users kept ON kept.id = (
+    SELECT MIN(id) FROM users earliest WHERE LOWER(earliest.username) = LOWER(dup.username)
+)
+WHERE dup.id <> kept.id;
+
+DELETE FROM users WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY LOWER(username));
+
+-- whoever lost their primary that way gets their oldest remaining account
+UPDATE users SET is_primary = TRUE
+WHERE id IN (SELECT MIN(id) FROM users GROUP BY discord_id HAVING NOT BOOL_OR(is_primary));
+CREATE UNIQUE INDEX idx_users_username ON users(LOWER(username));
+
+DROP INDEX idx_users_krunker_id;
+CREATE UNIQUE INDEX idx_users_krunker_id ON users(krunker_id) WHERE krunker_id IS NOT NULL;
@@ -0,0 +1,14 @@
+-- One row per command dispatched through the handler, kept for a limited time
+CREATE TABLE IF NOT EXISTS command_usage (
+    id BIGSERIAL PRIMARY KEY,
+    command TEXT NOT NULL,
+    guild_id TEXT,
+    discord_id TEXT NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    -- NULL when the command succeeded
+    error_class TEXT,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_command_usage_created_at ON command_usage(created_at);
+CREATE INDEX IF NOT EXISTS idx_command_usage_command ON command_usage(command, created_at);
@@ -0,0 +1,47 @@
+-- Ranked matches fetched from the Krunker API, kept for match history features
+CREATE TABLE matches (
+    match_id BIGINT PRIMARY KEY,
+    map TEXT NOT NULL,
+    duration_ms BIGINT NOT NULL,
+    -- as the API reports it
+    played_at TEXT NOT NULL,
+    fetched_at BIGINT NOT NULL
+);
+
+CREATE TABLE match_participants (
+    match_id BIGINT NOT NULL,
+    player_name TEXT NOT NULL,
+    team BIGINT NOT NULL,
+    -- 1 for the winning team, like the API's mp_victory
+    victory BIGINT NOT NULL,
+    kills BIGINT NOT NULL,
+    deaths BIGINT NOT NULL,
+    assists BIGINT NOT NULL,
+    score BIGINT NOT NULL,
+    damage_done BIGINT NOT NULL,
+    objective_score BIGINT NOT NULL,
+    PRIMARY KEY (match_id, player_name)
+);
+
+CREATE INDEX idx_match_participants_player ON match_participants(LOWER(player_name));
+
+-- Where each guild wants match announcements posted
+CREATE TABLE match_feed_channels (
+    guild_id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+-- Members who don't want their matches announced
+CREATE TABLE match_feed_optouts (
+    discord_id TEXT PRIMARY KEY,
+    created_at BIGINT NOT NULL
+);
+
+-- The newest match already seen for each linked account
+CREATE TABLE match_feed_cursors (
+    user_id BIGINT PRIMARY KEY,
+    last_match_id BIGINT NOT NULL,
+    checked_at BIGINT NOT NULL
+);
@@ -0,0 +1,33 @@
+-- Copies of get_player for tracked players, a new row whenever something changed
+CREATE TABLE profile_snapshots (
+    id BIGSERIAL PRIMARY KEY,
+    krunker_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    level BIGINT NOT NULL,
+    kr BIGINT NOT NULL,
+    games BIGINT NOT NULL,
+    kdr DOUBLE PRECISION NOT NULL,
+    clan TEXT,
+    taken_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_profile_snapshots_krunker_id ON profile_snapshots(krunker_id, taken_at);
+CREATE INDEX idx_profile_snapshots_username ON profile_snapshots(LOWER(username));
+
+-- Where each guild wants milestone announcements posted
+CREATE TABLE milestone_channels (
+    guild_id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+-- Milestones already announced, so a restart never announces one twice
+CREATE TABLE milestone_announcements (
+    id BIGSERIAL PRIMARY KEY,
+    krunker_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value BIGINT NOT NULL,
+    announced_at BIGINT NOT NULL,
+    UNIQUE (krunker_id, kind, value)
+);
@@ -0,0 +1,14 @@
+-- Ratings worked out from the stored ranked matches. Rebuilt in full on every
+-- recompute, nothing here is edited by hand.
+CREATE TABLE player_ratings (
+    -- player_name lowercased, names are matched in any case
+    player_key TEXT PRIMARY KEY,
+    player_name TEXT NOT NULL,
+    rating DOUBLE PRECISION NOT NULL,
+    games BIGINT NOT NULL,
+    wins BIGINT NOT NULL,
+    peak DOUBLE PRECISION NOT NULL,
+    computed_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_player_ratings_rating ON player_ratings(rating);
@@ -0,0 +1,29 @@
+-- Lobbies made by &queue, with the teams as they stood when the players locked
+-- them in or the reroll buttons timed out
+CREATE TABLE lobbies (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id TEXT,
+    channel_id TEXT NOT NULL,
+    started_by TEXT NOT NULL,
+    team_size BIGINT NOT NULL,
+    rerolls BIGINT NOT NULL,
+    -- 1 if the players locked the teams in, 0 if the buttons timed out
+    locked_in BIGINT NOT NULL,
+    -- team 1's total skill minus team 2's
+    skill_difference DOUBLE PRECISION NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX idx_lobbies_channel ON lobbies(channel_id, created_at);
+
+CREATE TABLE lobby_players (
+    lobby_id BIGINT NOT NULL,
+    discord_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    -- 1 or 2
+    team BIGINT NOT NULL,
+    skill DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (lobby_id, discord_id)
+);
+
+CREATE INDEX idx_lobby_players_discord_id ON lobby_players(discord_id);
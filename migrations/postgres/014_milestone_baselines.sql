@@ -0,0 +1,10 @@
+-- The numbers the milestones job last compared each player against. Only that job
+-- moves them, so a snapshot taken by &stats or another feature in between can't
+-- swallow a milestone.
+CREATE TABLE milestone_baselines (
+    krunker_id TEXT PRIMARY KEY,
+    level BIGINT NOT NULL,
+    kr BIGINT NOT NULL,
+    games BIGINT NOT NULL,
+    checked_at BIGINT NOT NULL
+);
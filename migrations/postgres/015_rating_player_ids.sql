@@ -0,0 +1,4 @@
+-- Ratings follow a player's krunker_id across renames. player_key becomes
+-- "id:<krunker_id>", or "name:<lowercased name>" for players never looked up.
+ALTER TABLE player_ratings ADD COLUMN krunker_id TEXT;
+UPDATE player_ratings SET player_key = 'name:' || player_key;
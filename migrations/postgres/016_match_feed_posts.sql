@@ -0,0 +1,10 @@
+-- Channels each new match card already went out to, so a run that only partly
+-- posted retries the channels that failed and no others. Rows go once the
+-- account's cursor moves past the match.
+CREATE TABLE match_feed_posts (
+    user_id BIGINT NOT NULL,
+    match_id BIGINT NOT NULL,
+    channel_id TEXT NOT NULL,
+    posted_at BIGINT NOT NULL,
+    PRIMARY KEY (user_id, match_id, channel_id)
+);
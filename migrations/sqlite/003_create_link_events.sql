@@ -0,0 +1,12 @@
+CREATE TABLE link_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_id TEXT NOT NULL,
+    krunker_username TEXT NOT NULL,
+    event TEXT NOT NULL,
+    actor_id TEXT NOT NULL,
+    details TEXT,
+    created_at INTEGER NOT NULL DEFAULT (strftime('%s', 'now'))
+);
+
+CREATE INDEX idx_link_events_discord_id ON link_events(discord_id);
+CREATE INDEX idx_link_events_username ON link_events(LOWER(krunker_username));
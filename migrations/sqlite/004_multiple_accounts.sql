@@ -0,0 +1,19 @@
+-- Allow several Krunker accounts per Discord user, with exactly one marked primary.
+-- SQLite can't drop a UNIQUE constraint in place, so the table is rebuilt.
+CREATE TABLE users_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL,
+    discord_id TEXT NOT NULL,
+    country TEXT,
+    day_created INTEGER NOT NULL DEFAULT (strftime('%s', 'now')),
+    is_primary BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+INSERT INTO users_new (id, username, discord_id, country, day_created, is_primary)
+SELECT id, username, discord_id, country, day_created, TRUE FROM users;
+
+DROP TABLE users;
+ALTER TABLE users_new RENAME TO users;
+
+CREATE INDEX idx_discord_id ON users(discord_id);
+CREATE UNIQUE INDEX idx_users_primary ON users(discord_id) WHERE is_primary;
@@ -0,0 +1,19 @@
+-- Stable Krunker identity for each linked account, so renames can be followed
+ALTER TABLE users ADD COLUMN krunker_id TEXT;
+ALTER TABLE users ADD COLUMN last_resolved_at INTEGER;
+ALTER TABLE users ADD COLUMN stale_since INTEGER;
+
+CREATE INDEX idx_users_krunker_id ON users(krunker_id);
+
+CREATE TABLE username_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    discord_id TEXT NOT NULL,
+    krunker_id TEXT NOT NULL,
+    old_username TEXT NOT NULL,
+    new_username TEXT NOT NULL,
+    changed_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_username_history_user_id ON username_history(user_id);
+CREATE INDEX idx_username_history_krunker_id ON username_history(krunker_id);
@@ -0,0 +1,9 @@
+-- Per-user anti-abuse state for verification, kept across sessions
+CREATE TABLE verification_limits (
+    discord_id TEXT PRIMARY KEY,
+    last_started_at INTEGER NOT NULL DEFAULT 0,
+    failed_sessions INTEGER NOT NULL DEFAULT 0,
+    locked_until INTEGER
+);
+
+CREATE INDEX idx_verification_username ON verifications(LOWER(krunker_username));
@@ -0,0 +1,47 @@
+-- Ranked matches fetched from the Krunker API, kept for match history features
+CREATE TABLE matches (
+    match_id INTEGER PRIMARY KEY,
+    map TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    -- as the API reports it
+    played_at TEXT NOT NULL,
+    fetched_at INTEGER NOT NULL
+);
+
+CREATE TABLE match_participants (
+    match_id INTEGER NOT NULL,
+    player_name TEXT NOT NULL,
+    team INTEGER NOT NULL,
+    -- 1 for the winning team, like the API's mp_victory
+    victory INTEGER NOT NULL,
+    kills INTEGER NOT NULL,
+    deaths INTEGER NOT NULL,
+    assists INTEGER NOT NULL,
+    score INTEGER NOT NULL,
+    damage_done INTEGER NOT NULL,
+    objective_score INTEGER NOT NULL,
+    PRIMARY KEY (match_id, player_name)
+);
+
+CREATE INDEX idx_match_participants_player ON match_participants(LOWER(player_name));
+
+-- Where each guild wants match announcements posted
+CREATE TABLE match_feed_channels (
+    guild_id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Members who don't want their matches announced
+CREATE TABLE match_feed_optouts (
+    discord_id TEXT PRIMARY KEY,
+    created_at INTEGER NOT NULL
+);
+
+-- The newest match already seen for each linked account
+CREATE TABLE match_feed_cursors (
+    user_id INTEGER PRIMARY KEY,
+    last_match_id INTEGER NOT NULL,
+    checked_at INTEGER NOT NULL
+);
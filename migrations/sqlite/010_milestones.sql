@@ -0,0 +1,33 @@
+-- Copies of get_player for tracked players, a new row whenever something changed
+CREATE TABLE profile_snapshots (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    krunker_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    level INTEGER NOT NULL,
+    kr INTEGER NOT NULL,
+    games INTEGER NOT NULL,
+    kdr REAL NOT NULL,
+    clan TEXT,
+    taken_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_profile_snapshots_krunker_id ON profile_snapshots(krunker_id, taken_at);
+CREATE INDEX idx_profile_snapshots_username ON profile_snapshots(LOWER(username));
+
+-- Where each guild wants milestone announcements posted
+CREATE TABLE milestone_channels (
+    guild_id TEXT PRIMARY KEY,
+    channel_id TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    updated_at INTEGER NOT NULL
+);
+
+-- Milestones already announced, so a restart never announces one twice
+CREATE TABLE milestone_announcements (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    krunker_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    value INTEGER NOT NULL,
+    announced_at INTEGER NOT NULL,
+    UNIQUE (krunker_id, kind, value)
+);
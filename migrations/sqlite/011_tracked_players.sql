@@ -0,0 +1,9 @@
+-- Players looked up with &stats or &progress, snapshotted daily for a while after
+-- so &progress has history for them too
+CREATE TABLE tracked_players (
+    krunker_id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    looked_up_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_tracked_players_looked_up_at ON tracked_players(looked_up_at);
@@ -0,0 +1,14 @@
+-- Ratings worked out from the stored ranked matches. Rebuilt in full on every
+-- recompute, nothing here is edited by hand.
+CREATE TABLE player_ratings (
+    -- player_name lowercased, names are matched in any case
+    player_key TEXT PRIMARY KEY,
+    player_name TEXT NOT NULL,
+    rating REAL NOT NULL,
+    games INTEGER NOT NULL,
+    wins INTEGER NOT NULL,
+    peak REAL NOT NULL,
+    computed_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_player_ratings_rating ON player_ratings(rating);
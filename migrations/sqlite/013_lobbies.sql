@@ -0,0 +1,29 @@
+-- Lobbies made by &queue, with the teams as they stood when the players locked
+-- them in or the reroll buttons timed out
+CREATE TABLE lobbies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id TEXT,
+    channel_id TEXT NOT NULL,
+    started_by TEXT NOT NULL,
+    team_size INTEGER NOT NULL,
+    rerolls INTEGER NOT NULL,
+    -- 1 if the players locked the teams in, 0 if the buttons timed out
+    locked_in INTEGER NOT NULL,
+    -- team 1's total skill minus team 2's
+    skill_difference REAL NOT NULL,
+    created_at INTEGER NOT NULL
+);
+
+CREATE INDEX idx_lobbies_channel ON lobbies(channel_id, created_at);
+
+CREATE TABLE lobby_players (
+    lobby_id INTEGER NOT NULL,
+    discord_id TEXT NOT NULL,
+    username TEXT NOT NULL,
+    -- 1 or 2
+    team INTEGER NOT NULL,
+    skill REAL NOT NULL,
+    PRIMARY KEY (lobby_id, discord_id)
+);
+
+CREATE INDEX idx_lobby_players_discord_id ON lobby_players(discord_id);
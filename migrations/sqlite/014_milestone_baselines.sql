@@ -0,0 +1,10 @@
+-- The numbers the milestones job last compared each player against. Only that job
+-- moves them, so a snapshot taken by &stats or another feature in between can't
+-- swallow a milestone.
+CREATE TABLE milestone_baselines (
+    krunker_id TEXT PRIMARY KEY,
+    level INTEGER NOT NULL,
+    kr INTEGER NOT NULL,
+    games INTEGER NOT NULL,
+    checked_at INTEGER NOT NULL
+);
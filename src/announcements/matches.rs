@@ -0,0 +1,276 @@
+// the match feed: a card in each guild's feed channel when a linked member
+// finishes a ranked match
+
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, Http};
+use serenity::prelude::{RwLock, TypeMap};
+use sqlx::AnyPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::announce;
+use crate::config::Config;
+use crate::database::models::{AnnouncementFeed, User};
+use crate::database::queries;
+use crate::history::matches::fetch_match;
+use crate::jobs::Job;
+use crate::metrics::track_api;
+
+/// New matches announced per account per run. Anything older is skipped, so a
+/// member coming back from a long session doesn't flood the channel.
+const MAX_MATCHES_PER_PLAYER: usize = 5;
+/// Pause between players, to stay well inside the API rate limit
+const FEED_DELAY_MILLIS: u64 = 1000;
+
+/// What a match card shows, taken from the player's `pmr_matches` entry
+#[derive(Debug, Clone)]
+struct MatchCard {
+    user_id: i64,
+    discord_id: String,
+    username: String,
+    match_id: i64,
+    victory: bool,
+    kills: i64,
+    deaths: i64,
+    assists: i64,
+    score: i64,
+    accuracy: String,
+    map: Option<String>,
+}
+
+fn kda(card: &MatchCard) -> String {
+    let kdr = if card.deaths > 0 {
+        card.kills as f64 / card.deaths as f64
+    } else {
+        card.kills as f64
+    };
+    format!(
+        "{}/{}/{} ({:.2} K/D)",
+        card.kills, card.deaths, card.assists, kdr
+    )
+}
+
+fn render(card: &MatchCard, config: &Config) -> CreateEmbed {
+    let result = if card.victory {
+        "✅ Victory"
+    } else {
+        "❌ Defeat"
+    };
+
+    CreateEmbed::new()
+        .title(format!("{} - {}", result, card.username))
+        .description(format!("<@{}>", card.discord_id))
+        .field("K/D/A", kda(card), true)
+        .field("Score", card.score.to_string(), true)
+        .field("Accuracy", format!("{}%", card.accuracy), true)
+        .field("Map", card.map.as_deref().unwrap_or("Unknown"), true)
+        .footer(CreateEmbedFooter::new(format!(
+            "Full match: {}match {}",
+            config.bot.prefix, card.match_id
+        )))
+        .color(config.colors.stats)
+}
+
+/// Announces linked members' new ranked matches in each guild's feed channel
+pub struct MatchFeed {
+    pub krunker_api: Arc<KrunkerClient>,
+    pub http: Arc<Http>,
+    /// The client's shared data, read for the live config
+    pub data: Arc<RwLock<TypeMap>>,
+}
+
+impl MatchFeed {
+    /// Matches `user` finished since the last check, with the id the cursor moves to
+    /// once they're posted. The first check only records where the account is,
+    /// history isn't announced.
+    async fn new_matches(
+        &self,
+        pool: &AnyPool,
+        user: &User,
+    ) -> Result<Option<(Vec<MatchCard>, i64)>, Box<dyn std::error::Error + Send + Sync>> {
+        let data = track_api(
+            "get_player_matches",
+            self.krunker_api
+                .get_player_matches(&user.username, None, None),
+        )
+        .await?;
+        let matches = data.pmr_matches.unwrap_or_default();
+        let Some(newest) = matches.iter().map(|m| m.pm_match_id as i64).max() else {
+            return Ok(None);
+        };
+
+        let Some(last_seen) = queries::get_match_feed_cursor(pool, user.id).await? else {
+            let now = Utc::now().timestamp();
+            queries::set_match_feed_cursor(pool, user.id, newest, now).await?;
+            return Ok(None);
+        };
+        if newest <= last_seen {
+            return Ok(None);
+        }
+
+        let mut fresh: Vec<_> = matches
+            .iter()
+            .filter(|m| m.pm_match_id as i64 > last_seen)
+            .collect();
+        fresh.sort_by_key(|m| m.pm_match_id as i64);
+        let skipped = fresh.len().saturating_sub(MAX_MATCHES_PER_PLAYER);
+
+        let mut cards = Vec::new();
+        for pmatch in &fresh[skipped..] {
+            let match_id = pmatch.pm_match_id as i64;
+            // the card still goes out without the map if the match can't be fetched
+            let map = match fetch_match(pool, &self.krunker_api, match_id).await {
+                Ok((stored, _)) => Some(stored.map),
+                Err(e) => {
+                    tracing::warn!(match_id, "Failed to store match: {}", e);
+                    None
+                }
+            };
+            cards.push(MatchCard {
+                user_id: user.id,
+                discord_id: user.discord_id.clone(),
+                username: user.username.clone(),
+                match_id,
+                victory: pmatch.pm_victory == 1,
+                kills: pmatch.pm_kills as i64,
+                deaths: pmatch.pm_deaths as i64,
+                assists: pmatch.pm_assists as i64,
+                score: pmatch.pm_score as i64,
+                accuracy: pmatch.pm_accuracy.to_string(),
+                map,
+            });
+        }
+
+        Ok(Some((cards, newest)))
+    }
+}
+
+#[async_trait]
+impl Job for MatchFeed {
+    fn name(&self) -> &'static str {
+        "match_feed"
+    }
+
+    fn description(&self) -> &'static str {
+        "Announce linked members' new ranked matches"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let channels = queries::get_announcement_channels(pool, AnnouncementFeed::Matches).await?;
+        if channels.is_empty() {
+            return Ok("no feed channels set".to_string());
+        }
+
+        let users = queries::get_announced_users(pool).await?;
+        let mut cards = Vec::new();
+        // cursors only move once the cards are out everywhere, until then each run
+        // retries the channels that missed out
+        let mut cursors = Vec::new();
+        for user in &users {
+            match self.new_matches(pool, user).await {
+                Ok(Some((new, newest))) => {
+                    cards.extend(new);
+                    cursors.push((user, newest));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::warn!(username = %user.username, "Failed to check matches: {}", e);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(FEED_DELAY_MILLIS)).await;
+        }
+        if cards.is_empty() {
+            return Ok(format!("checked {} accounts, no new matches", users.len()));
+        }
+
+        let config = crate::bot::state::config_from(&self.data).await;
+        let rendered: Vec<_> = cards
+            .iter()
+            .map(|card| (card.discord_id.clone(), render(card, &config)))
+            .collect();
+
+        let mut posted_before = HashSet::new();
+        for (user, _) in &cursors {
+            for (match_id, channel_id) in queries::get_match_feed_posts(pool, user.id).await? {
+                let index = cards
+                    .iter()
+                    .position(|card| card.user_id == user.id && card.match_id == match_id);
+                if let Some(index) = index {
+                    posted_before.insert((channel_id, index));
+                }
+            }
+        }
+        let announced = announce(
+            &self.http,
+            &channels,
+            &rendered,
+            "ranked matches",
+            &posted_before,
+        )
+        .await;
+
+        let now = Utc::now().timestamp();
+        for (channel_id, index) in &announced.delivered {
+            let card = &cards[*index];
+            queries::record_match_feed_post(pool, card.user_id, card.match_id, channel_id, now)
+                .await?;
+        }
+
+        let mut held = 0;
+        for (user, newest) in cursors {
+            let missed = announced
+                .failed
+                .iter()
+                .any(|&index| cards[index].user_id == user.id);
+            if missed {
+                held += 1;
+                continue;
+            }
+            queries::set_match_feed_cursor(pool, user.id, newest, now).await?;
+        }
+
+        Ok(format!(
+            "checked {} accounts, {} new matches, posted {} cards, {} accounts held back",
+            users.len(),
+            cards.len(),
+            announced.posted,
+            held
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kda() {
+        let mut card = MatchCard {
+            user_id: 1,
+            discord_id: "123".to_string(),
+            username: "Player".to_string(),
+            match_id: 1,
+            victory: true,
+            kills: 25,
+            deaths: 10,
+            assists: 4,
+            score: 3200,
+            accuracy: "31".to_string(),
+            map: None,
+        };
+        assert_eq!(kda(&card), "25/10/4 (2.50 K/D)");
+
+        card.deaths = 0;
+        assert_eq!(kda(&card), "25/0/4 (25.00 K/D)");
+    }
+}
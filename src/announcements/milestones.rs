@@ -0,0 +1,285 @@
+// congratulations when a linked member reaches a level, KR or games milestone
+
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, Http};
+use serenity::prelude::{RwLock, TypeMap};
+use sqlx::AnyPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::announce;
+use crate::config::{Config, MilestonesConfig};
+use crate::database::models::{
+    AnnouncementFeed, MilestoneBaseline, MilestoneKind, ProfileSnapshot, User,
+};
+use crate::database::queries;
+use crate::history::snapshots;
+use crate::jobs::Job;
+
+/// Pause between players, to stay well inside the API rate limit
+const MILESTONE_DELAY_MILLIS: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Milestone {
+    pub kind: MilestoneKind,
+    pub value: i64,
+}
+
+/// The highest milestone of each kind passed since the baseline. Only the
+/// highest, so a player who jumps several levels gets one announcement.
+pub fn crossed_milestones(
+    before: &MilestoneBaseline,
+    after: &ProfileSnapshot,
+    config: &MilestonesConfig,
+) -> Vec<Milestone> {
+    let mut crossed = Vec::new();
+
+    let step = config.level_step.max(1);
+    let level = after.level / step * step;
+    if level > before.level {
+        crossed.push(Milestone {
+            kind: MilestoneKind::Level,
+            value: level,
+        });
+    }
+
+    let thresholds = [
+        (MilestoneKind::Kr, &config.kr, before.kr, after.kr),
+        (
+            MilestoneKind::Games,
+            &config.games,
+            before.games,
+            after.games,
+        ),
+    ];
+    for (kind, values, before, after) in thresholds {
+        let passed = values
+            .iter()
+            .copied()
+            .filter(|&value| before < value && after >= value)
+            .max();
+        if let Some(value) = passed {
+            crossed.push(Milestone { kind, value });
+        }
+    }
+
+    crossed
+}
+
+/// 1234567 as "1,234,567"
+fn thousands(value: i64) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    if value < 0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+fn headline(username: &str, milestone: &Milestone) -> String {
+    match milestone.kind {
+        MilestoneKind::Level => format!("{} reached level {}!", username, milestone.value),
+        MilestoneKind::Kr => format!("{} passed {} KR!", username, thousands(milestone.value)),
+        MilestoneKind::Games => {
+            format!("{} played {} games!", username, thousands(milestone.value))
+        }
+    }
+}
+
+fn render(
+    user: &User,
+    snapshot: &ProfileSnapshot,
+    milestone: &Milestone,
+    config: &Config,
+) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(format!("🎉 {}", headline(&snapshot.username, milestone)))
+        .description(format!("Congratulations <@{}>!", user.discord_id))
+        .field("Level", snapshot.level.to_string(), true)
+        .field("KR", thousands(snapshot.kr), true)
+        .field("Games", thousands(snapshot.games), true)
+        .field("K/D Ratio", format!("{:.2}", snapshot.kdr), true)
+        .color(config.colors.info)
+}
+
+/// Snapshots linked members' profiles and announces milestones they pass
+pub struct Milestones {
+    pub krunker_api: Arc<KrunkerClient>,
+    pub http: Arc<Http>,
+    /// The client's shared data, read for the live config
+    pub data: Arc<RwLock<TypeMap>>,
+}
+
+impl Milestones {
+    /// Takes a fresh snapshot of `user` and returns the milestones not announced yet.
+    /// Compared against this job's own baseline rather than the latest snapshot,
+    /// which other features take too. A player's first check only sets the baseline.
+    async fn check(
+        &self,
+        pool: &AnyPool,
+        user: &User,
+        config: &MilestonesConfig,
+    ) -> Result<Vec<(ProfileSnapshot, Milestone)>, Box<dyn std::error::Error + Send + Sync>> {
+        let (snapshot, _) = snapshots::take(pool, &self.krunker_api, &user.username).await?;
+
+        let mut fresh = Vec::new();
+        if let Some(baseline) = queries::get_milestone_baseline(pool, &snapshot.krunker_id).await? {
+            for milestone in crossed_milestones(&baseline, &snapshot, config) {
+                if queries::record_milestone(
+                    pool,
+                    &snapshot.krunker_id,
+                    milestone.kind,
+                    milestone.value,
+                    snapshot.taken_at,
+                )
+                .await?
+                {
+                    fresh.push((snapshot.clone(), milestone));
+                }
+            }
+        }
+
+        queries::set_milestone_baseline(pool, &MilestoneBaseline::of(&snapshot)).await?;
+        Ok(fresh)
+    }
+}
+
+#[async_trait]
+impl Job for Milestones {
+    fn name(&self) -> &'static str {
+        "milestones"
+    }
+
+    fn description(&self) -> &'static str {
+        "Snapshot linked profiles and announce level, KR and games milestones"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(30 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let config = crate::bot::state::config_from(&self.data).await;
+        let channels =
+            queries::get_announcement_channels(pool, AnnouncementFeed::Milestones).await?;
+
+        // snapshots are kept even with nowhere to announce, later features read them
+        let users = queries::get_announced_users(pool).await?;
+        let mut cards = Vec::new();
+        for user in &users {
+            match self.check(pool, user, &config.milestones).await {
+                Ok(reached) => {
+                    for (snapshot, milestone) in reached {
+                        let card = render(user, &snapshot, &milestone, &config);
+                        cards.push((user.discord_id.clone(), card));
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(username = %user.username, "Failed to check milestones: {}", e);
+                }
+            }
+            tokio::time::sleep(Duration::from_millis(MILESTONE_DELAY_MILLIS)).await;
+        }
+
+        let announced =
+            announce(&self.http, &channels, &cards, "milestones", &HashSet::new()).await;
+        Ok(format!(
+            "checked {} accounts, {} milestones, posted {} cards",
+            users.len(),
+            cards.len(),
+            announced.posted
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn baseline(level: i64, kr: i64, games: i64) -> MilestoneBaseline {
+        MilestoneBaseline::of(&snapshot(level, kr, games))
+    }
+
+    fn snapshot(level: i64, kr: i64, games: i64) -> ProfileSnapshot {
+        ProfileSnapshot {
+            id: 0,
+            krunker_id: "k1".to_string(),
+            username: "Player".to_string(),
+            level,
+            kr,
+            games,
+            kdr: 1.5,
+            clan: None,
+            taken_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_crossed_milestones() {
+        let config = MilestonesConfig {
+            level_step: 5,
+            kr: vec![10_000, 50_000, 100_000],
+            games: vec![100, 500],
+        };
+
+        // nothing passed
+        assert!(
+            crossed_milestones(&baseline(41, 9_000, 90), &snapshot(44, 9_999, 99), &config)
+                .is_empty()
+        );
+
+        let crossed = crossed_milestones(
+            &baseline(44, 9_000, 99),
+            &snapshot(51, 60_000, 100),
+            &config,
+        );
+        assert_eq!(
+            crossed,
+            vec![
+                Milestone {
+                    kind: MilestoneKind::Level,
+                    value: 50
+                },
+                Milestone {
+                    kind: MilestoneKind::Kr,
+                    value: 50_000
+                },
+                Milestone {
+                    kind: MilestoneKind::Games,
+                    value: 100
+                },
+            ]
+        );
+
+        // dropping below a threshold and coming back isn't a new milestone here,
+        // record_milestone keeps it from being announced twice
+        assert!(
+            crossed_milestones(
+                &baseline(51, 60_000, 100),
+                &snapshot(51, 40_000, 100),
+                &config
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_thousands() {
+        assert_eq!(thousands(0), "0");
+        assert_eq!(thousands(999), "999");
+        assert_eq!(thousands(1_000), "1,000");
+        assert_eq!(thousands(1_234_567), "1,234,567");
+        assert_eq!(thousands(-25_000), "-25,000");
+    }
+}
@@ -0,0 +1,153 @@
+// posts the bot makes on its own, into the channels guilds picked for them
+
+use serenity::all::{ChannelId, CreateEmbed, CreateMessage, GuildId, Http, UserId};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::models::AnnouncementChannel;
+
+pub mod matches;
+pub mod milestones;
+
+/// Discord's limit on embeds in one message
+const EMBEDS_PER_MESSAGE: usize = 10;
+/// Cards posted to one channel per run, the rest are summed up in a single line
+const MAX_CARDS_PER_RUN: usize = 30;
+
+/// Whether `user_id` is in `guild_id`. Lookup failures count as not a member, so
+/// nothing is announced to a guild the player isn't in.
+pub async fn is_member(http: &Http, guild_id: GuildId, user_id: UserId) -> bool {
+    match guild_id.member(http, user_id).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::debug!(guild = %guild_id, user = %user_id, "Member lookup failed: {}", e);
+            false
+        }
+    }
+}
+
+/// How far `post_cards` got before Discord refused a message
+#[derive(Debug)]
+pub struct PostFailure {
+    /// Cards at the front of the list that did go out
+    pub delivered: usize,
+    pub error: serenity::Error,
+}
+
+/// Posts `cards` to a channel, several to a message. Past `MAX_CARDS_PER_RUN` the
+/// remainder is only counted, e.g. "...and 4 more ranked matches". Returns how many
+/// cards were shown, or how many went out before a message failed.
+pub async fn post_cards(
+    http: &Http,
+    channel_id: ChannelId,
+    mut cards: Vec<CreateEmbed>,
+    overflow: &str,
+) -> Result<usize, PostFailure> {
+    let extra = cards.len().saturating_sub(MAX_CARDS_PER_RUN);
+    cards.truncate(MAX_CARDS_PER_RUN);
+
+    let mut delivered = 0;
+    for batch in cards.chunks(EMBEDS_PER_MESSAGE) {
+        channel_id
+            .send_message(http, CreateMessage::new().embeds(batch.to_vec()))
+            .await
+            .map_err(|error| PostFailure { delivered, error })?;
+        delivered += batch.len();
+    }
+
+    if extra > 0 {
+        channel_id
+            .say(http, format!("...and {} more {}", extra, overflow))
+            .await
+            .map_err(|error| PostFailure { delivered, error })?;
+    }
+    Ok(cards.len())
+}
+
+/// What came of an `announce` call
+#[derive(Debug, Default)]
+pub struct Announced {
+    /// Cards shown, summed over every channel
+    pub posted: usize,
+    /// (channel_id, card index) for each card that went out, or was counted in an
+    /// overflow line
+    pub delivered: Vec<(String, usize)>,
+    /// Indexes of cards that didn't go out to at least one channel
+    pub failed: HashSet<usize>,
+}
+
+/// Posts each card, tagged with the Discord id it's about, to every channel whose
+/// guild that member is in, leaving out the (channel_id, card index) pairs in `skip`.
+/// Failures are logged per channel and reported per card, so callers can retry
+/// just the channels that missed out.
+pub async fn announce(
+    http: &Http,
+    channels: &[AnnouncementChannel],
+    cards: &[(String, CreateEmbed)],
+    overflow: &str,
+    skip: &HashSet<(String, usize)>,
+) -> Announced {
+    let mut membership: HashMap<(u64, u64), bool> = HashMap::new();
+    let mut announced = Announced::default();
+
+    for channel in channels {
+        let (Ok(guild_id), Ok(channel_id)) = (
+            channel.guild_id.parse::<u64>(),
+            channel.channel_id.parse::<u64>(),
+        ) else {
+            continue;
+        };
+
+        let mut guild_cards = Vec::new();
+        let mut indexes = Vec::new();
+        for (index, (discord_id, card)) in cards.iter().enumerate() {
+            if skip.contains(&(channel.channel_id.clone(), index)) {
+                continue;
+            }
+            let Ok(user_id) = discord_id.parse::<u64>() else {
+                continue;
+            };
+            let member = match membership.get(&(guild_id, user_id)) {
+                Some(&member) => member,
+                None => {
+                    let member =
+                        is_member(http, GuildId::new(guild_id), UserId::new(user_id)).await;
+                    membership.insert((guild_id, user_id), member);
+                    member
+                }
+            };
+            if member {
+                guild_cards.push(card.clone());
+                indexes.push(index);
+            }
+        }
+        if guild_cards.is_empty() {
+            continue;
+        }
+
+        let delivered =
+            match post_cards(http, ChannelId::new(channel_id), guild_cards, overflow).await {
+                Ok(count) => {
+                    announced.posted += count;
+                    indexes.len()
+                }
+                Err(failure) => {
+                    tracing::warn!(
+                        guild = guild_id,
+                        "Failed to post {}: {}",
+                        overflow,
+                        failure.error
+                    );
+                    announced.posted += failure.delivered;
+                    failure.delivered
+                }
+            };
+        let (sent, missed) = indexes.split_at(delivered);
+        announced.delivered.extend(
+            sent.iter()
+                .map(|&index| (channel.channel_id.clone(), index)),
+        );
+        announced.failed.extend(missed);
+    }
+
+    announced
+}
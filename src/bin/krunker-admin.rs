@@ -0,0 +1,388 @@
+// maintenance tasks for bot.db, run alongside or instead of the bot
+
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use sqlx::{AnyPool, Connection};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use krunker_bot::config::Config;
+use krunker_bot::database::models::{LinkEventKind, SYSTEM_ACTOR, User};
+use krunker_bot::database::{self, queries};
+use krunker_bot::history::ratings;
+use krunker_bot::verification::flow;
+
+type Error = Box<dyn std::error::Error>;
+
+#[derive(Parser)]
+#[command(name = "krunker-admin", about = "Manage the bot's database")]
+struct Cli {
+    /// Database to use instead of database.url from the config
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply any pending migrations
+    Migrate,
+    /// List linked accounts, oldest first
+    Users {
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+        #[arg(long, default_value_t = 0)]
+        offset: i64,
+    },
+    /// Find linked accounts by part of the username or an exact Discord id
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Write every link out
+    Export {
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// File to write to instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Link accounts from an export. Usernames that are already linked are skipped.
+    Import {
+        path: PathBuf,
+        /// Guessed from the file extension if left out
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Report what would be imported without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Mark overdue pending verifications as expired
+    PurgeVerifications,
+    /// Rebuild every player rating from the stored ranked matches
+    RecomputeRatings,
+    /// Copy an SQLite database to a new file, safe while the bot is running
+    Backup { path: PathBuf },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, ValueEnum)]
+enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// One linked account as it appears in exports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LinkRecord {
+    discord_id: String,
+    username: String,
+    country: Option<String>,
+    is_primary: bool,
+    linked_at: i64,
+}
+
+impl From<User> for LinkRecord {
+    fn from(user: User) -> Self {
+        Self {
+            discord_id: user.discord_id,
+            username: user.username,
+            country: user.country,
+            is_primary: user.is_primary,
+            linked_at: user.day_created,
+        }
+    }
+}
+
+fn write_records(format: Format, records: &[LinkRecord], out: impl Write) -> Result<(), Error> {
+    match format {
+        Format::Json => serde_json::to_writer_pretty(out, records)?,
+        Format::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for record in records {
+                writer.serialize(record)?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+fn read_records(format: Format, input: impl Read) -> Result<Vec<LinkRecord>, Error> {
+    let records = match format {
+        Format::Json => serde_json::from_reader(input)?,
+        Format::Csv => csv::Reader::from_reader(input)
+            .deserialize()
+            .collect::<Result<_, _>>()?,
+    };
+    Ok(records)
+}
+
+fn print_users(users: &[User]) {
+    if users.is_empty() {
+        println!("No linked accounts");
+        return;
+    }
+    println!(
+        "{:>6}  {:<20}  {:<24}  {:<7}  {}",
+        "id", "discord_id", "username", "primary", "linked_at"
+    );
+    for user in users {
+        println!(
+            "{:>6}  {:<20}  {:<24}  {:<7}  {}{}",
+            user.id,
+            user.discord_id,
+            user.username,
+            if user.is_primary { "yes" } else { "" },
+            user.day_created,
+            if user.stale_since.is_some() {
+                "  (stale)"
+            } else {
+                ""
+            }
+        );
+    }
+}
+
+async fn import(pool: &AnyPool, records: Vec<LinkRecord>, dry_run: bool) -> Result<(), Error> {
+    let mut imported = 0;
+    let mut skipped = Vec::new();
+    let mut tx = pool.begin().await?;
+
+    for record in &records {
+        if record.discord_id.is_empty() || record.username.is_empty() {
+            return Err(format!("record is missing a discord_id or username: {:?}", record).into());
+        }
+
+        // a savepoint per record, since on Postgres a failed statement aborts the
+        // whole transaction and a skipped record would take the rest with it
+        let mut row = tx.begin().await?;
+        match queries::import_user(
+            &mut *row,
+            &record.username,
+            &record.discord_id,
+            record.country.as_deref(),
+            record.is_primary,
+            record.linked_at,
+        )
+        .await
+        {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                row.rollback().await?;
+                skipped.push(record.username.as_str());
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        }
+        queries::record_link_event(
+            &mut *row,
+            &record.discord_id,
+            &record.username,
+            LinkEventKind::Imported,
+            SYSTEM_ACTOR,
+            Some("krunker-admin import"),
+        )
+        .await?;
+        row.commit().await?;
+        imported += 1;
+    }
+
+    if dry_run {
+        tx.rollback().await?;
+        println!("Dry run, would import {} links", imported);
+    } else {
+        tx.commit().await?;
+        println!("Imported {} links", imported);
+    }
+    if !skipped.is_empty() {
+        println!(
+            "Skipped {} already linked: {}",
+            skipped.len(),
+            skipped.join(", ")
+        );
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+
+    let url = match cli.database_url {
+        Some(url) => url,
+        None => Config::load()?.database.url,
+    };
+    let pool = database::connect(&url).await?;
+
+    match cli.command {
+        Command::Migrate => {
+            database::migrate(&pool).await?;
+            println!("Migrations applied to {}", url);
+        }
+        Command::Users { limit, offset } => {
+            print_users(&queries::list_users(&pool, limit, offset).await?);
+        }
+        Command::Search { query, limit } => {
+            print_users(&queries::search_users(&pool, &query, limit).await?);
+        }
+        Command::Export { format, output } => {
+            let records: Vec<LinkRecord> = queries::list_users(&pool, i64::MAX, 0)
+                .await?
+                .into_iter()
+                .map(LinkRecord::from)
+                .collect();
+            match output {
+                Some(path) => {
+                    write_records(format, &records, std::fs::File::create(&path)?)?;
+                    eprintln!("Exported {} links to {}", records.len(), path.display());
+                }
+                None => write_records(format, &records, std::io::stdout().lock())?,
+            }
+        }
+        Command::Import {
+            path,
+            format,
+            dry_run,
+        } => {
+            let Some(format) = format.or_else(|| Format::from_path(&path)) else {
+                return Err("can't tell the format from the file name, pass --format".into());
+            };
+            let records = read_records(format, std::fs::File::open(&path)?)?;
+            import(&pool, records, dry_run).await?;
+        }
+        Command::PurgeVerifications => {
+            let now = chrono::Utc::now().timestamp();
+            let settings = Config::load()?.verification_settings();
+            let expired = flow::expire_stale_verifications(&pool, settings, now).await?;
+            println!("Expired {} verifications", expired);
+        }
+        Command::RecomputeRatings => {
+            let now = chrono::Utc::now().timestamp();
+            let (players, rated) = ratings::recompute(&pool, now).await?;
+            println!("Rated {} players from {} matches", players, rated);
+        }
+        Command::Backup { path } => {
+            if path.exists() {
+                return Err(format!("{} already exists", path.display()).into());
+            }
+            database::backup(&pool, &path.to_string_lossy()).await?;
+            println!("Backed up to {}", path.display());
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<LinkRecord> {
+        vec![
+            LinkRecord {
+                discord_id: "111".to_string(),
+                username: "Alt".to_string(),
+                country: None,
+                is_primary: false,
+                linked_at: 1_700_000_100,
+            },
+            LinkRecord {
+                discord_id: "111".to_string(),
+                username: "Main".to_string(),
+                country: Some("US".to_string()),
+                is_primary: true,
+                linked_at: 1_700_000_000,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        for format in [Format::Json, Format::Csv] {
+            let mut buffer = Vec::new();
+            write_records(format, &records(), &mut buffer).unwrap();
+            let read = read_records(format, buffer.as_slice()).unwrap();
+            assert_eq!(read, records(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("links.csv")), Some(Format::Csv));
+        assert_eq!(
+            Format::from_path(Path::new("links.json")),
+            Some(Format::Json)
+        );
+        assert_eq!(Format::from_path(Path::new("links")), None);
+    }
+
+    #[tokio::test]
+    async fn test_import_skips_linked_and_keeps_primary() {
+        let pool = database::connect("sqlite::memory:").await.unwrap();
+        database::migrate(&pool).await.unwrap();
+        queries::create_user(&pool, "Taken", "222", None)
+            .await
+            .unwrap();
+
+        let mut records = records();
+        records.push(LinkRecord {
+            discord_id: "333".to_string(),
+            username: "taken".to_string(),
+            country: None,
+            is_primary: true,
+            linked_at: 0,
+        });
+        import(&pool, records, false).await.unwrap();
+
+        let accounts = queries::get_users_by_discord_id(&pool, "111")
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].username, "Main");
+        assert!(accounts[0].is_primary);
+        assert_eq!(accounts[0].day_created, 1_700_000_000);
+        assert!(!accounts[1].is_primary);
+        assert_eq!(accounts[1].day_created, 1_700_000_100);
+        assert!(
+            queries::get_users_by_discord_id(&pool, "333")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let events = queries::get_link_events_by_discord_id(&pool, "111", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "imported");
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_writes_nothing() {
+        let pool = database::connect("sqlite::memory:").await.unwrap();
+        database::migrate(&pool).await.unwrap();
+
+        import(&pool, records(), true).await.unwrap();
+        assert!(
+            queries::list_users(&pool, i64::MAX, 0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
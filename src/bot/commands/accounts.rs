@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use serenity::utils::parse_user_mention;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::database::queries;
+
+pub struct Accounts;
+
+#[async_trait]
+impl KrunkerCommand for Accounts {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "accounts",
+            description: "List the Krunker accounts linked to you or another user",
+            usage: "&accounts [@user]",
+            aliases: &["alts"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let user_id = args
+            .first()
+            .and_then(|a| parse_user_mention(a))
+            .unwrap_or(msg.author.id);
+        let discord_id = user_id.to_string();
+
+        let accounts = queries::get_users_by_discord_id(pool, &discord_id).await?;
+        if accounts.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "No Krunker accounts linked. Use `&link <username>` to add one.",
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let lines = accounts
+            .iter()
+            .map(|account| {
+                let mut line = format!(
+                    "{} **{}** - linked <t:{}:d>",
+                    if account.is_primary { "⭐" } else { "•" },
+                    account.username,
+                    account.day_created
+                );
+                if account.stale_since.is_some() {
+                    line.push_str(" ⚠️ no longer resolves, renamed? Try `&renamed <new name>`");
+                }
+                line
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let embed = CreateEmbed::new()
+            .title("Linked Krunker Accounts")
+            .description(format!("<@{}>\n\n{}", discord_id, lines))
+            .color(colors.info)
+            .footer(serenity::all::CreateEmbedFooter::new(
+                "⭐ = primary, used when commands are run without a username",
+            ));
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{OWNER_ONLY, is_bot_owner};
+use crate::database::queries;
+
+/// How many commands the top list shows
+const TOP_COMMANDS: i64 = 10;
+
+/// The windows &botstats accepts, in seconds
+const WINDOWS: &[(&str, i64)] = &[
+    ("1h", 60 * 60),
+    ("24h", 24 * 60 * 60),
+    ("7d", 7 * 24 * 60 * 60),
+    ("30d", 30 * 24 * 60 * 60),
+];
+
+pub struct BotStats;
+
+fn error_rate(errors: i64, uses: i64) -> String {
+    if uses == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", errors as f64 * 100.0 / uses as f64)
+}
+
+#[async_trait]
+impl KrunkerCommand for BotStats {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "botstats",
+            description: "(Owner) Show command usage, error rates and latency",
+            usage: "&botstats [1h|24h|7d|30d]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        if !is_bot_owner(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, OWNER_ONLY).await?;
+            return Ok(());
+        }
+
+        let window = args.first().copied().unwrap_or("24h");
+        let Some(&(label, seconds)) = WINDOWS.iter().find(|(label, _)| *label == window) else {
+            msg.channel_id
+                .say(&ctx.http, "Usage: &botstats [1h|24h|7d|30d]")
+                .await?;
+            return Ok(());
+        };
+        let since = Utc::now().timestamp() - seconds;
+
+        let summary = queries::get_usage_summary(pool, since).await?;
+        let commands = queries::get_command_stats(pool, since, TOP_COMMANDS).await?;
+        let (linked_users, linked_accounts) = queries::count_linked_users(pool).await?;
+        let new_links = queries::count_new_links(pool, since).await?;
+
+        let top = if commands.is_empty() {
+            "No commands used".to_string()
+        } else {
+            commands
+                .iter()
+                .map(|stats| {
+                    format!(
+                        "`{}` {} uses, {} errors, p95 {}ms",
+                        stats.command,
+                        stats.uses,
+                        error_rate(stats.errors, stats.uses),
+                        stats.p95_latency_ms
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        let embed = CreateEmbed::new()
+            .title(format!("Bot Stats (last {})", label))
+            .field("Commands", summary.uses.to_string(), true)
+            .field("Error Rate", error_rate(summary.errors, summary.uses), true)
+            .field(
+                "p95 Latency",
+                summary
+                    .p95_latency_ms
+                    .map(|ms| format!("{}ms", ms))
+                    .unwrap_or_else(|| "-".to_string()),
+                true,
+            )
+            .field("Active Guilds", summary.active_guilds.to_string(), true)
+            .field("Active Users", summary.active_users.to_string(), true)
+            .field(
+                "Linked Users",
+                format!(
+                    "{} ({} accounts, {} new)",
+                    linked_users, linked_accounts, new_links
+                ),
+                true,
+            )
+            .field("Top Commands", top, false)
+            .color(colors.info);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+
+        Ok(())
+    }
+}
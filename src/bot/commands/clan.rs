@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage, UserId};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::announcements::is_member;
+use crate::database::models::ClanMember;
+use crate::database::queries;
+use crate::history::clans::{self, ClanRanking};
+
+const USAGE: &str = "Usage: &clan <tag> [level|kr|kdr]";
+/// Members listed by name, the rest are only counted
+const LISTED_MEMBERS: usize = 20;
+/// Places shown on the server leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+
+pub struct Clan;
+
+fn member_line(member: &ClanMember) -> String {
+    format!(
+        "{} - Lvl {}, {} KR, {:.2} K/D",
+        member.username, member.level, member.kr, member.kdr
+    )
+}
+
+#[async_trait]
+impl KrunkerCommand for Clan {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "clan",
+            description: "Show a clan's known players, their averages and a server leaderboard",
+            usage: "&clan <tag> [level|kr|kdr]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (tag, ranking) = match args.as_slice() {
+            [tag] => (*tag, Some(ClanRanking::Level)),
+            [tag, ranking] => (*tag, ClanRanking::parse(ranking)),
+            _ => ("", None),
+        };
+        // accept the tag as the game shows it, [TAG]
+        let tag = tag.trim_start_matches('[').trim_end_matches(']');
+        let Some(ranking) = ranking.filter(|_| !tag.is_empty()) else {
+            msg.channel_id.say(&ctx.http, USAGE).await?;
+            return Ok(());
+        };
+
+        let mut members = queries::get_clan_members(pool, tag).await?;
+        if members.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!(
+                        "No known players in [{}]. Players are known once they're linked or turn up in a stored ranked match, and their clan is read from their last profile snapshot.",
+                        tag
+                    ),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let summary = clans::summarize(&members);
+        let win_rate = match summary.win_rate() {
+            Some(rate) => format!("{:.1}% over {} games", rate * 100.0, summary.ranked_games),
+            None => "No stored ranked games".to_string(),
+        };
+
+        clans::rank(&mut members, ranking);
+        let mut listed: Vec<_> = members
+            .iter()
+            .take(LISTED_MEMBERS)
+            .map(member_line)
+            .collect();
+        if members.len() > LISTED_MEMBERS {
+            listed.push(format!("...and {} more", members.len() - LISTED_MEMBERS));
+        }
+
+        let config = crate::bot::state::config(ctx).await;
+        let mut embed = CreateEmbed::new()
+            .title(format!("[{}]", tag))
+            .description(format!(
+                "{} known players, {} linked",
+                summary.members, summary.linked
+            ))
+            .field(
+                "Average Level",
+                format!("{:.1}", summary.average_level),
+                true,
+            )
+            .field("Average K/D", format!("{:.2}", summary.average_kdr), true)
+            .field("Ranked Win Rate", win_rate, true)
+            .field(
+                format!("Members by {}", ranking.as_str()),
+                listed.join("\n"),
+                false,
+            )
+            .footer(CreateEmbedFooter::new(
+                "From stored profile snapshots, &stats refreshes a player",
+            ))
+            .color(config.colors.leaderboard);
+
+        // the server leaderboard only has linked players who are in this server
+        if let Some(guild_id) = msg.guild_id {
+            let mut board = Vec::new();
+            for member in &members {
+                if board.len() == LEADERBOARD_SIZE {
+                    break;
+                }
+                let Some(user_id) = member
+                    .discord_id
+                    .as_deref()
+                    .and_then(|id| id.parse::<u64>().ok())
+                else {
+                    continue;
+                };
+                if is_member(&ctx.http, guild_id, UserId::new(user_id)).await {
+                    board.push(format!(
+                        "{}. <@{}> {}",
+                        board.len() + 1,
+                        user_id,
+                        member_line(member)
+                    ));
+                }
+            }
+            let board = if board.is_empty() {
+                "No linked members in this server".to_string()
+            } else {
+                board.join("\n")
+            };
+            embed = embed.field("Server Leaderboard", board, false);
+        }
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
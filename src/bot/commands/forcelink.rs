@@ -0,0 +1,139 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use serenity::utils::parse_user_mention;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::confirm::confirm;
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::LinkEventKind;
+use crate::database::queries;
+
+pub struct ForceLink;
+
+#[async_trait]
+impl KrunkerCommand for ForceLink {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "forcelink",
+            description: "(Moderators) Add a Krunker account to a Discord user without verification",
+            usage: "&forcelink <@user> <krunker_name>",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !is_moderator(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+            return Ok(());
+        }
+
+        let (user_id, username) = match (
+            args.first().and_then(|a| parse_user_mention(a)),
+            args.get(1),
+        ) {
+            (Some(id), Some(name)) if !name.is_empty() => (id, *name),
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &forcelink <@user> <krunker_name>")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let discord_id = user_id.to_string();
+        let moderator_id = msg.author.id.to_string();
+
+        let owner = queries::get_user_by_username(pool, username).await?;
+
+        if let Some(owner) = &owner {
+            if owner.discord_id == discord_id {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("<@{}> is already linked to **{}**.", discord_id, username),
+                    )
+                    .await?;
+                return Ok(());
+            }
+
+            // Taking the account away from someone else is destructive, so ask first
+            let prompt = format!(
+                "**{}** is currently linked to <@{}>. Move it to <@{}>?",
+                owner.username, owner.discord_id, discord_id
+            );
+            if !confirm(ctx, msg, &prompt).await? {
+                return Ok(());
+            }
+        }
+
+        // the unlink and the new link go in together, a failed link leaves the old one
+        let mut tx = pool.begin().await?;
+        if let Some(owner) = &owner {
+            queries::delete_user_by_username(&mut tx, &owner.username).await?;
+            queries::record_link_event(
+                &mut *tx,
+                &owner.discord_id,
+                &owner.username,
+                LinkEventKind::ForceUnlinked,
+                &moderator_id,
+                Some(&format!("moved to {} by forcelink", discord_id)),
+            )
+            .await?;
+        }
+
+        // someone may have finished verifying this name while we were waiting on the prompt
+        match queries::create_user(&mut *tx, username, &discord_id, None).await {
+            Ok(_) => {}
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!(
+                            "**{}** was linked by someone else in the meantime, try again.",
+                            username
+                        ),
+                    )
+                    .await?;
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        }
+        queries::record_link_event(
+            &mut *tx,
+            &discord_id,
+            username,
+            LinkEventKind::ForceLinked,
+            &moderator_id,
+            None,
+        )
+        .await?;
+        tx.commit().await?;
+
+        tracing::info!(
+            moderator = %msg.author.id,
+            discord_id = %discord_id,
+            username = %username,
+            "Force linked account"
+        );
+
+        msg.channel_id
+            .say(
+                &ctx.http,
+                format!("✅ <@{}> is now linked to **{}**.", discord_id, username),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
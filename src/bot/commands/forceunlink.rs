@@ -0,0 +1,134 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use serenity::utils::parse_user_mention;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::confirm::confirm;
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::LinkEventKind;
+use crate::database::queries;
+
+pub struct ForceUnlink;
+
+#[async_trait]
+impl KrunkerCommand for ForceUnlink {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "forceunlink",
+            description: "(Moderators) Remove one or all of a Discord user's Krunker links",
+            usage: "&forceunlink <@user> [krunker_name]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !is_moderator(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+            return Ok(());
+        }
+
+        let user_id = match args.first().and_then(|a| parse_user_mention(a)) {
+            Some(id) => id,
+            None => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &forceunlink <@user> [krunker_name]")
+                    .await?;
+                return Ok(());
+            }
+        };
+        let discord_id = user_id.to_string();
+
+        let accounts = queries::get_users_by_discord_id(pool, &discord_id).await?;
+
+        // a specific account, or every account when no name is given
+        let targets: Vec<String> = match args.get(1) {
+            Some(name) => accounts
+                .iter()
+                .filter(|a| a.username.eq_ignore_ascii_case(name))
+                .map(|a| a.username.clone())
+                .collect(),
+            None => accounts.iter().map(|a| a.username.clone()).collect(),
+        };
+
+        if targets.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("<@{}> has no matching linked Krunker account.", discord_id),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let prompt = format!(
+            "Unlink <@{}> from **{}**?",
+            discord_id,
+            targets.join("**, **")
+        );
+        if !confirm(ctx, msg, &prompt).await? {
+            return Ok(());
+        }
+
+        let moderator_id = msg.author.id.to_string();
+        let mut unlinked = Vec::new();
+        for username in &targets {
+            // the user may have unlinked it themselves while the prompt was up
+            let mut tx = pool.begin().await?;
+            if !queries::delete_user_account(&mut tx, &discord_id, username).await? {
+                continue;
+            }
+            queries::record_link_event(
+                &mut *tx,
+                &discord_id,
+                username,
+                LinkEventKind::ForceUnlinked,
+                &moderator_id,
+                None,
+            )
+            .await?;
+            tx.commit().await?;
+            unlinked.push(username.as_str());
+        }
+
+        if unlinked.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("<@{}> has no matching linked Krunker account.", discord_id),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        tracing::info!(
+            moderator = %msg.author.id,
+            discord_id = %discord_id,
+            accounts = ?unlinked,
+            "Force unlinked accounts"
+        );
+
+        msg.channel_id
+            .say(
+                &ctx.http,
+                format!(
+                    "✅ Unlinked <@{}> from **{}**.",
+                    discord_id,
+                    unlinked.join("**, **")
+                ),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
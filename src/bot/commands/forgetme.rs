@@ -0,0 +1,55 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::confirm::confirm;
+use crate::database::queries;
+
+pub struct ForgetMe;
+
+#[async_trait]
+impl KrunkerCommand for ForgetMe {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "forgetme",
+            description: "Delete everything the bot stores about you, including linked accounts",
+            usage: "&forgetme",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        _args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let discord_id = msg.author.id.to_string();
+
+        let prompt = "This deletes your linked Krunker accounts, verification history and \
+            usage logs for good. Verification cooldowns and lockouts stay in place. Use \
+            `&mydata` first if you want a copy. Continue?";
+        if !confirm(ctx, msg, prompt).await? {
+            return Ok(());
+        }
+
+        let removed = queries::forget_user(pool, &discord_id).await?;
+        // only a count, the id itself is what we were asked to forget
+        tracing::info!(removed, "Forgot a user");
+
+        msg.channel_id
+            .say(
+                &ctx.http,
+                "✅ Everything the bot stored about you has been deleted.",
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -3,11 +3,14 @@ use krunker_rs::Client as KrunkerClient;
 use serenity::all::{CreateEmbed, CreateMessage};
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{all_commands, CommandMetadata, KrunkerCommand};
 
+/// Discord's limit on fields in one embed
+const FIELDS_PER_EMBED: usize = 25;
+
 pub struct Help;
 
 #[async_trait]
@@ -28,25 +31,40 @@ impl KrunkerCommand for Help {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        _pool: &SqlitePool,
+        _pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let mut embed = CreateEmbed::new()
-            .title("Krunker Bot Help")
-            .description("Available commands (Prefix: `&`)")
-            .color(0x3498db)
-            .footer(serenity::all::CreateEmbedFooter::new("Krunker RS Bot"));
+        let config = crate::bot::state::config(ctx).await;
+        let prefix = config.bot.prefix.as_str();
+        let commands = all_commands();
 
-        for cmd in all_commands() {
-            let meta = cmd.metadata();
-            embed = embed.field(
-                format!("`{}`", meta.usage),
-                meta.description,
-                false,
-            );
+        // more commands than fit in one embed, so the list continues in the next
+        // ones; the title goes on the first and the footer on the last
+        let mut embeds = Vec::new();
+        for chunk in commands.chunks(FIELDS_PER_EMBED) {
+            let mut embed = CreateEmbed::new().color(config.colors.info);
+            if embeds.is_empty() {
+                embed = embed
+                    .title("Krunker Bot Help")
+                    .description(format!("Available commands (Prefix: `{}`)", prefix));
+            }
+            for cmd in chunk {
+                let meta = cmd.metadata();
+                // usages are written with the default prefix
+                let usage = meta.usage.strip_prefix('&').unwrap_or(meta.usage);
+                embed = embed.field(
+                    format!("`{}{}`", prefix, usage),
+                    meta.description,
+                    false,
+                );
+            }
+            embeds.push(embed);
+        }
+        if let Some(last) = embeds.pop() {
+            embeds.push(last.footer(serenity::all::CreateEmbedFooter::new("Krunker RS Bot")));
         }
 
         msg.channel_id
-            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .send_message(&ctx.http, CreateMessage::new().embeds(embeds))
             .await?;
             
         Ok(())
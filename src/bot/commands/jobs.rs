@@ -0,0 +1,130 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{OWNER_ONLY, is_bot_owner};
+use crate::jobs::{JobRun, TriggerError};
+
+pub struct Jobs;
+
+/// Formats an interval the way the config spells it, e.g. "15m"
+fn format_every(every: Duration) -> String {
+    let seconds = every.as_secs();
+    for (unit, size) in [("d", 24 * 60 * 60), ("h", 60 * 60), ("m", 60)] {
+        if seconds >= size && seconds % size == 0 {
+            return format!("{}{}", seconds / size, unit);
+        }
+    }
+    format!("{}s", seconds)
+}
+
+fn format_run(run: &JobRun) -> String {
+    let result = match &run.result {
+        Ok(summary) => summary.clone(),
+        Err(e) => format!("failed: {}", e),
+    };
+    format!(
+        "<t:{}:R>, {}ms, {}",
+        run.started_at,
+        run.elapsed.as_millis(),
+        result
+    )
+}
+
+#[async_trait]
+impl KrunkerCommand for Jobs {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "jobs",
+            description: "(Owner) List maintenance jobs or run one now",
+            usage: "&jobs [run <job>]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        _pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        if !is_bot_owner(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, OWNER_ONLY).await?;
+            return Ok(());
+        }
+
+        let Some(scheduler) = crate::bot::state::scheduler(ctx).await else {
+            msg.channel_id
+                .say(&ctx.http, "The job scheduler isn't running.")
+                .await?;
+            return Ok(());
+        };
+
+        match args.as_slice() {
+            [] => {
+                let mut embed = CreateEmbed::new()
+                    .title("Maintenance Jobs")
+                    .color(colors.info);
+                for status in scheduler.statuses() {
+                    let state = if status.running {
+                        "running now"
+                    } else if status.enabled {
+                        "enabled"
+                    } else {
+                        "disabled"
+                    };
+                    let last_run = status
+                        .last_run
+                        .as_ref()
+                        .map(format_run)
+                        .unwrap_or_else(|| "never".to_string());
+                    embed = embed.field(
+                        status.name,
+                        format!(
+                            "{}\nEvery {}, {}\nLast run: {}",
+                            status.description,
+                            format_every(status.every),
+                            state,
+                            last_run
+                        ),
+                        false,
+                    );
+                }
+                msg.channel_id
+                    .send_message(&ctx.http, CreateMessage::new().embed(embed))
+                    .await?;
+            }
+            ["run", name] => {
+                msg.channel_id
+                    .say(&ctx.http, format!("Running `{}`...", name))
+                    .await?;
+                let reply = match scheduler.trigger(name).await {
+                    Ok(run) => format!("`{}` finished {}", name, format_run(&run)),
+                    Err(TriggerError::UnknownJob) => {
+                        format!("There's no job called `{}`. See `&jobs`.", name)
+                    }
+                    Err(TriggerError::AlreadyRunning) => {
+                        format!("`{}` is already running.", name)
+                    }
+                };
+                msg.channel_id.say(&ctx.http, reply).await?;
+            }
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &jobs [run <job>]")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
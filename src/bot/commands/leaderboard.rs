@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::database::queries;
+use crate::history::ratings::PROVISIONAL_GAMES;
+
+const DEFAULT_COUNT: i64 = 10;
+const MAX_COUNT: i64 = 25;
+
+pub struct Leaderboard;
+
+#[async_trait]
+impl KrunkerCommand for Leaderboard {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "leaderboard",
+            description: "Show the highest rated players, see &rating",
+            usage: "&leaderboard [count]",
+            aliases: &["lb"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let count = args
+            .first()
+            .and_then(|arg| arg.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_COUNT)
+            .clamp(1, MAX_COUNT);
+
+        let top = queries::get_top_ratings(pool, PROVISIONAL_GAMES, count).await?;
+        if top.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!(
+                        "Nobody has {} rated games yet. Ratings come from the ranked matches the bot has stored.",
+                        PROVISIONAL_GAMES
+                    ),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let lines: Vec<String> = top
+            .iter()
+            .enumerate()
+            .map(|(i, rating)| {
+                format!(
+                    "{}. **{}** - {:.0} ({} games, {:.1}% won)",
+                    i + 1,
+                    rating.player_name,
+                    rating.rating,
+                    rating.games,
+                    rating.wins as f64 / rating.games as f64 * 100.0
+                )
+            })
+            .collect();
+
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let embed = CreateEmbed::new()
+            .title("Rating Leaderboard")
+            .description(lines.join("\n"))
+            .footer(CreateEmbedFooter::new(format!(
+                "Players with at least {} rated games",
+                PROVISIONAL_GAMES
+            )))
+            .color(colors.leaderboard);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use krunker_rs::Client as KrunkerClient;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{CommandMetadata, KrunkerCommand};
@@ -26,7 +26,7 @@ impl KrunkerCommand for Link {
         msg: &Message,
         _krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        pool: &SqlitePool,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let username = match args.get(0) {
             Some(u) if !u.is_empty() => u,
@@ -39,8 +39,11 @@ impl KrunkerCommand for Link {
         };
 
         let discord_id = msg.author.id.to_string();
+        let service = crate::bot::state::verification_service(ctx).await;
 
-        match crate::verification::flow::start_verification(pool, &discord_id, username).await {
+        match crate::verification::flow::start_verification(&service, pool, &discord_id, username)
+            .await
+        {
             Ok(code) => {
                 let response = format!(
                     "Verification started for **{}**!\n\n\
@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use serenity::utils::parse_user_mention;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::{LinkEvent, SYSTEM_ACTOR};
+use crate::database::queries::{self, FORGOTTEN_ACTOR};
+
+const HISTORY_LIMIT: i64 = 20;
+
+pub struct LinkHistory;
+
+#[async_trait]
+impl KrunkerCommand for LinkHistory {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "linkhistory",
+            description: "(Moderators) Show the link audit log for a user or Krunker name",
+            usage: "&linkhistory <@user|krunker_name>",
+            aliases: &["lh"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        if !is_moderator(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+            return Ok(());
+        }
+
+        let target = match args.first() {
+            Some(t) if !t.is_empty() => *t,
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &linkhistory <@user|krunker_name>")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let (events, target_label) = match parse_user_mention(target) {
+            Some(user_id) => (
+                queries::get_link_events_by_discord_id(pool, &user_id.to_string(), HISTORY_LIMIT)
+                    .await?,
+                format!("<@{}>", user_id),
+            ),
+            None => (
+                queries::get_link_events_by_username(pool, target, HISTORY_LIMIT).await?,
+                format!("**{}**", target),
+            ),
+        };
+
+        if events.is_empty() {
+            msg.channel_id
+                .say(&ctx.http, "No link events recorded for that user.")
+                .await?;
+            return Ok(());
+        }
+
+        let lines = events
+            .iter()
+            .map(format_event)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let embed = CreateEmbed::new()
+            .title("Link History")
+            .description(format!("Target: {}\n\n{}", target_label, lines))
+            .color(colors.moderation)
+            .footer(serenity::all::CreateEmbedFooter::new(format!(
+                "Showing up to {} most recent events",
+                HISTORY_LIMIT
+            )));
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn format_event(event: &LinkEvent) -> String {
+    let actor = match event.actor_id.as_str() {
+        SYSTEM_ACTOR => "system".to_string(),
+        FORGOTTEN_ACTOR => "a forgotten user".to_string(),
+        id => format!("<@{}>", id),
+    };
+
+    let mut line = format!(
+        "<t:{}:f> `{}` **{}** ↔ <@{}> (by {})",
+        event.created_at, event.event, event.krunker_username, event.discord_id, actor
+    );
+
+    if let Some(details) = &event.details {
+        line.push_str(&format!(" - {}", details));
+    }
+
+    line
+}
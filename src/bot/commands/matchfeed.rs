@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{
+    CommandMetadata, KrunkerCommand, announcement_channel_status, set_announcement_channel,
+};
+use crate::database::models::AnnouncementFeed;
+use crate::database::queries;
+
+pub struct MatchFeed;
+
+#[async_trait]
+impl KrunkerCommand for MatchFeed {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "matchfeed",
+            description: "Announce members' ranked matches in a channel, or opt yourself out",
+            usage: "&matchfeed [channel <#channel|off>|optout|optin]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let discord_id = msg.author.id.to_string();
+        let now = Utc::now().timestamp();
+
+        match args.as_slice() {
+            [] => {
+                let channel =
+                    announcement_channel_status(pool, msg, AnnouncementFeed::Matches).await?;
+                let opted_out = queries::get_announcement_opt_out(pool, &discord_id)
+                    .await?
+                    .is_some();
+                let you = if opted_out {
+                    "You're opted out of announcements. Use `&matchfeed optin` to change that."
+                } else {
+                    "Your matches are announced. Use `&matchfeed optout` to stop that."
+                };
+                msg.channel_id
+                    .say(&ctx.http, format!("Match feed: {}\n{}", channel, you))
+                    .await?;
+            }
+            // covers milestones too, an opted out member isn't announced at all
+            ["optout"] => {
+                queries::set_announcement_opt_out(pool, &discord_id, true, now).await?;
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "Your matches and milestones won't be announced anymore.",
+                    )
+                    .await?;
+            }
+            ["optin"] => {
+                queries::set_announcement_opt_out(pool, &discord_id, false, now).await?;
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "Your matches and milestones will be announced again.",
+                    )
+                    .await?;
+            }
+            ["channel", target] => {
+                set_announcement_channel(
+                    ctx,
+                    msg,
+                    pool,
+                    AnnouncementFeed::Matches,
+                    target,
+                    "Ranked matches",
+                )
+                .await?;
+            }
+            _ => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "Usage: &matchfeed [channel <#channel|off>|optout|optin]",
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{
+    CommandMetadata, KrunkerCommand, announcement_channel_status, set_announcement_channel,
+};
+use crate::database::models::AnnouncementFeed;
+
+pub struct Milestones;
+
+fn list(values: &[i64]) -> String {
+    if values.is_empty() {
+        return "none".to_string();
+    }
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[async_trait]
+impl KrunkerCommand for Milestones {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "milestones",
+            description: "Announce members' level, KR and games milestones in a channel",
+            usage: "&milestones [channel <#channel|off>]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match args.as_slice() {
+            [] => {
+                let config = crate::bot::state::config(ctx).await;
+                let milestones = &config.milestones;
+                let channel =
+                    announcement_channel_status(pool, msg, AnnouncementFeed::Milestones).await?;
+                let level = if milestones.level_step == 1 {
+                    "every level".to_string()
+                } else {
+                    format!("every {} levels", milestones.level_step)
+                };
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!(
+                            "Milestones: {}\nAnnounced: {}; KR {}; games {}",
+                            channel,
+                            level,
+                            list(&milestones.kr),
+                            list(&milestones.games)
+                        ),
+                    )
+                    .await?;
+            }
+            ["channel", target] => {
+                set_announcement_channel(
+                    ctx,
+                    msg,
+                    pool,
+                    AnnouncementFeed::Milestones,
+                    target,
+                    "Milestones",
+                )
+                .await?;
+            }
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &milestones [channel <#channel|off>]")
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -2,9 +2,14 @@ use async_trait::async_trait;
 use krunker_rs::Client as KrunkerClient;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use serenity::utils::{parse_channel_mention, parse_user_mention};
+use sqlx::AnyPool;
 use std::sync::Arc;
 
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::AnnouncementFeed;
+use crate::database::queries;
+
 pub mod ping;
 pub mod stats;
 pub mod ranked_stats;
@@ -14,6 +19,29 @@ pub mod help;
 pub mod link;
 pub mod verify;
 pub mod unlink;
+pub mod linkhistory;
+pub mod whois;
+pub mod whoowns;
+pub mod forcelink;
+pub mod forceunlink;
+pub mod accounts;
+pub mod setprimary;
+pub mod renamed;
+pub mod reloadconfig;
+pub mod botstats;
+pub mod mydata;
+pub mod forgetme;
+pub mod jobs;
+pub mod matchfeed;
+pub mod milestones;
+pub mod progress;
+pub mod clan;
+pub mod together;
+pub mod teammates;
+pub mod rivals;
+pub mod rating;
+pub mod leaderboard;
+pub mod queue;
 
 pub struct CommandMetadata {
     pub name: &'static str,
@@ -32,7 +60,7 @@ pub trait KrunkerCommand: Send + Sync {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        pool: &SqlitePool,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
@@ -47,5 +75,136 @@ pub fn all_commands() -> Vec<Arc<dyn KrunkerCommand>> {
         Arc::new(link::Link),
         Arc::new(verify::Verify),
         Arc::new(unlink::Unlink),
+        Arc::new(linkhistory::LinkHistory),
+        Arc::new(whois::WhoIs),
+        Arc::new(whoowns::WhoOwns),
+        Arc::new(forcelink::ForceLink),
+        Arc::new(forceunlink::ForceUnlink),
+        Arc::new(accounts::Accounts),
+        Arc::new(setprimary::SetPrimary),
+        Arc::new(renamed::Renamed),
+        Arc::new(reloadconfig::ReloadConfig),
+        Arc::new(botstats::BotStats),
+        Arc::new(mydata::MyData),
+        Arc::new(forgetme::ForgetMe),
+        Arc::new(jobs::Jobs),
+        Arc::new(matchfeed::MatchFeed),
+        Arc::new(milestones::Milestones),
+        Arc::new(progress::Progress),
+        Arc::new(clan::Clan),
+        Arc::new(together::Together),
+        Arc::new(teammates::Teammates),
+        Arc::new(rivals::Rivals),
+        Arc::new(rating::Rating),
+        Arc::new(leaderboard::Leaderboard),
+        Arc::new(queue::Queue),
     ]
 }
+
+/// Picks the Krunker username a player-scoped command should target: an explicit
+/// name, a mentioned user's primary account, or the caller's primary account.
+pub async fn resolve_player(
+    pool: &AnyPool,
+    msg: &Message,
+    arg: Option<&str>,
+) -> Result<Option<String>, sqlx::Error> {
+    let discord_id = match arg {
+        Some(arg) => match parse_user_mention(arg) {
+            Some(user_id) => user_id.to_string(),
+            None => return Ok(Some(arg.to_string())),
+        },
+        None => msg.author.id.to_string(),
+    };
+
+    Ok(queries::get_user_by_discord_id(pool, &discord_id)
+        .await?
+        .map(|user| user.username))
+}
+
+/// Splits `[player] [count]` arguments, treating a lone number as the count
+pub fn split_player_args<'a>(args: &[&'a str]) -> (Option<&'a str>, Option<&'a str>) {
+    match args {
+        [] => (None, None),
+        [only] if only.parse::<u32>().is_ok() => (None, Some(*only)),
+        [player] => (Some(*player), None),
+        [player, count, ..] => (Some(*player), Some(*count)),
+    }
+}
+
+/// Handles `channel <#channel|off>` for one kind of announcement. Moderators only,
+/// and the channel has to be in the server the command was sent from. `what` names
+/// the announcements in replies, e.g. "Ranked matches".
+pub async fn set_announcement_channel(
+    ctx: &Context,
+    msg: &Message,
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+    target: &str,
+    what: &str,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some(guild_id) = msg.guild_id else {
+        msg.channel_id
+            .say(&ctx.http, "Announcement channels are set from a server.")
+            .await?;
+        return Ok(());
+    };
+    if !is_moderator(ctx, msg).await? {
+        msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+        return Ok(());
+    }
+    let guild = guild_id.to_string();
+
+    if target == "off" {
+        let reply = if queries::clear_announcement_channel(pool, feed, &guild).await? {
+            format!("{} won't be announced anymore.", what)
+        } else {
+            format!("{} aren't being announced.", what)
+        };
+        msg.channel_id.say(&ctx.http, reply).await?;
+        return Ok(());
+    }
+
+    let Some(channel_id) = parse_channel_mention(target) else {
+        msg.channel_id
+            .say(&ctx.http, "Give a #channel, or `off` to stop announcing.")
+            .await?;
+        return Ok(());
+    };
+    let in_guild = channel_id
+        .to_channel(&ctx.http)
+        .await?
+        .guild()
+        .is_some_and(|channel| channel.guild_id == guild_id);
+    if !in_guild {
+        msg.channel_id
+            .say(&ctx.http, "That channel isn't in this server.")
+            .await?;
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let channel = channel_id.to_string();
+    queries::set_announcement_channel(pool, feed, &guild, &channel, &msg.author.id.to_string(), now)
+        .await?;
+    msg.channel_id
+        .say(&ctx.http, format!("{} will be announced in <#{}>.", what, channel_id))
+        .await?;
+    Ok(())
+}
+
+/// The channel a guild's announcements of one kind go to, for status replies
+pub async fn announcement_channel_status(
+    pool: &AnyPool,
+    msg: &Message,
+    feed: AnnouncementFeed,
+) -> Result<String, sqlx::Error> {
+    let Some(guild_id) = msg.guild_id else {
+        return Ok("off".to_string());
+    };
+    Ok(
+        match queries::get_announcement_channel(pool, feed, &guild_id.to_string()).await? {
+            Some(channel) => format!("<#{}>", channel.channel_id),
+            None => "off".to_string(),
+        },
+    )
+}
@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateAttachment, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::database::queries;
+
+pub struct MyData;
+
+#[async_trait]
+impl KrunkerCommand for MyData {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "mydata",
+            description: "DM you a copy of everything the bot stores about you",
+            usage: "&mydata",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        _args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let discord_id = msg.author.id.to_string();
+
+        let data = queries::export_user_data(pool, &discord_id, Utc::now().timestamp()).await?;
+        let json = serde_json::to_vec_pretty(&data)?;
+
+        let dm = CreateMessage::new()
+            .content("Here's everything the bot stores about you. `&forgetme` deletes it.")
+            .add_file(CreateAttachment::bytes(json, "mydata.json"));
+
+        // the export never goes to a guild channel, only the reply does
+        if msg.author.direct_message(ctx, dm).await.is_err() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "I couldn't DM you. Allow direct messages from server members and try again.",
+                )
+                .await?;
+            return Ok(());
+        }
+
+        if msg.guild_id.is_some() {
+            msg.channel_id
+                .say(&ctx.http, "📬 Sent your data in a DM.")
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use krunker_rs::Client as KrunkerClient;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{CommandMetadata, KrunkerCommand};
@@ -27,7 +27,7 @@ impl KrunkerCommand for Ping {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        _pool: &SqlitePool,
+        _pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         msg.channel_id.say(&ctx.http, "ping back").await?;
         Ok(())
@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::database::queries;
+use crate::history::snapshots;
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+const USAGE: &str = "Usage: &progress [username|@user] [7d|30d|all]";
+
+pub struct Progress;
+
+/// Days covered by a period argument, None for "all"
+fn parse_period(arg: &str) -> Option<Option<i64>> {
+    match arg.to_lowercase().as_str() {
+        "7d" => Some(Some(7)),
+        "30d" => Some(Some(30)),
+        "all" => Some(None),
+        _ => None,
+    }
+}
+
+fn change(from: i64, to: i64) -> String {
+    format!("{} → {} ({:+})", from, to, to - from)
+}
+
+#[async_trait]
+impl KrunkerCommand for Progress {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "progress",
+            description: "Show how a player's level, KR, K/D and games changed over time",
+            usage: "&progress [username|@user] [7d|30d|all]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let (player, period) = match args.as_slice() {
+            [] => (None, "7d"),
+            [only] if parse_period(only).is_some() => (None, *only),
+            [player] => (Some(*player), "7d"),
+            [player, period, ..] => (Some(*player), *period),
+        };
+        let Some(days) = parse_period(period) else {
+            msg.channel_id.say(&ctx.http, USAGE).await?;
+            return Ok(());
+        };
+        let Some(username) = resolve_player(pool, msg, player).await? else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("No linked Krunker account found. {}", USAGE),
+                )
+                .await?;
+            return Ok(());
+        };
+
+        let current = match snapshots::take(pool, krunker_api, &username).await {
+            Ok((current, _)) => current,
+            Err(e) => {
+                msg.channel_id
+                    .say(&ctx.http, format!("Error fetching stats: {}", e))
+                    .await?;
+                return Ok(());
+            }
+        };
+        queries::track_player(
+            pool,
+            &current.krunker_id,
+            &current.username,
+            current.taken_at,
+        )
+        .await?;
+
+        // the baseline is where the player stood when the period began, or the
+        // oldest snapshot if tracking started later than that
+        let start = days.map_or(0, |days| current.taken_at - days * SECONDS_PER_DAY);
+        let history = queries::get_snapshots_since(pool, &current.krunker_id, start).await?;
+        let baseline = match queries::get_snapshot_at(pool, &current.krunker_id, start).await? {
+            Some(baseline) => baseline,
+            None => history.first().cloned().unwrap_or_else(|| current.clone()),
+        };
+        if baseline.taken_at >= current.taken_at {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!(
+                        "No earlier snapshots of {} yet. They're taken daily from now on, check back in a few days.",
+                        current.username
+                    ),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let elapsed_days =
+            ((current.taken_at - baseline.taken_at) as f64 / SECONDS_PER_DAY as f64).max(1.0);
+        let games_per_day = (current.games - baseline.games) as f64 / elapsed_days;
+
+        let mut points = vec![baseline.clone()];
+        points.extend(history);
+        points.push(current.clone());
+        let days_seen = snapshots::daily(&points);
+        let trend = if days_seen.len() >= 2 {
+            let kdr: Vec<_> = days_seen.iter().map(|s| s.kdr).collect();
+            let kr: Vec<_> = days_seen.iter().map(|s| s.kr as f64).collect();
+            format!(
+                "K/D `{}`\nKR  `{}`",
+                snapshots::sparkline(&kdr),
+                snapshots::sparkline(&kr)
+            )
+        } else {
+            "Not enough days tracked yet".to_string()
+        };
+
+        let title = match days {
+            Some(days) => format!("{}'s progress, last {} days", current.username, days),
+            None => format!("{}'s progress", current.username),
+        };
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let embed = CreateEmbed::new()
+            .title(title)
+            .description(format!("Since <t:{}:R>", baseline.taken_at))
+            .field("Level", change(baseline.level, current.level), true)
+            .field("KR", change(baseline.kr, current.kr), true)
+            .field(
+                "K/D Ratio",
+                format!(
+                    "{:.2} → {:.2} ({:+.2})",
+                    baseline.kdr,
+                    current.kdr,
+                    current.kdr - baseline.kdr
+                ),
+                true,
+            )
+            .field(
+                "Games Played",
+                format!(
+                    "{} ({:.1} a day)",
+                    change(baseline.games, current.games),
+                    games_per_day
+                ),
+                true,
+            )
+            .field("Trend", trend, false)
+            .color(colors.stats);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
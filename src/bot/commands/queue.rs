@@ -0,0 +1,596 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+    CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage, EditMessage,
+};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::future::IntoFuture;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::{Lobby, LobbyPlayer};
+use crate::database::queries;
+use crate::history::ratings::PROVISIONAL_GAMES;
+use crate::history::snapshots;
+use crate::lifecycle::Lifecycle;
+use crate::matchmaking::balance::{Split, skill, splits};
+use crate::matchmaking::{QueuedPlayer, Queues};
+
+const USAGE: &str = "Usage: &queue [join|leave|start|clear|last]";
+/// How long the reroll buttons wait for a press, counted again after each one
+const LOBBY_TIMEOUT_SECONDS: u64 = 300;
+/// Splits the buttons go through, most balanced first, before starting over
+const MAX_PROPOSALS: usize = 10;
+
+const REROLL_ID: &str = "queue_reroll";
+const LOCK_IN_ID: &str = "queue_lock_in";
+
+pub struct Queue;
+
+fn average(skills: &[f64], team: &[usize]) -> f64 {
+    team.iter().map(|&i| skills[i]).sum::<f64>() / team.len() as f64
+}
+
+fn team_field(players: &[QueuedPlayer], skills: &[f64], team: &[usize]) -> String {
+    team.iter()
+        .map(|&i| {
+            format!(
+                "<@{}> {} ({:.0})",
+                players[i].discord_id, players[i].username, skills[i]
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn lobby_embed(
+    players: &[QueuedPlayer],
+    skills: &[f64],
+    split: &Split,
+    status: &str,
+    color: u32,
+) -> CreateEmbed {
+    CreateEmbed::new()
+        .title(format!(
+            "{}v{} Lobby",
+            split.teams[0].len(),
+            split.teams[1].len()
+        ))
+        .description(status)
+        .field(
+            format!("Team 1 ({:.0} avg)", average(skills, &split.teams[0])),
+            team_field(players, skills, &split.teams[0]),
+            true,
+        )
+        .field(
+            format!("Team 2 ({:.0} avg)", average(skills, &split.teams[1])),
+            team_field(players, skills, &split.teams[1]),
+            true,
+        )
+        .footer(CreateEmbedFooter::new(format!(
+            "Skill difference {:.0}. Skill is the rating from stored ranked matches, blended with profile K/D under {} rated games.",
+            split.difference.abs(),
+            PROVISIONAL_GAMES
+        )))
+        .color(color)
+}
+
+fn buttons(proposals: usize) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(REROLL_ID)
+            .label("Reroll")
+            .style(ButtonStyle::Secondary)
+            .disabled(proposals < 2),
+        CreateButton::new(LOCK_IN_ID)
+            .label("Lock In")
+            .style(ButtonStyle::Success),
+    ])]
+}
+
+fn proposal_status(shown: usize, proposals: usize) -> String {
+    format!(
+        "Option {} of {}, most balanced first. Anyone in the lobby can reroll or lock the teams in.",
+        shown + 1,
+        proposals
+    )
+}
+
+/// A posted lobby waiting on its buttons
+struct PendingLobby {
+    message: Message,
+    guild_id: Option<String>,
+    started_by: String,
+    players: Vec<QueuedPlayer>,
+    skills: Vec<f64>,
+    proposals: Vec<Split>,
+    color: u32,
+}
+
+/// Rates `players` and posts the most balanced teams with reroll buttons. The
+/// buttons are waited on in the background, so the command finishes once the
+/// lobby is posted.
+async fn start_lobby(
+    ctx: &Context,
+    msg: &Message,
+    krunker_api: &KrunkerClient,
+    pool: &AnyPool,
+    players: &[QueuedPlayer],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut skills = Vec::new();
+    let mut unrated = Vec::new();
+    for player in players {
+        // the profile refresh doubles as a snapshot for &progress
+        let (kdr, krunker_id) = match snapshots::take(pool, krunker_api, &player.username).await {
+            Ok((snapshot, _)) => (Some(snapshot.kdr), Some(snapshot.krunker_id)),
+            Err(e) => {
+                tracing::warn!(username = %player.username, "Failed to fetch profile: {}", e);
+                unrated.push(player.username.as_str());
+                (None, None)
+            }
+        };
+        let rating = queries::get_rating(
+            pool,
+            krunker_id.as_deref(),
+            &player.username,
+            PROVISIONAL_GAMES,
+        )
+        .await?
+        .map(|(rating, _)| rating);
+        skills.push(skill(kdr, rating.as_ref()));
+    }
+
+    let proposals: Vec<Split> = splits(&skills).into_iter().take(MAX_PROPOSALS).collect();
+    let Some(first) = proposals.first() else {
+        return Err("Couldn't split the lobby into even teams".into());
+    };
+
+    let color = crate::bot::state::config(ctx).await.colors.info;
+    let mentions: Vec<String> = players
+        .iter()
+        .map(|p| format!("<@{}>", p.discord_id))
+        .collect();
+    let mut content = format!("Lobby ready: {}", mentions.join(" "));
+    if !unrated.is_empty() {
+        content.push_str(&format!(
+            "\nCouldn't fetch {}, rated from stored matches only.",
+            unrated.join(", ")
+        ));
+    }
+
+    let message = msg
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content(content)
+                .embed(lobby_embed(
+                    players,
+                    &skills,
+                    first,
+                    &proposal_status(0, proposals.len()),
+                    color,
+                ))
+                .components(buttons(proposals.len())),
+        )
+        .await?;
+
+    let lobby = PendingLobby {
+        message,
+        guild_id: msg.guild_id.map(|id| id.to_string()),
+        started_by: msg.author.id.to_string(),
+        players: players.to_vec(),
+        skills,
+        proposals,
+        color,
+    };
+    let lifecycle = crate::bot::state::lifecycle(ctx).await;
+    // tracked like a command, but the buttons stop waiting once shutdown starts
+    // so the drain isn't held up
+    let work = lifecycle.begin();
+    let ctx = ctx.clone();
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = run_lobby(&ctx, &pool, &lifecycle, lobby).await {
+            tracing::error!("Lobby failed: {}", e);
+        }
+        drop(work);
+    });
+    Ok(())
+}
+
+/// Handles the reroll and lock in buttons until someone locks the teams in, they
+/// time out or the bot shuts down, then records the teams that were kept
+async fn run_lobby(
+    ctx: &Context,
+    pool: &AnyPool,
+    lifecycle: &Lifecycle,
+    lobby: PendingLobby,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let PendingLobby {
+        mut message,
+        guild_id,
+        started_by,
+        players,
+        skills,
+        proposals,
+        color,
+    } = lobby;
+    let mut shown = 0;
+    let mut rerolls = 0;
+    let mut stopping = false;
+    let locked_in = loop {
+        let pressed = tokio::select! {
+            interaction = message
+                .await_component_interaction(&ctx.shard)
+                .timeout(Duration::from_secs(LOBBY_TIMEOUT_SECONDS))
+                .into_future() => interaction,
+            _ = lifecycle.stopped() => {
+                stopping = true;
+                None
+            }
+        };
+        let Some(interaction) = pressed else {
+            break false;
+        };
+
+        let presser = interaction.user.id.get();
+        if !players.iter().any(|p| p.discord_id == presser) {
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::Message(
+                        CreateInteractionResponseMessage::new()
+                            .content("Only players in this lobby can do that.")
+                            .ephemeral(true),
+                    ),
+                )
+                .await?;
+            continue;
+        }
+
+        if interaction.data.custom_id == LOCK_IN_ID {
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .embed(lobby_embed(
+                                &players,
+                                &skills,
+                                &proposals[shown],
+                                &format!("✅ Locked in by <@{}>. Good luck!", presser),
+                                color,
+                            ))
+                            .components(vec![]),
+                    ),
+                )
+                .await?;
+            break true;
+        }
+
+        shown = (shown + 1) % proposals.len();
+        rerolls += 1;
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(lobby_embed(
+                            &players,
+                            &skills,
+                            &proposals[shown],
+                            &proposal_status(shown, proposals.len()),
+                            color,
+                        ))
+                        .components(buttons(proposals.len())),
+                ),
+            )
+            .await?;
+    };
+
+    let split = &proposals[shown];
+    if !locked_in {
+        let status = if stopping {
+            "The bot is restarting, these are the teams."
+        } else {
+            "⌛ Timed out, these are the teams."
+        };
+        let embed = lobby_embed(&players, &skills, split, status, color);
+        message
+            .edit(ctx, EditMessage::new().embed(embed).components(vec![]))
+            .await?;
+    }
+
+    let record = Lobby {
+        id: 0,
+        guild_id,
+        channel_id: message.channel_id.to_string(),
+        started_by,
+        team_size: split.teams[0].len() as i64,
+        rerolls,
+        locked_in: locked_in as i64,
+        skill_difference: split.difference,
+        created_at: Utc::now().timestamp(),
+    };
+    let lobby_players: Vec<LobbyPlayer> = split
+        .teams
+        .iter()
+        .zip(1..)
+        .flat_map(|(team, number)| team.iter().map(move |&i| (i, number)))
+        .map(|(i, team)| LobbyPlayer {
+            lobby_id: 0,
+            discord_id: players[i].discord_id.to_string(),
+            username: players[i].username.clone(),
+            team,
+            skill: skills[i],
+        })
+        .collect();
+    queries::record_lobby(pool, &record, &lobby_players).await?;
+    Ok(())
+}
+
+/// Starts a lobby for players already taken off the queue, putting them back at
+/// the front if it couldn't be made
+async fn start_or_requeue(
+    ctx: &Context,
+    msg: &Message,
+    krunker_api: &KrunkerClient,
+    pool: &AnyPool,
+    queues: &Mutex<Queues>,
+    players: Vec<QueuedPlayer>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let result = start_lobby(ctx, msg, krunker_api, pool, &players).await;
+    if result.is_err() {
+        queues
+            .lock()
+            .unwrap()
+            .requeue(msg.channel_id.get(), players);
+    }
+    result
+}
+
+async fn show_queue(
+    ctx: &Context,
+    msg: &Message,
+    players: &[QueuedPlayer],
+    needed: usize,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let description = if players.is_empty() {
+        "Nobody is queued. Join with `&queue join`.".to_string()
+    } else {
+        players
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                format!(
+                    "{}. <@{}> {} <t:{}:R>",
+                    i + 1,
+                    p.discord_id,
+                    p.username,
+                    p.joined_at
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let color = crate::bot::state::config(ctx).await.colors.info;
+    let embed = CreateEmbed::new()
+        .title(format!("Queue ({}/{})", players.len(), needed))
+        .description(description)
+        .footer(CreateEmbedFooter::new(
+            "Teams are made once the queue is full, or early with &queue start",
+        ))
+        .color(color);
+    msg.channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await?;
+    Ok(())
+}
+
+async fn show_last_lobby(
+    ctx: &Context,
+    msg: &Message,
+    pool: &AnyPool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let Some((lobby, players)) = queries::get_last_lobby(pool, &msg.channel_id.to_string()).await?
+    else {
+        msg.channel_id
+            .say(&ctx.http, "No lobbies have been made in this channel yet.")
+            .await?;
+        return Ok(());
+    };
+
+    let team = |number: i64| {
+        players
+            .iter()
+            .filter(|p| p.team == number)
+            .map(|p| format!("<@{}> {} ({:.0})", p.discord_id, p.username, p.skill))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let outcome = if lobby.locked_in == 1 {
+        "locked in"
+    } else {
+        "timed out"
+    };
+
+    let color = crate::bot::state::config(ctx).await.colors.info;
+    let embed = CreateEmbed::new()
+        .title(format!("Last Lobby #{}", lobby.id))
+        .description(format!(
+            "Made <t:{}:R>, {} after {} rerolls",
+            lobby.created_at, outcome, lobby.rerolls
+        ))
+        .field("Team 1", team(1), true)
+        .field("Team 2", team(2), true)
+        .footer(CreateEmbedFooter::new(format!(
+            "Skill difference {:.0}",
+            lobby.skill_difference.abs()
+        )))
+        .color(color);
+    msg.channel_id
+        .send_message(&ctx.http, CreateMessage::new().embed(embed))
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl KrunkerCommand for Queue {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "queue",
+            description: "Queue for an in-house lobby, teams are balanced from stats and ranked history",
+            usage: "&queue [join|leave|start|clear|last]",
+            aliases: &["q"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if msg.guild_id.is_none() {
+            msg.channel_id
+                .say(&ctx.http, "Queues only work in a server channel.")
+                .await?;
+            return Ok(());
+        }
+
+        let channel_id = msg.channel_id.get();
+        let author_id = msg.author.id.get();
+        let needed = 2 * crate::bot::state::config(ctx).await.queue.team_size;
+        let queues = crate::bot::state::queues(ctx).await;
+
+        match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            None => {
+                let players = queues.lock().unwrap().players(channel_id).to_vec();
+                show_queue(ctx, msg, &players, needed).await?;
+            }
+            Some("join") => {
+                let Some(user) =
+                    queries::get_user_by_discord_id(pool, &author_id.to_string()).await?
+                else {
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            "Link a Krunker account with `&link <username>` before queueing.",
+                        )
+                        .await?;
+                    return Ok(());
+                };
+                let player = QueuedPlayer {
+                    discord_id: author_id,
+                    username: user.username,
+                    joined_at: Utc::now().timestamp(),
+                };
+
+                let (joined, full) = {
+                    let mut queues = queues.lock().unwrap();
+                    let joined = queues.join(channel_id, player);
+                    let full = match joined {
+                        Some(queued) if queued >= needed => queues.take(channel_id, needed),
+                        _ => None,
+                    };
+                    (joined, full)
+                };
+
+                match (joined, full) {
+                    (None, _) => {
+                        msg.channel_id
+                            .say(&ctx.http, "You're already in the queue.")
+                            .await?;
+                    }
+                    (Some(_), Some(players)) => {
+                        start_or_requeue(ctx, msg, krunker_api, pool, &queues, players).await?;
+                    }
+                    (Some(queued), None) => {
+                        msg.channel_id
+                            .say(
+                                &ctx.http,
+                                format!(
+                                    "<@{}> joined the queue ({}/{}).",
+                                    author_id, queued, needed
+                                ),
+                            )
+                            .await?;
+                    }
+                }
+            }
+            Some("leave") => {
+                let left = queues.lock().unwrap().leave(channel_id, author_id);
+                let reply = if left {
+                    "You left the queue."
+                } else {
+                    "You're not in the queue."
+                };
+                msg.channel_id.say(&ctx.http, reply).await?;
+            }
+            Some("start") => {
+                let (queued, in_queue) = {
+                    let queues = queues.lock().unwrap();
+                    let players = queues.players(channel_id);
+                    (
+                        players.len(),
+                        players.iter().any(|p| p.discord_id == author_id),
+                    )
+                };
+                if !in_queue && !is_moderator(ctx, msg).await? {
+                    msg.channel_id
+                        .say(
+                            &ctx.http,
+                            "Only queued players or moderators can start a lobby early.",
+                        )
+                        .await?;
+                    return Ok(());
+                }
+
+                // the longest waiting players, an even number of them
+                let count = queued.min(needed) / 2 * 2;
+                let taken = if count >= 2 {
+                    queues.lock().unwrap().take(channel_id, count)
+                } else {
+                    None
+                };
+                match taken {
+                    Some(players) => {
+                        start_or_requeue(ctx, msg, krunker_api, pool, &queues, players).await?
+                    }
+                    None => {
+                        msg.channel_id
+                            .say(&ctx.http, "At least 2 players need to be queued.")
+                            .await?;
+                    }
+                }
+            }
+            Some("clear") => {
+                if !is_moderator(ctx, msg).await? {
+                    msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+                    return Ok(());
+                }
+                let cleared = queues.lock().unwrap().clear(channel_id);
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("Cleared the queue, {} players removed.", cleared),
+                    )
+                    .await?;
+            }
+            Some("last") => show_last_lobby(ctx, msg, pool).await?,
+            Some(_) => {
+                msg.channel_id.say(&ctx.http, USAGE).await?;
+            }
+        }
+        Ok(())
+    }
+}
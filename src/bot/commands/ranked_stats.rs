@@ -3,10 +3,11 @@ use krunker_rs::Client as KrunkerClient;
 use serenity::all::{CreateEmbed, CreateMessage};
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
-use super::{CommandMetadata, KrunkerCommand};
+use super::{CommandMetadata, KrunkerCommand, resolve_player, split_player_args};
+use crate::metrics::track_api;
 
 pub struct RankedStats;
 
@@ -16,7 +17,7 @@ impl KrunkerCommand for RankedStats {
         CommandMetadata {
             name: "rankedstats",
             description: "Show detailed stats for the last N ranked matches",
-            usage: "&rankedstats <username> [count]",
+            usage: "&rankedstats [username|@user] [count]",
             aliases: &["r"],
         }
     }
@@ -27,24 +28,36 @@ impl KrunkerCommand for RankedStats {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        _pool: &SqlitePool,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let username = args.get(0).unwrap_or(&"");
-        let count_str = args.get(1).unwrap_or(&"");
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let (player_arg, count_arg) = split_player_args(&args);
+        let count_str = count_arg.unwrap_or("");
 
-        if username.is_empty() {
-            msg.channel_id
-                .say(&ctx.http, "Usage: &rankedstats <username> [count]")
-                .await?;
-            return Ok(());
-        }
+        let username = match resolve_player(pool, msg, player_arg).await? {
+            Some(u) => u,
+            None => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "No linked Krunker account found. Usage: &rankedstats [username|@user] [count]",
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
 
         let mut count: i32 = 1;
         if !count_str.is_empty() {
             count = count_str.parse::<i32>().unwrap_or(1);
         }
 
-        match krunker_api.get_player_matches(username, None, None).await {
+        match track_api(
+            "get_player_matches",
+            krunker_api.get_player_matches(&username, None, None),
+        )
+        .await
+        {
             Ok(data) => {
                 let matches = data.pmr_matches.unwrap_or_default();
                 if matches.is_empty() {
@@ -56,7 +69,7 @@ impl KrunkerCommand for RankedStats {
 
                 let mut embed = CreateEmbed::new()
                     .title(format!("Recent Ranked Matches - {}", username))
-                    .color(0x00ff00);
+                    .color(colors.stats);
 
                 for (_i, pmatch) in matches.iter().take(count as usize).enumerate() {
                     let kdr = if pmatch.pm_deaths > 0 {
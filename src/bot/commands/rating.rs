@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::database::queries;
+use crate::history::ratings::PROVISIONAL_GAMES;
+
+pub struct Rating;
+
+#[async_trait]
+impl KrunkerCommand for Rating {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "rating",
+            description: "Show a player's rating from the ranked matches the bot has stored",
+            usage: "&rating [username|@user]",
+            aliases: &["elo"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(username) = resolve_player(pool, msg, args.first().copied()).await? else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "No linked Krunker account found. Usage: &rating [username|@user]",
+                )
+                .await?;
+            return Ok(());
+        };
+
+        // linked accounts know their krunker_id, so a rename keeps its rating
+        let krunker_id = queries::get_user_by_username(pool, &username)
+            .await?
+            .and_then(|user| user.krunker_id);
+        let Some((rating, place)) =
+            queries::get_rating(pool, krunker_id.as_deref(), &username, PROVISIONAL_GAMES).await?
+        else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!(
+                        "{} has no rating yet. Ratings come from the ranked matches the bot has stored and are recomputed hourly.",
+                        username
+                    ),
+                )
+                .await?;
+            return Ok(());
+        };
+        let (_, ranked) = queries::count_ratings(pool, PROVISIONAL_GAMES).await?;
+
+        let place = match place {
+            Some(place) => format!("#{} of {}", place, ranked),
+            None => format!(
+                "Provisional, {} more games to place",
+                PROVISIONAL_GAMES - rating.games
+            ),
+        };
+        let win_rate = rating.wins as f64 / rating.games.max(1) as f64 * 100.0;
+
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let embed = CreateEmbed::new()
+            .title(format!("{}'s Rating", rating.player_name))
+            .description(format!("Recomputed <t:{}:R>", rating.computed_at))
+            .field("Rating", format!("{:.0}", rating.rating), true)
+            .field("Peak", format!("{:.0}", rating.peak), true)
+            .field("Place", place, true)
+            .field("Rated Games", rating.games.to_string(), true)
+            .field("Win Rate", format!("{:.1}%", win_rate), true)
+            .footer(CreateEmbedFooter::new(
+                "Team Elo over the ranked matches the bot has stored",
+            ))
+            .color(colors.stats);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
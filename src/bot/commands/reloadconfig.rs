@@ -0,0 +1,86 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{OWNER_ONLY, is_bot_owner};
+use crate::bot::state::{ConfigKey, VerificationServiceKey};
+use crate::config::Config;
+
+pub struct ReloadConfig;
+
+#[async_trait]
+impl KrunkerCommand for ReloadConfig {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "reloadconfig",
+            description: "(Owner) Reload the config file without restarting",
+            usage: "&reloadconfig",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        _args: Vec<&str>,
+        _pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !is_bot_owner(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, OWNER_ONLY).await?;
+            return Ok(());
+        }
+
+        // a broken file leaves the running config alone
+        let new = match Config::load() {
+            Ok(config) => config,
+            Err(e) => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("Config not reloaded, nothing changed.\n```\n{}\n```", e),
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let restart_required = {
+            let mut data = ctx.data.write().await;
+            let running = data.get::<ConfigKey>().cloned().unwrap_or_default();
+            let (config, restart_required) = running.reload(new);
+
+            let service = data
+                .get::<VerificationServiceKey>()
+                .cloned()
+                .unwrap_or_default()
+                .with_settings(config.verification_settings());
+            data.insert::<VerificationServiceKey>(service);
+            data.insert::<ConfigKey>(Arc::new(config));
+
+            restart_required
+        };
+
+        tracing::info!(moderator = %msg.author.id, "Reloaded config");
+
+        let mut response = "Config reloaded.".to_string();
+        if !restart_required.is_empty() {
+            response.push_str(&format!(
+                "\nThese changes only apply after a restart: {}",
+                restart_required
+                    .iter()
+                    .map(|name| format!("`{}`", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        msg.channel_id.say(&ctx.http, response).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::identity::resolver::{lookup_krunker_id, observe_player};
+
+pub struct Renamed;
+
+#[async_trait]
+impl KrunkerCommand for Renamed {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "renamed",
+            description: "Update your link after changing your Krunker name",
+            usage: "&renamed <new_username>",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let new_name = match args.first() {
+            Some(u) if !u.is_empty() => *u,
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &renamed <new_username>")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let krunker_id = match lookup_krunker_id(krunker_api, new_name).await? {
+            Some(id) => id,
+            None => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        format!("No Krunker player named **{}**.", new_name),
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let response = match observe_player(pool, new_name, &krunker_id).await? {
+            Some(before) if before.discord_id == msg.author.id.to_string() => format!(
+                "✅ Updated your link: **{}** is now **{}**.",
+                before.username, new_name
+            ),
+            Some(_) => format!(
+                "**{}** was linked to another Discord account; its stored name has been updated.",
+                new_name
+            ),
+            None => format!(
+                "**{}** isn't one of your linked accounts under an older name. \
+                If you just linked it, there is nothing to update.",
+                new_name
+            ),
+        };
+
+        msg.channel_id.say(&ctx.http, response).await?;
+
+        Ok(())
+    }
+}
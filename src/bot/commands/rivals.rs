@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::history::pairings;
+
+/// Opponents listed, most games against first
+const LISTED: usize = 10;
+
+pub struct Rivals;
+
+#[async_trait]
+impl KrunkerCommand for Rivals {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "rivals",
+            description: "Show who a player faces most in ranked, and the head-to-head record",
+            usage: "&rivals [username|@user]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(username) = resolve_player(pool, msg, args.first().copied()).await? else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "No linked Krunker account found. Usage: &rivals [username|@user]",
+                )
+                .await?;
+            return Ok(());
+        };
+
+        let pairings = pairings::load(pool, krunker_api, &username).await?;
+        if pairings.rivals.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("No stored ranked matches with opponents for {}.", username),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let lines: Vec<String> = pairings
+            .rivals
+            .iter()
+            .take(LISTED)
+            .enumerate()
+            .map(|(i, rival)| {
+                format!(
+                    "{}. **{}** - {} games, {}W - {}L, {:.2} K/D",
+                    i + 1,
+                    rival.name,
+                    rival.games,
+                    rival.wins,
+                    rival.games - rival.wins,
+                    rival.kdr()
+                )
+            })
+            .collect();
+
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let embed = CreateEmbed::new()
+            .title(format!("{}'s Rivals", username))
+            .description(lines.join("\n"))
+            .footer(CreateEmbedFooter::new(format!(
+                "Records are {}'s, over {} stored ranked matches",
+                username, pairings.overall.games
+            )))
+            .color(colors.leaderboard);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
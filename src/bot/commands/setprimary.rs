@@ -0,0 +1,65 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::database::queries;
+
+pub struct SetPrimary;
+
+#[async_trait]
+impl KrunkerCommand for SetPrimary {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "setprimary",
+            description: "Choose which linked Krunker account commands use by default",
+            usage: "&setprimary <username>",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let username = match args.first() {
+            Some(u) if !u.is_empty() => *u,
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &setprimary <username>")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let discord_id = msg.author.id.to_string();
+
+        if queries::set_primary_account(pool, &discord_id, username).await? {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("✅ **{}** is now your primary account.", username),
+                )
+                .await?;
+        } else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!(
+                        "**{}** isn't linked to your Discord account. See `&accounts`.",
+                        username
+                    ),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
@@ -5,10 +5,11 @@ use krunker_rs::MatchParticipant;
 use serenity::all::{CreateEmbed, CreateMessage};
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{CommandMetadata, KrunkerCommand};
+use crate::metrics::track_api;
 
 pub struct SpecificMatch;
 
@@ -19,7 +20,7 @@ impl KrunkerCommand for SpecificMatch {
             name: "specificmatch",
             description: "Get detailed statistics for a specific match ID",
             usage: "&sm <match_id>",
-            aliases: &["sm"],
+            aliases: &["sm", "match"],
         }
     }
 
@@ -29,8 +30,9 @@ impl KrunkerCommand for SpecificMatch {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        _pool: &SqlitePool,
+        _pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
         let id_str = args.get(0).unwrap_or(&"");
         let match_id = id_str.parse::<i64>().unwrap_or(0);
 
@@ -39,7 +41,7 @@ impl KrunkerCommand for SpecificMatch {
             return Ok(());
         }
 
-        match krunker_api.get_match(match_id).await {
+        match track_api("get_match", krunker_api.get_match(match_id)).await {
             Ok(data) => {
                 let participants = match data.match_participants {
                     Some(ref p) if !p.is_empty() => p,
@@ -58,7 +60,7 @@ impl KrunkerCommand for SpecificMatch {
                     .field("Map", data.match_map.to_string(), true)
                     .field("Duration", format!("{}m {}s", mins, secs), true)
                     .field("Date", &data.match_date, true)
-                    .color(0x00ff00);
+                    .color(colors.stats);
 
                 let mut team_1: Vec<&MatchParticipant> = Vec::new();
                 let mut team_2: Vec<&MatchParticipant> = Vec::new();
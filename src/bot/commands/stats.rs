@@ -3,10 +3,13 @@ use krunker_rs::Client as KrunkerClient;
 use serenity::all::{CreateEmbed, CreateMessage};
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
-use super::{CommandMetadata, KrunkerCommand};
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::database::queries;
+use crate::history::snapshots;
+use crate::identity::resolver::observe_player;
 
 pub struct Stats;
 
@@ -16,7 +19,7 @@ impl KrunkerCommand for Stats {
         CommandMetadata {
             name: "stats",
             description: "Show general player statistics (K/D, Level, KR)",
-            usage: "&stats <username>",
+            usage: "&stats [username|@user]",
             aliases: &["p"],
         }
     }
@@ -28,39 +31,48 @@ impl KrunkerCommand for Stats {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         args: Vec<&str>,
-        _pool: &SqlitePool,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let username = args.get(0).unwrap_or(&"");
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let username = match resolve_player(pool, msg, args.first().copied()).await? {
+            Some(u) => u,
+            None => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "No linked Krunker account found. Usage: &stats [username|@user]",
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
 
-        if username.is_empty() {
-            msg.channel_id
-                .say(&ctx.http, "Usage: &stats <username>")
-                .await?;
-            return Ok(());
-        }
+        // kept for &progress, a looked up player is snapshotted daily for a while
+        match snapshots::take(pool, krunker_api, &username).await {
+            Ok((snapshot, _)) => {
+                if let Err(e) = observe_player(pool, &snapshot.username, &snapshot.krunker_id).await
+                {
+                    tracing::warn!("Failed to check {} for a rename: {}", snapshot.username, e);
+                }
+                if let Err(e) = queries::track_player(
+                    pool,
+                    &snapshot.krunker_id,
+                    &snapshot.username,
+                    snapshot.taken_at,
+                )
+                .await
+                {
+                    tracing::warn!("Failed to track {}: {}", snapshot.username, e);
+                }
 
-        match krunker_api.get_player(username).await {
-            Ok(player) => {
                 let embed = CreateEmbed::new()
-                    .title(format!(
-                        "{}{}",
-                        player.player_name,
-                        if player.player_verified { " ✅" } else { "" }
-                    ))
-                    .field(
-                        "Clan",
-                        if player.player_clan.is_empty() {
-                            "None"
-                        } else {
-                            &player.player_clan
-                        },
-                        true,
-                    )
-                    .field("Level", player.player_level.to_string(), true)
-                    .field("KR", player.player_kr.to_string(), true)
-                    .field("K/D Ratio", format!("{:.2}", player.player_kdr), true)
-                    .field("Games Played", player.player_games.to_string(), true)
-                    .color(0x00ff00);
+                    .title(&snapshot.username)
+                    .field("Clan", snapshot.clan.as_deref().unwrap_or("None"), true)
+                    .field("Level", snapshot.level.to_string(), true)
+                    .field("KR", snapshot.kr.to_string(), true)
+                    .field("K/D Ratio", format!("{:.2}", snapshot.kdr), true)
+                    .field("Games Played", snapshot.games.to_string(), true)
+                    .color(colors.stats);
 
                 msg.channel_id
                     .send_message(&ctx.http, CreateMessage::new().embed(embed))
@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::history::pairings;
+
+/// Teammates listed, most games together first
+const LISTED: usize = 10;
+
+pub struct Teammates;
+
+#[async_trait]
+impl KrunkerCommand for Teammates {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "teammates",
+            description: "Show who a player teams with most in ranked, and how they do together",
+            usage: "&teammates [username|@user]",
+            aliases: &["duos"],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(username) = resolve_player(pool, msg, args.first().copied()).await? else {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    "No linked Krunker account found. Usage: &teammates [username|@user]",
+                )
+                .await?;
+            return Ok(());
+        };
+
+        let pairings = pairings::load(pool, krunker_api, &username).await?;
+        if pairings.teammates.is_empty() {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("No stored ranked matches with teammates for {}.", username),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        // the K/D change is against the player's K/D over all their stored matches
+        let overall_kdr = pairings.overall.kdr();
+        let lines: Vec<String> = pairings
+            .teammates
+            .iter()
+            .take(LISTED)
+            .enumerate()
+            .map(|(i, teammate)| {
+                format!(
+                    "{}. **{}** - {} games, {:.1}% won, {:.2} K/D ({:+.2})",
+                    i + 1,
+                    teammate.name,
+                    teammate.games,
+                    teammate.win_rate().unwrap_or(0.0) * 100.0,
+                    teammate.kdr(),
+                    teammate.kdr() - overall_kdr
+                )
+            })
+            .collect();
+
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        let embed = CreateEmbed::new()
+            .title(format!("{}'s Teammates", username))
+            .description(lines.join("\n"))
+            .footer(CreateEmbedFooter::new(format!(
+                "{} stored ranked matches, {:.2} K/D overall",
+                pairings.overall.games, overall_kdr
+            )))
+            .color(colors.leaderboard);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateEmbedFooter, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand, resolve_player};
+use crate::database::queries;
+use crate::history::matches::store_missing;
+use crate::history::together::{PlayerTotals, analyze};
+use crate::metrics::track_api;
+
+const USAGE: &str = "Usage: &together <player> <player> [player...]";
+const MAX_PLAYERS: usize = 5;
+/// Shared matches looked up with `get_match` per command, the rest wait for a later run
+const MAX_LOOKUPS: usize = 10;
+/// Newest shared matches counted
+const MAX_SHARED: usize = 200;
+/// Matches listed at the bottom
+const RECENT_SHOWN: usize = 5;
+
+pub struct Together;
+
+fn percent(rate: Option<f64>) -> String {
+    rate.map_or("-".to_string(), |rate| format!("{:.1}%", rate * 100.0))
+}
+
+fn same_team_summary(totals: &[PlayerTotals]) -> String {
+    let Some(first) = totals.first().filter(|first| first.games > 0) else {
+        return "No stored games on the same team".to_string();
+    };
+    let mut lines = vec![format!(
+        "{}W - {}L, {} win rate",
+        first.wins,
+        first.games - first.wins,
+        percent(first.win_rate())
+    )];
+    lines.extend(
+        totals
+            .iter()
+            .map(|totals| format!("{}: {:.2} K/D", totals.name, totals.kdr())),
+    );
+    lines.join("\n")
+}
+
+fn opposite_summary(totals: &[PlayerTotals]) -> String {
+    if totals.first().is_none_or(|first| first.games == 0) {
+        return "No stored games against each other".to_string();
+    }
+    totals
+        .iter()
+        .map(|totals| {
+            format!(
+                "{}: {} wins ({}), {:.2} K/D",
+                totals.name,
+                totals.wins,
+                percent(totals.win_rate()),
+                totals.kdr()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[async_trait]
+impl KrunkerCommand for Together {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "together",
+            description: "Find ranked matches players shared, with and against each other",
+            usage: "&together <player> <player> [player...]",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if args.len() < 2 || args.len() > MAX_PLAYERS {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("{} (2 to {} players)", USAGE, MAX_PLAYERS),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let mut usernames: Vec<String> = Vec::new();
+        for arg in &args {
+            let Some(username) = resolve_player(pool, msg, Some(arg)).await? else {
+                msg.channel_id
+                    .say(&ctx.http, format!("{} has no linked Krunker account.", arg))
+                    .await?;
+                return Ok(());
+            };
+            if usernames.iter().any(|u| u.eq_ignore_ascii_case(&username)) {
+                msg.channel_id
+                    .say(&ctx.http, format!("{} is listed twice.", username))
+                    .await?;
+                return Ok(());
+            }
+            usernames.push(username);
+        }
+
+        // matches in every player's recent ranked list were shared; they're
+        // fetched so their teams are known, then everything comes from the store
+        let mut recent: Option<HashSet<i64>> = None;
+        let mut notes = Vec::new();
+        for username in &usernames {
+            match track_api(
+                "get_player_matches",
+                krunker_api.get_player_matches(username, None, None),
+            )
+            .await
+            {
+                Ok(data) => {
+                    let ids: HashSet<i64> = data
+                        .pmr_matches
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|m| m.pm_match_id as i64)
+                        .collect();
+                    recent = Some(match recent {
+                        Some(shared) => shared.intersection(&ids).copied().collect(),
+                        None => ids,
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!(%username, "Failed to fetch ranked matches: {}", e);
+                    notes.push(format!(
+                        "Couldn't check {}'s recent matches, only stored ones are counted.",
+                        username
+                    ));
+                    recent = Some(HashSet::new());
+                }
+            }
+        }
+
+        let mut to_fetch: Vec<i64> = recent.unwrap_or_default().into_iter().collect();
+        to_fetch.sort_unstable_by(|a, b| b.cmp(a));
+        if store_missing(pool, krunker_api, &to_fetch, MAX_LOOKUPS).await? > 0 {
+            notes
+                .push("Some recent matches weren't looked up yet, try again in a bit.".to_string());
+        }
+
+        let names: Vec<&str> = usernames.iter().map(String::as_str).collect();
+        let ids = queries::get_shared_match_ids(pool, &names).await?;
+        let mut matches = Vec::new();
+        for &match_id in ids.iter().take(MAX_SHARED) {
+            matches.push(queries::get_match_participants(pool, match_id).await?);
+        }
+        let together = analyze(&names, &matches);
+
+        let title = usernames.join(" & ");
+        if together.matches.is_empty() {
+            let mut reply = format!("No shared ranked matches found for {}.", title);
+            for note in &notes {
+                reply.push('\n');
+                reply.push_str(note);
+            }
+            msg.channel_id.say(&ctx.http, reply).await?;
+            return Ok(());
+        }
+
+        let recent_lines: Vec<String> = together
+            .matches
+            .iter()
+            .take(RECENT_SHOWN)
+            .map(|shared| {
+                if shared.same_team {
+                    let result = if shared.winners.is_empty() {
+                        "lost"
+                    } else {
+                        "won"
+                    };
+                    format!("`{}` same team, {}", shared.match_id, result)
+                } else if shared.winners.is_empty() {
+                    format!("`{}` opposite teams, neither won", shared.match_id)
+                } else {
+                    format!(
+                        "`{}` opposite teams, {} won",
+                        shared.match_id,
+                        shared.winners.join(" & ")
+                    )
+                }
+            })
+            .collect();
+
+        let mut description = format!("{} shared ranked matches", together.matches.len());
+        for note in &notes {
+            description.push('\n');
+            description.push_str(note);
+        }
+
+        let config = crate::bot::state::config(ctx).await;
+        let embed = CreateEmbed::new()
+            .title(title)
+            .description(description)
+            .field(
+                format!("Same Team ({})", together.same_team_games()),
+                same_team_summary(&together.same_team),
+                false,
+            )
+            .field(
+                format!("Opposite Teams ({})", together.opposite_games()),
+                opposite_summary(&together.opposite),
+                false,
+            )
+            .field("Recent", recent_lines.join("\n"), false)
+            .footer(CreateEmbedFooter::new(format!(
+                "Full match: {}sm <match id>",
+                config.bot.prefix
+            )))
+            .color(config.colors.stats);
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+        Ok(())
+    }
+}
@@ -2,10 +2,11 @@ use async_trait::async_trait;
 use krunker_rs::Client as KrunkerClient;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{CommandMetadata, KrunkerCommand};
+use crate::database::models::LinkEventKind;
 use crate::database::queries;
 
 pub struct Unlink;
@@ -15,8 +16,8 @@ impl KrunkerCommand for Unlink {
     fn metadata(&self) -> CommandMetadata {
         CommandMetadata {
             name: "unlink",
-            description: "Unlink your Krunker account from your Discord account",
-            usage: "&unlink",
+            description: "Unlink one of your Krunker accounts from your Discord account",
+            usage: "&unlink [username]",
             aliases: &[],
         }
     }
@@ -26,24 +27,63 @@ impl KrunkerCommand for Unlink {
         ctx: &Context,
         msg: &Message,
         _krunker_api: &Arc<KrunkerClient>,
-        _args: Vec<&str>,
-        pool: &SqlitePool,
+        args: Vec<&str>,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let discord_id = msg.author.id.to_string();
 
-        if !queries::user_exists(pool, &discord_id).await? {
+        let accounts = queries::get_users_by_discord_id(pool, &discord_id).await?;
+        if accounts.is_empty() {
             msg.channel_id
                 .say(&ctx.http, "You are not linked to any Krunker account.")
                 .await?;
             return Ok(());
         }
 
-        queries::delete_user(pool, &discord_id).await?;
+        // with several accounts linked, make the user say which one
+        let username = match (args.first(), accounts.as_slice()) {
+            (Some(name), _) => name.to_string(),
+            (None, [only]) => only.username.clone(),
+            (None, _) => {
+                msg.channel_id
+                    .say(
+                        &ctx.http,
+                        "You have several accounts linked. Usage: &unlink <username> (see `&accounts`)",
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let mut tx = pool.begin().await?;
+        if !queries::delete_user_account(&mut tx, &discord_id, &username).await? {
+            msg.channel_id
+                .say(
+                    &ctx.http,
+                    format!("**{}** isn't linked to your Discord account.", username),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        queries::record_link_event(
+            &mut *tx,
+            &discord_id,
+            &username,
+            LinkEventKind::Unlinked,
+            &discord_id,
+            None,
+        )
+        .await?;
+        tx.commit().await?;
 
         msg.channel_id
             .say(
                 &ctx.http,
-                "✅ Successfully unlinked your account. You can now link a new one with `&link <username>`.",
+                format!(
+                    "✅ Successfully unlinked **{}**. You can link another account with `&link <username>`.",
+                    username
+                ),
             )
             .await?;
 
@@ -2,7 +2,7 @@ use async_trait::async_trait;
 use krunker_rs::Client as KrunkerClient;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 use std::sync::Arc;
 
 use super::{CommandMetadata, KrunkerCommand};
@@ -26,15 +26,39 @@ impl KrunkerCommand for Verify {
         msg: &Message,
         krunker_api: &Arc<KrunkerClient>,
         _args: Vec<&str>,
-        pool: &SqlitePool,
+        pool: &AnyPool,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        use crate::verification::flow::{check_verification, complete_verification, VerificationResult};
+        use crate::identity::resolver::lookup_krunker_id;
+        use crate::verification::flow::{
+            VerificationResult, check_verification, complete_verification,
+        };
 
         let discord_id = msg.author.id.to_string();
+        let service = crate::bot::state::verification_service(ctx).await;
 
-        match check_verification(pool, krunker_api, &discord_id).await {
+        match check_verification(&service, pool, krunker_api.as_ref(), &discord_id).await {
             Ok(VerificationResult::Success { krunker_username }) => {
-                complete_verification(pool, &discord_id, &krunker_username).await?;
+                // the background resolver fills the identity in later if this fails
+                let krunker_id = match lookup_krunker_id(krunker_api, &krunker_username).await {
+                    Ok(id) => id,
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to resolve identity for {}: {}",
+                            krunker_username,
+                            e
+                        );
+                        None
+                    }
+                };
+
+                complete_verification(
+                    &service,
+                    pool,
+                    &discord_id,
+                    &krunker_username,
+                    krunker_id.as_deref(),
+                )
+                .await?;
                 msg.channel_id
                     .say(
                         &ctx.http,
@@ -53,8 +77,11 @@ impl KrunkerCommand for Verify {
                 let response = format!(
                     "❌ Verification code not found for **{}**.\n\n\
                     Make sure you've posted this exactly: `{}`\n\
-                    Attempts: {}/5",
-                    krunker_username, code, attempts
+                    Attempts: {}/{}",
+                    krunker_username,
+                    code,
+                    attempts,
+                    service.settings().max_attempts
                 );
                 msg.channel_id.say(&ctx.http, response).await?;
             }
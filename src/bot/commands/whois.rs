@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateEmbed, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use serenity::utils::parse_user_mention;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::LinkEventKind;
+use crate::database::queries;
+
+pub struct WhoIs;
+
+#[async_trait]
+impl KrunkerCommand for WhoIs {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "whois",
+            description: "(Moderators) Show the Krunker accounts linked to a Discord user",
+            usage: "&whois <@user>",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let colors = crate::bot::state::config(ctx).await.colors.clone();
+        if !is_moderator(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+            return Ok(());
+        }
+
+        let user_id = match args.first().and_then(|a| parse_user_mention(a)) {
+            Some(id) => id,
+            None => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &whois <@user>")
+                    .await?;
+                return Ok(());
+            }
+        };
+        let discord_id = user_id.to_string();
+
+        let mut embed = CreateEmbed::new()
+            .title("Who Is")
+            .field("Discord", format!("<@{}>", discord_id), true)
+            .color(colors.moderation);
+
+        let accounts = queries::get_users_by_discord_id(pool, &discord_id).await?;
+        queries::record_link_event(
+            pool,
+            &discord_id,
+            accounts
+                .first()
+                .map_or("", |account| account.username.as_str()),
+            LinkEventKind::LookedUp,
+            &msg.author.id.to_string(),
+            Some("whois"),
+        )
+        .await?;
+
+        if accounts.is_empty() {
+            embed = embed.field("Krunker", "Not linked", true);
+        } else {
+            let lines = accounts
+                .iter()
+                .map(|account| {
+                    format!(
+                        "{} {} (linked <t:{}:f>){}",
+                        if account.is_primary { "⭐" } else { "•" },
+                        account.username,
+                        account.day_created,
+                        match account.stale_since {
+                            Some(since) => format!(" ⚠️ stale since <t:{}:d>", since),
+                            None => String::new(),
+                        }
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            embed = embed.field("Krunker", lines, false);
+        }
+
+        if let Some(verification) =
+            queries::get_verification_by_discord_id(pool, &discord_id, Utc::now().timestamp())
+                .await?
+        {
+            embed = embed.field(
+                "Pending Verification",
+                format!(
+                    "{} (attempts: {}, expires <t:{}:R>)",
+                    verification.krunker_username, verification.attempts, verification.expires_at
+                ),
+                false,
+            );
+        } else if let Some(verification) =
+            queries::get_latest_verification(pool, &discord_id).await?
+        {
+            let history = queries::get_verification_history(pool, verification.id).await?;
+            let reason = history
+                .last()
+                .and_then(|change| change.reason.as_deref())
+                .map(|reason| format!(": {}", reason))
+                .unwrap_or_default();
+
+            embed = embed.field(
+                "Last Verification",
+                format!(
+                    "{} ({}{}, started <t:{}:R>)",
+                    verification.krunker_username,
+                    verification.status,
+                    reason,
+                    verification.created_at
+                ),
+                false,
+            );
+        }
+
+        msg.channel_id
+            .send_message(&ctx.http, CreateMessage::new().embed(embed))
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use krunker_rs::Client as KrunkerClient;
+use serenity::all::{CreateAllowedMentions, CreateMessage};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+use sqlx::AnyPool;
+use std::sync::Arc;
+
+use super::{CommandMetadata, KrunkerCommand};
+use crate::bot::permissions::{MODERATOR_ONLY, is_moderator};
+use crate::database::models::LinkEventKind;
+use crate::database::queries;
+
+pub struct WhoOwns;
+
+#[async_trait]
+impl KrunkerCommand for WhoOwns {
+    fn metadata(&self) -> CommandMetadata {
+        CommandMetadata {
+            name: "whoowns",
+            description: "(Moderators) Show which Discord user a Krunker account is linked to",
+            usage: "&whoowns <krunker_name>",
+            aliases: &[],
+        }
+    }
+
+    async fn execute(
+        &self,
+        ctx: &Context,
+        msg: &Message,
+        _krunker_api: &Arc<KrunkerClient>,
+        args: Vec<&str>,
+        pool: &AnyPool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if !is_moderator(ctx, msg).await? {
+            msg.channel_id.say(&ctx.http, MODERATOR_ONLY).await?;
+            return Ok(());
+        }
+
+        let username = match args.first() {
+            Some(u) if !u.is_empty() => *u,
+            _ => {
+                msg.channel_id
+                    .say(&ctx.http, "Usage: &whoowns <krunker_name>")
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        let owner = queries::get_user_by_username(pool, username).await?;
+        queries::record_link_event(
+            pool,
+            owner.as_ref().map_or("", |user| user.discord_id.as_str()),
+            username,
+            LinkEventKind::LookedUp,
+            &msg.author.id.to_string(),
+            Some("whoowns"),
+        )
+        .await?;
+
+        let response = match owner {
+            Some(user) => format!(
+                "**{}** is linked to <@{}> since <t:{}:f>.",
+                user.username, user.discord_id, user.day_created
+            ),
+            None => format!("**{}** is not linked to any Discord account.", username),
+        };
+
+        // don't ping the owner just because a moderator looked them up
+        msg.channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .content(response)
+                    .allowed_mentions(CreateAllowedMentions::new()),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
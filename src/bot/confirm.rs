@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use serenity::all::{
+    ButtonStyle, CreateActionRow, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage,
+};
+use serenity::model::channel::Message;
+use serenity::prelude::*;
+
+const CONFIRM_TIMEOUT_SECONDS: u64 = 30;
+
+const CONFIRM_ID: &str = "confirm";
+const CANCEL_ID: &str = "cancel";
+
+/// Posts `prompt` with Confirm/Cancel buttons and waits for the author of `msg`
+/// to press one. Returns false on cancel or timeout.
+pub async fn confirm(
+    ctx: &Context,
+    msg: &Message,
+    prompt: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let buttons = vec![
+        CreateButton::new(CONFIRM_ID)
+            .label("Confirm")
+            .style(ButtonStyle::Danger),
+        CreateButton::new(CANCEL_ID)
+            .label("Cancel")
+            .style(ButtonStyle::Secondary),
+    ];
+
+    let mut prompt_msg = msg
+        .channel_id
+        .send_message(
+            &ctx.http,
+            CreateMessage::new()
+                .content(prompt)
+                .components(vec![CreateActionRow::Buttons(buttons)]),
+        )
+        .await?;
+
+    let interaction = prompt_msg
+        .await_component_interaction(&ctx.shard)
+        .author_id(msg.author.id)
+        .timeout(Duration::from_secs(CONFIRM_TIMEOUT_SECONDS))
+        .await;
+
+    match interaction {
+        Some(interaction) => {
+            let confirmed = interaction.data.custom_id == CONFIRM_ID;
+            let outcome = if confirmed {
+                "✅ Confirmed."
+            } else {
+                "❌ Cancelled."
+            };
+
+            interaction
+                .create_response(
+                    &ctx.http,
+                    CreateInteractionResponse::UpdateMessage(
+                        CreateInteractionResponseMessage::new()
+                            .content(format!("{}\n\n{}", prompt, outcome))
+                            .components(vec![]),
+                    ),
+                )
+                .await?;
+
+            Ok(confirmed)
+        }
+        None => {
+            prompt_msg
+                .edit(
+                    ctx,
+                    EditMessage::new()
+                        .content(format!("{}\n\n⌛ Timed out, nothing was changed.", prompt))
+                        .components(vec![]),
+                )
+                .await?;
+
+            Ok(false)
+        }
+    }
+}
@@ -1,6 +1,9 @@
 // std stuff
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
+
+use tracing::Instrument;
 
 // krunker API wrapper
 use krunker_rs::Client as KrunkerClient;
@@ -10,20 +13,24 @@ use krunker_rs::Client as KrunkerClient;
 use serenity::async_trait;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
-use sqlx::SqlitePool;
+use sqlx::AnyPool;
 
 // internal
 use super::commands;
+use super::usage;
+use crate::lifecycle::Lifecycle;
+use crate::metrics::metrics;
 
 #[allow(dead_code)]
 pub struct Handler {
     pub krunker_api: Arc<KrunkerClient>,
-    pub pool: SqlitePool,
+    pub pool: AnyPool,
     pub commands: HashMap<String, Arc<dyn commands::KrunkerCommand>>,
+    pub lifecycle: Lifecycle,
 }
 
 impl Handler {
-    pub fn new(krunker_api: Arc<KrunkerClient>, pool: SqlitePool) -> Self {
+    pub fn new(krunker_api: Arc<KrunkerClient>, pool: AnyPool, lifecycle: Lifecycle) -> Self {
         let mut commands_map = HashMap::new();
 
         for cmd in commands::all_commands() {
@@ -38,10 +45,16 @@ impl Handler {
             krunker_api,
             pool,
             commands: commands_map,
+            lifecycle,
         }
     }
 }
 
+/// Short random id to tie together every log line from one command
+fn request_id() -> String {
+    format!("{:08x}", rand::random::<u32>())
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
@@ -49,30 +62,79 @@ impl EventHandler for Handler {
             return;
         }
 
-        if !msg.content.starts_with("&") {
+        let config = super::state::config(&ctx).await;
+        let Some(content) = msg.content.strip_prefix(config.bot.prefix.as_str()) else {
             return;
-        }
-
-        tracing::info!(
-            user = %msg.author.name,
-            user_id = %msg.author.id,
-            command = %msg.content,
-            "Command received"
-        );
+        };
 
-        let content = &msg.content[1..];
         let mut parts = content.split_whitespace();
         let command = parts.next().unwrap_or("");
         let args: Vec<&str> = parts.collect();
 
         if let Some(cmd) = self.commands.get(command) {
-            if let Err(why) = cmd.execute(&ctx, &msg, &self.krunker_api, args, &self.pool).await {
-                tracing::error!("Error executing command {}: {:?}", command, why);
-                let _ = msg.channel_id.say(&ctx.http, format!("Error: {}", why)).await;
+            let name = cmd.metadata().name;
+            // everything the command logs, including API and database calls, nests under this
+            let span = tracing::info_span!(
+                "command",
+                request_id = %request_id(),
+                guild = msg.guild_id.map(|id| id.get()),
+                channel = %msg.channel_id,
+                user = %msg.author.id,
+                command = name,
+                outcome = tracing::field::Empty,
+            );
+
+            async {
+                tracing::debug!(content = %msg.content, "Command received");
+
+                // held until the command finishes so shutdown can wait for it
+                let Some(_work) = self.lifecycle.begin() else {
+                    tracing::Span::current().record("outcome", "rejected");
+                    let _ = msg
+                        .channel_id
+                        .say(
+                            &ctx.http,
+                            "The bot is shutting down, try again in a moment.",
+                        )
+                        .await;
+                    return;
+                };
+
+                let started = Instant::now();
+                let result = cmd
+                    .execute(&ctx, &msg, &self.krunker_api, args, &self.pool)
+                    .await;
+                let elapsed = started.elapsed();
+                metrics().record_command(name, result.is_ok(), elapsed);
+
+                // recorded while the work guard is held, so shutdown waits for the write
+                let error = result.as_ref().err().map(|why| why.as_ref());
+                usage::record(&self.pool, name, &msg, elapsed, error).await;
+
+                match result {
+                    Ok(()) => {
+                        tracing::Span::current().record("outcome", "ok");
+                        tracing::info!(elapsed_ms = elapsed.as_millis() as u64, "Command finished");
+                    }
+                    Err(why) => {
+                        tracing::Span::current().record("outcome", "error");
+                        tracing::error!(
+                            elapsed_ms = elapsed.as_millis() as u64,
+                            error = %why,
+                            "Command failed"
+                        );
+                        let _ = msg
+                            .channel_id
+                            .say(&ctx.http, format!("Error: {}", why))
+                            .await;
+                    }
+                }
             }
+            .instrument(span)
+            .await;
         } else {
             if let Err(why) = msg.channel_id.say(&ctx.http, "Not a valid command!").await {
-                tracing::error!("Error sending message: {why:?}");
+                tracing::error!(error = %why, "Error sending message");
             }
         }
     }
@@ -1,2 +1,6 @@
 pub mod commands;
+pub mod confirm;
 pub mod handler;
+pub mod permissions;
+pub mod state;
+pub mod usage;
@@ -0,0 +1,67 @@
+use serenity::model::Permissions;
+use serenity::model::channel::Message;
+use serenity::model::id::RoleId;
+use serenity::prelude::*;
+
+/// Reply used when a non-moderator tries a moderation command
+pub const MODERATOR_ONLY: &str = "You need the Manage Server permission to use this command.";
+
+/// Reply used when someone other than the bot's owner tries an owner command
+pub const OWNER_ONLY: &str = "Only the bot's owner can use this command.";
+
+/// Returns true if the message author is allowed to use moderation commands in
+/// the guild the message was sent in (guild owner, Administrator or Manage Server).
+/// Always false in DMs.
+pub async fn is_moderator(
+    ctx: &Context,
+    msg: &Message,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let guild_id = match msg.guild_id {
+        Some(id) => id,
+        None => return Ok(false),
+    };
+
+    let guild = guild_id.to_partial_guild(&ctx.http).await?;
+    if guild.owner_id == msg.author.id {
+        return Ok(true);
+    }
+
+    let member = guild_id.member(&ctx.http, msg.author.id).await?;
+
+    // @everyone shares its id with the guild
+    let everyone = guild
+        .roles
+        .get(&RoleId::new(guild_id.get()))
+        .map(|role| role.permissions)
+        .unwrap_or_default();
+
+    let permissions: Permissions = member
+        .roles
+        .iter()
+        .filter_map(|id| guild.roles.get(id))
+        .fold(everyone, |acc, role| acc | role.permissions);
+
+    Ok(permissions.administrator() || permissions.manage_guild())
+}
+
+/// Returns true if the message author owns the bot's Discord application, or is a
+/// member of the team that does
+pub async fn is_bot_owner(
+    ctx: &Context,
+    msg: &Message,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let info = ctx.http.get_current_application_info().await?;
+
+    if info
+        .owner
+        .as_ref()
+        .is_some_and(|owner| owner.id == msg.author.id)
+    {
+        return Ok(true);
+    }
+    Ok(info.team.is_some_and(|team| {
+        team.members
+            .iter()
+            .any(|member| member.user.id == msg.author.id)
+    }))
+}
@@ -0,0 +1,90 @@
+// shared state that lives in serenity's TypeMap, for things commands need
+// beyond the api client and pool they're handed
+
+use serenity::prelude::{Context, RwLock, TypeMap, TypeMapKey};
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::jobs::Scheduler;
+use crate::lifecycle::Lifecycle;
+use crate::matchmaking::Queues;
+use crate::verification::service::VerificationService;
+
+/// The running config, swapped out wholesale by &reloadconfig
+pub struct ConfigKey;
+
+impl TypeMapKey for ConfigKey {
+    type Value = Arc<Config>;
+}
+
+pub async fn config(ctx: &Context) -> Arc<Config> {
+    config_from(&ctx.data).await
+}
+
+/// Same as `config`, for background tasks that hold the client's data but no Context
+pub async fn config_from(data: &RwLock<TypeMap>) -> Arc<Config> {
+    data.read()
+        .await
+        .get::<ConfigKey>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub struct VerificationServiceKey;
+
+impl TypeMapKey for VerificationServiceKey {
+    type Value = VerificationService;
+}
+
+/// The verification service registered at startup, falling back to the system one
+pub async fn verification_service(ctx: &Context) -> VerificationService {
+    ctx.data
+        .read()
+        .await
+        .get::<VerificationServiceKey>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The maintenance job scheduler, for &jobs
+pub struct SchedulerKey;
+
+impl TypeMapKey for SchedulerKey {
+    type Value = Arc<Scheduler>;
+}
+
+pub async fn scheduler(ctx: &Context) -> Option<Arc<Scheduler>> {
+    ctx.data.read().await.get::<SchedulerKey>().cloned()
+}
+
+/// The bot's lifecycle, for work a command hands off to a background task
+pub struct LifecycleKey;
+
+impl TypeMapKey for LifecycleKey {
+    type Value = Lifecycle;
+}
+
+pub async fn lifecycle(ctx: &Context) -> Lifecycle {
+    ctx.data
+        .read()
+        .await
+        .get::<LifecycleKey>()
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// The &queue queues, made on first use
+pub struct QueuesKey;
+
+impl TypeMapKey for QueuesKey {
+    type Value = Arc<Mutex<Queues>>;
+}
+
+pub async fn queues(ctx: &Context) -> Arc<Mutex<Queues>> {
+    ctx.data
+        .write()
+        .await
+        .entry::<QueuesKey>()
+        .or_insert_with(Arc::default)
+        .clone()
+}
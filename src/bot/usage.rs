@@ -0,0 +1,66 @@
+// command usage analytics: one row per dispatched command, read back by &botstats
+
+use serenity::model::channel::Message;
+use sqlx::AnyPool;
+use std::time::Duration;
+
+use crate::database::queries;
+
+/// Commands that aren't recorded. &forgetme would otherwise leave a fresh row
+/// behind for the user it just deleted.
+const UNRECORDED_COMMANDS: &[&str] = &["forgetme"];
+
+/// Stores one dispatch of `command`. Failures are logged, never surfaced to the user.
+pub async fn record(
+    pool: &AnyPool,
+    command: &str,
+    msg: &Message,
+    elapsed: Duration,
+    error: Option<&(dyn std::error::Error + Send + Sync + 'static)>,
+) {
+    if UNRECORDED_COMMANDS.contains(&command) {
+        return;
+    }
+
+    let guild_id = msg.guild_id.map(|id| id.to_string());
+    let result = queries::record_command_usage(
+        pool,
+        command,
+        guild_id.as_deref(),
+        &msg.author.id.to_string(),
+        elapsed.as_millis() as i64,
+        error.map(error_class),
+        chrono::Utc::now().timestamp(),
+    )
+    .await;
+    if let Err(e) = result {
+        tracing::warn!("Failed to record command usage: {}", e);
+    }
+}
+
+/// A coarse bucket for a command error, so failures can be grouped without
+/// storing user-facing messages
+pub fn error_class(error: &(dyn std::error::Error + Send + Sync + 'static)) -> &'static str {
+    if error.downcast_ref::<sqlx::Error>().is_some() {
+        "database"
+    } else if error.downcast_ref::<serenity::Error>().is_some() {
+        "discord"
+    } else {
+        // commands report bad input and the like as plain string errors
+        "other"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_class() {
+        let db: Box<dyn std::error::Error + Send + Sync> = Box::new(sqlx::Error::RowNotFound);
+        assert_eq!(error_class(db.as_ref()), "database");
+
+        let plain: Box<dyn std::error::Error + Send + Sync> = "Player not found".into();
+        assert_eq!(error_class(plain.as_ref()), "other");
+    }
+}
@@ -0,0 +1,963 @@
+use serde::Deserialize;
+use std::fmt;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::database::Backend;
+use crate::identity::resolver::{
+    RESOLVE_BATCH_SIZE, RESOLVE_DELAY_MILLIS, RESOLVE_MAX_AGE_SECONDS,
+};
+use crate::matchmaking::balance::MAX_TEAM_SIZE;
+use crate::verification::flow::{
+    LINK_COOLDOWN_SECONDS, LOCKOUT_SECONDS, MAX_FAILED_SESSIONS, MAX_VERIFICATION_ATTEMPTS,
+    VERIFICATION_EXPIRY_SECONDS,
+};
+use crate::verification::service::VerificationSettings;
+
+/// Read when `CONFIG_PATH` isn't set. Unlike an explicit path it's fine for it to be missing.
+pub const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Everything tunable about the bot except the tokens, which stay in the environment
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub bot: BotConfig,
+    pub database: DatabaseConfig,
+    pub logging: LoggingConfig,
+    pub verification: VerificationConfig,
+    pub identity: IdentityConfig,
+    pub colors: ColorConfig,
+    pub metrics: MetricsConfig,
+    pub analytics: AnalyticsConfig,
+    pub jobs: JobsConfig,
+    pub milestones: MilestonesConfig,
+    pub queue: QueueConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+    pub prefix: String,
+}
+
+impl Default for BotConfig {
+    fn default() -> Self {
+        Self {
+            prefix: "&".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: "sqlite:bot.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    /// One of trace, debug, info, warn, error
+    pub level: String,
+    pub format: LogFormat,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: LogFormat::Text,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// One JSON object per line, for log collectors
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected text or json".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerificationConfig {
+    pub expiry_seconds: i64,
+    pub max_attempts: i32,
+    /// Minimum time between two &link calls from the same user
+    pub cooldown_seconds: i64,
+    /// Failed sessions in a row before the user is locked out
+    pub max_failed_sessions: i32,
+    pub lockout_seconds: i64,
+}
+
+impl Default for VerificationConfig {
+    fn default() -> Self {
+        Self {
+            expiry_seconds: VERIFICATION_EXPIRY_SECONDS,
+            max_attempts: MAX_VERIFICATION_ATTEMPTS,
+            cooldown_seconds: LINK_COOLDOWN_SECONDS,
+            max_failed_sessions: MAX_FAILED_SESSIONS,
+            lockout_seconds: LOCKOUT_SECONDS,
+        }
+    }
+}
+
+/// How the resolve_identities job re-checks links, read at startup only
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdentityConfig {
+    /// Links resolved more recently than this are skipped
+    pub max_age_seconds: i64,
+    /// Links checked per run
+    pub batch_size: i64,
+    /// Pause between API calls within a run
+    pub delay_millis: u64,
+}
+
+impl Default for IdentityConfig {
+    fn default() -> Self {
+        Self {
+            max_age_seconds: RESOLVE_MAX_AGE_SECONDS,
+            batch_size: RESOLVE_BATCH_SIZE,
+            delay_millis: RESOLVE_DELAY_MILLIS,
+        }
+    }
+}
+
+/// Embed colors as 0xRRGGBB
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorConfig {
+    /// Help and account listings
+    pub info: u32,
+    /// Player and match stats
+    pub stats: u32,
+    /// Ranked leaderboards
+    pub leaderboard: u32,
+    /// Moderator tools
+    pub moderation: u32,
+}
+
+impl Default for ColorConfig {
+    fn default() -> Self {
+        Self {
+            info: 0x3498db,
+            stats: 0x00ff00,
+            leaderboard: 0x0000ff,
+            moderation: 0xe67e22,
+        }
+    }
+}
+
+/// The optional HTTP server for `/healthz` and `/metrics`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub listen: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: "127.0.0.1:9898".to_string(),
+        }
+    }
+}
+
+/// Command usage recording for &botstats
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyticsConfig {
+    /// Usage rows older than this are deleted
+    pub retention_days: i64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self { retention_days: 90 }
+    }
+}
+
+/// Which milestones are announced. Read on every check, so &reloadconfig applies it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MilestonesConfig {
+    /// Levels that are a multiple of this are announced
+    pub level_step: i64,
+    pub kr: Vec<i64>,
+    pub games: Vec<i64>,
+}
+
+impl Default for MilestonesConfig {
+    fn default() -> Self {
+        Self {
+            level_step: 1,
+            kr: vec![10_000, 50_000, 100_000, 250_000, 500_000, 1_000_000],
+            games: vec![100, 500, 1_000, 2_500, 5_000, 10_000],
+        }
+    }
+}
+
+/// In-house lobbies made by &queue. Read on every join, so &reloadconfig applies it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QueueConfig {
+    /// Players per team, a lobby starts once twice this many are queued
+    pub team_size: usize,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { team_size: 4 }
+    }
+}
+
+/// Schedules for the maintenance jobs, by job name
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobsConfig {
+    pub expire_verifications: JobConfig,
+    pub usage_retention: JobConfig,
+    pub resolve_identities: JobConfig,
+    pub match_feed: JobConfig,
+    pub milestones: JobConfig,
+    pub profile_snapshots: JobConfig,
+    pub ratings: JobConfig,
+}
+
+impl JobsConfig {
+    pub fn get(&self, name: &str) -> Option<&JobConfig> {
+        match name {
+            "expire_verifications" => Some(&self.expire_verifications),
+            "usage_retention" => Some(&self.usage_retention),
+            "resolve_identities" => Some(&self.resolve_identities),
+            "match_feed" => Some(&self.match_feed),
+            "milestones" => Some(&self.milestones),
+            "profile_snapshots" => Some(&self.profile_snapshots),
+            "ratings" => Some(&self.ratings),
+            _ => None,
+        }
+    }
+
+    fn all(&self) -> [(&'static str, &JobConfig); 7] {
+        [
+            ("expire_verifications", &self.expire_verifications),
+            ("usage_retention", &self.usage_retention),
+            ("resolve_identities", &self.resolve_identities),
+            ("match_feed", &self.match_feed),
+            ("milestones", &self.milestones),
+            ("profile_snapshots", &self.profile_snapshots),
+            ("ratings", &self.ratings),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JobConfig {
+    pub enabled: bool,
+    /// How often the job runs, like "90s", "15m", "6h" or "1d". The job's own
+    /// default when unset.
+    pub every: Option<String>,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            every: None,
+        }
+    }
+}
+
+impl JobConfig {
+    /// `every` parsed, None when unset. `name` is the job's, for the error.
+    pub fn interval(&self, name: &str) -> Result<Option<Duration>, String> {
+        let Some(every) = &self.every else {
+            return Ok(None);
+        };
+        parse_interval(every).map(Some).map_err(|_| {
+            format!(
+                "jobs.{}.every must be a positive interval like \"15m\" or \"6h\", got {:?}",
+                name, every
+            )
+        })
+    }
+}
+
+/// Parses an interval like "90s", "15m", "6h" or "1d"
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    const EXPECTED: &str = "expected a number followed by s, m, h or d";
+    let value = value.trim();
+    let Some((number, seconds_per_unit)) =
+        [("s", 1), ("m", 60), ("h", 60 * 60), ("d", 24 * 60 * 60)]
+            .into_iter()
+            .find_map(|(unit, seconds)| value.strip_suffix(unit).map(|number| (number, seconds)))
+    else {
+        return Err(EXPECTED.to_string());
+    };
+    let number: u64 = number.parse().map_err(|_| EXPECTED.to_string())?;
+    if number == 0 {
+        return Err("must be longer than zero".to_string());
+    }
+    number
+        .checked_mul(seconds_per_unit)
+        .map(Duration::from_secs)
+        .ok_or_else(|| "is too long".to_string())
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    Env {
+        var: &'static str,
+        value: String,
+        message: String,
+    },
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(
+                    f,
+                    "couldn't read config file {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            ConfigError::Parse { path, message } => {
+                write!(f, "invalid config file {}: {}", path.display(), message)
+            }
+            ConfigError::Env {
+                var,
+                value,
+                message,
+            } => write!(f, "invalid value {:?} for {}: {}", value, var, message),
+            ConfigError::Invalid(problems) => {
+                write!(f, "invalid config:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Reads the config file, applies environment overrides and validates the result
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, required) = match std::env::var("CONFIG_PATH") {
+            Ok(path) => (PathBuf::from(path), true),
+            Err(_) => (PathBuf::from(DEFAULT_CONFIG_PATH), false),
+        };
+
+        let mut config = match std::fs::read_to_string(&path) {
+            Ok(contents) => Self::from_toml(&contents).map_err(|message| ConfigError::Parse {
+                path: path.clone(),
+                message,
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && !required => Self::default(),
+            Err(source) => return Err(ConfigError::Read { path, source }),
+        };
+
+        config.apply_env(|var| std::env::var(var).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_toml(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|e| e.to_string())
+    }
+
+    /// Overrides settings from `KRUNKER_BOT_*` variables, looked up through `lookup`
+    pub fn apply_env(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        if let Some(value) = lookup("KRUNKER_BOT_PREFIX") {
+            self.bot.prefix = value;
+        }
+        if let Some(value) = lookup("KRUNKER_BOT_DATABASE_URL") {
+            self.database.url = value;
+        }
+        if let Some(value) = lookup("KRUNKER_BOT_LOG_LEVEL") {
+            self.logging.level = value;
+        }
+        const LOG_FORMAT: &str = "KRUNKER_BOT_LOG_FORMAT";
+        if let Some(value) = lookup(LOG_FORMAT) {
+            self.logging.format = parse_env(LOG_FORMAT, value)?;
+        }
+
+        const METRICS_ENABLED: &str = "KRUNKER_BOT_METRICS_ENABLED";
+        if let Some(value) = lookup(METRICS_ENABLED) {
+            self.metrics.enabled = parse_env(METRICS_ENABLED, value)?;
+        }
+        if let Some(value) = lookup("KRUNKER_BOT_METRICS_LISTEN") {
+            self.metrics.listen = value;
+        }
+
+        const RETENTION: &str = "KRUNKER_BOT_ANALYTICS_RETENTION_DAYS";
+        if let Some(value) = lookup(RETENTION) {
+            self.analytics.retention_days = parse_env(RETENTION, value)?;
+        }
+
+        let jobs = [
+            (
+                "KRUNKER_BOT_JOBS_EXPIRE_VERIFICATIONS_ENABLED",
+                "KRUNKER_BOT_JOBS_EXPIRE_VERIFICATIONS_EVERY",
+                &mut self.jobs.expire_verifications,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_USAGE_RETENTION_ENABLED",
+                "KRUNKER_BOT_JOBS_USAGE_RETENTION_EVERY",
+                &mut self.jobs.usage_retention,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_RESOLVE_IDENTITIES_ENABLED",
+                "KRUNKER_BOT_JOBS_RESOLVE_IDENTITIES_EVERY",
+                &mut self.jobs.resolve_identities,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_MATCH_FEED_ENABLED",
+                "KRUNKER_BOT_JOBS_MATCH_FEED_EVERY",
+                &mut self.jobs.match_feed,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_MILESTONES_ENABLED",
+                "KRUNKER_BOT_JOBS_MILESTONES_EVERY",
+                &mut self.jobs.milestones,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_PROFILE_SNAPSHOTS_ENABLED",
+                "KRUNKER_BOT_JOBS_PROFILE_SNAPSHOTS_EVERY",
+                &mut self.jobs.profile_snapshots,
+            ),
+            (
+                "KRUNKER_BOT_JOBS_RATINGS_ENABLED",
+                "KRUNKER_BOT_JOBS_RATINGS_EVERY",
+                &mut self.jobs.ratings,
+            ),
+        ];
+        for (enabled, every, job) in jobs {
+            if let Some(value) = lookup(enabled) {
+                job.enabled = parse_env(enabled, value)?;
+            }
+            if let Some(value) = lookup(every) {
+                job.every = Some(value);
+            }
+        }
+
+        const LEVEL_STEP: &str = "KRUNKER_BOT_MILESTONES_LEVEL_STEP";
+        if let Some(value) = lookup(LEVEL_STEP) {
+            self.milestones.level_step = parse_env(LEVEL_STEP, value)?;
+        }
+        const MILESTONES_KR: &str = "KRUNKER_BOT_MILESTONES_KR";
+        if let Some(value) = lookup(MILESTONES_KR) {
+            self.milestones.kr = parse_env_list(MILESTONES_KR, value)?;
+        }
+        const MILESTONES_GAMES: &str = "KRUNKER_BOT_MILESTONES_GAMES";
+        if let Some(value) = lookup(MILESTONES_GAMES) {
+            self.milestones.games = parse_env_list(MILESTONES_GAMES, value)?;
+        }
+
+        const TEAM_SIZE: &str = "KRUNKER_BOT_QUEUE_TEAM_SIZE";
+        if let Some(value) = lookup(TEAM_SIZE) {
+            self.queue.team_size = parse_env(TEAM_SIZE, value)?;
+        }
+
+        const EXPIRY: &str = "KRUNKER_BOT_VERIFICATION_EXPIRY_SECONDS";
+        if let Some(value) = lookup(EXPIRY) {
+            self.verification.expiry_seconds = parse_env(EXPIRY, value)?;
+        }
+        const ATTEMPTS: &str = "KRUNKER_BOT_VERIFICATION_MAX_ATTEMPTS";
+        if let Some(value) = lookup(ATTEMPTS) {
+            self.verification.max_attempts = parse_env(ATTEMPTS, value)?;
+        }
+        const COOLDOWN: &str = "KRUNKER_BOT_VERIFICATION_COOLDOWN_SECONDS";
+        if let Some(value) = lookup(COOLDOWN) {
+            self.verification.cooldown_seconds = parse_env(COOLDOWN, value)?;
+        }
+        const FAILED_SESSIONS: &str = "KRUNKER_BOT_VERIFICATION_MAX_FAILED_SESSIONS";
+        if let Some(value) = lookup(FAILED_SESSIONS) {
+            self.verification.max_failed_sessions = parse_env(FAILED_SESSIONS, value)?;
+        }
+        const LOCKOUT: &str = "KRUNKER_BOT_VERIFICATION_LOCKOUT_SECONDS";
+        if let Some(value) = lookup(LOCKOUT) {
+            self.verification.lockout_seconds = parse_env(LOCKOUT, value)?;
+        }
+
+        const MAX_AGE: &str = "KRUNKER_BOT_IDENTITY_MAX_AGE_SECONDS";
+        if let Some(value) = lookup(MAX_AGE) {
+            self.identity.max_age_seconds = parse_env(MAX_AGE, value)?;
+        }
+        const BATCH_SIZE: &str = "KRUNKER_BOT_IDENTITY_BATCH_SIZE";
+        if let Some(value) = lookup(BATCH_SIZE) {
+            self.identity.batch_size = parse_env(BATCH_SIZE, value)?;
+        }
+        const DELAY: &str = "KRUNKER_BOT_IDENTITY_DELAY_MILLIS";
+        if let Some(value) = lookup(DELAY) {
+            self.identity.delay_millis = parse_env(DELAY, value)?;
+        }
+
+        let colors = [
+            ("KRUNKER_BOT_COLOR_INFO", &mut self.colors.info),
+            ("KRUNKER_BOT_COLOR_STATS", &mut self.colors.stats),
+            (
+                "KRUNKER_BOT_COLOR_LEADERBOARD",
+                &mut self.colors.leaderboard,
+            ),
+            ("KRUNKER_BOT_COLOR_MODERATION", &mut self.colors.moderation),
+        ];
+        for (var, color) in colors {
+            if let Some(value) = lookup(var) {
+                let hex = value.trim_start_matches('#').trim_start_matches("0x");
+                *color = u32::from_str_radix(hex, 16).map_err(|e| ConfigError::Env {
+                    var,
+                    value: value.clone(),
+                    message: e.to_string(),
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks every setting and reports all problems at once
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.bot.prefix.is_empty() || self.bot.prefix.chars().any(char::is_whitespace) {
+            problems.push(format!(
+                "bot.prefix must be non-empty and contain no whitespace, got {:?}",
+                self.bot.prefix
+            ));
+        }
+        if Backend::from_url(&self.database.url).is_none() {
+            problems.push(format!(
+                "database.url must be a sqlite: or postgres:// URL, got {:?}",
+                self.database.url
+            ));
+        }
+        if self.logging.level.parse::<tracing::Level>().is_err() {
+            problems.push(format!(
+                "logging.level must be one of trace, debug, info, warn, error, got {:?}",
+                self.logging.level
+            ));
+        }
+        if self.verification.expiry_seconds <= 0 {
+            problems.push(format!(
+                "verification.expiry_seconds must be positive, got {}",
+                self.verification.expiry_seconds
+            ));
+        }
+        if self.verification.max_attempts < 1 {
+            problems.push(format!(
+                "verification.max_attempts must be at least 1, got {}",
+                self.verification.max_attempts
+            ));
+        }
+        if self.verification.cooldown_seconds < 0 {
+            problems.push(format!(
+                "verification.cooldown_seconds can't be negative, got {}",
+                self.verification.cooldown_seconds
+            ));
+        }
+        if self.verification.max_failed_sessions < 1 {
+            problems.push(format!(
+                "verification.max_failed_sessions must be at least 1, got {}",
+                self.verification.max_failed_sessions
+            ));
+        }
+        if self.verification.lockout_seconds <= 0 {
+            problems.push(format!(
+                "verification.lockout_seconds must be positive, got {}",
+                self.verification.lockout_seconds
+            ));
+        }
+
+        if self.identity.max_age_seconds <= 0 {
+            problems.push(format!(
+                "identity.max_age_seconds must be positive, got {}",
+                self.identity.max_age_seconds
+            ));
+        }
+        if self.identity.batch_size < 1 {
+            problems.push(format!(
+                "identity.batch_size must be at least 1, got {}",
+                self.identity.batch_size
+            ));
+        }
+
+        if self.metrics.listen.parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "metrics.listen must be an address like 127.0.0.1:9898, got {:?}",
+                self.metrics.listen
+            ));
+        }
+
+        if self.analytics.retention_days <= 0 {
+            problems.push(format!(
+                "analytics.retention_days must be positive, got {}",
+                self.analytics.retention_days
+            ));
+        }
+
+        for (name, job) in self.jobs.all() {
+            if let Err(problem) = job.interval(name) {
+                problems.push(problem);
+            }
+        }
+
+        if self.milestones.level_step < 1 {
+            problems.push(format!(
+                "milestones.level_step must be at least 1, got {}",
+                self.milestones.level_step
+            ));
+        }
+        let thresholds = [
+            ("milestones.kr", &self.milestones.kr),
+            ("milestones.games", &self.milestones.games),
+        ];
+        for (name, values) in thresholds {
+            if values.iter().any(|&value| value <= 0) {
+                problems.push(format!("{} must all be positive, got {:?}", name, values));
+            }
+        }
+
+        if !(1..=MAX_TEAM_SIZE).contains(&self.queue.team_size) {
+            problems.push(format!(
+                "queue.team_size must be between 1 and {}, got {}",
+                MAX_TEAM_SIZE, self.queue.team_size
+            ));
+        }
+
+        let colors = [
+            ("colors.info", self.colors.info),
+            ("colors.stats", self.colors.stats),
+            ("colors.leaderboard", self.colors.leaderboard),
+            ("colors.moderation", self.colors.moderation),
+        ];
+        for (name, color) in colors {
+            if color > 0xffffff {
+                problems.push(format!(
+                    "{} must be at most 0xffffff, got {:#x}",
+                    name, color
+                ));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// Where to serve metrics, or None if the server is disabled
+    pub fn metrics_listen(&self) -> Option<SocketAddr> {
+        if !self.metrics.enabled {
+            return None;
+        }
+        self.metrics.listen.parse().ok()
+    }
+
+    pub fn verification_settings(&self) -> VerificationSettings {
+        VerificationSettings {
+            expiry_seconds: self.verification.expiry_seconds,
+            max_attempts: self.verification.max_attempts,
+            cooldown_seconds: self.verification.cooldown_seconds,
+            max_failed_sessions: self.verification.max_failed_sessions,
+            lockout_seconds: self.verification.lockout_seconds,
+        }
+    }
+
+    /// Takes the live settings from `new` and keeps the ones that are only read at
+    /// startup. Returns the merged config and which startup settings were changed.
+    pub fn reload(&self, new: Config) -> (Config, Vec<&'static str>) {
+        let mut restart_required = Vec::new();
+        if new.database != self.database {
+            restart_required.push("database.url");
+        }
+        if new.logging != self.logging {
+            restart_required.push("logging");
+        }
+        if new.metrics != self.metrics {
+            restart_required.push("metrics");
+        }
+        if new.analytics != self.analytics {
+            restart_required.push("analytics");
+        }
+        if new.identity != self.identity {
+            restart_required.push("identity");
+        }
+        if new.jobs != self.jobs {
+            restart_required.push("jobs");
+        }
+
+        let merged = Config {
+            database: self.database.clone(),
+            logging: self.logging.clone(),
+            metrics: self.metrics.clone(),
+            analytics: self.analytics.clone(),
+            identity: self.identity.clone(),
+            jobs: self.jobs.clone(),
+            ..new
+        };
+
+        (merged, restart_required)
+    }
+}
+
+fn parse_env<T>(var: &'static str, value: String) -> Result<T, ConfigError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value.parse().map_err(|e: T::Err| ConfigError::Env {
+        var,
+        message: e.to_string(),
+        value,
+    })
+}
+
+/// A comma separated list, e.g. "100,500,1000"
+fn parse_env_list<T>(var: &'static str, value: String) -> Result<Vec<T>, ConfigError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    value
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse().map_err(|e: T::Err| ConfigError::Env {
+                var,
+                message: e.to_string(),
+                value: value.clone(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |var| vars.get(var).cloned()
+    }
+
+    #[test]
+    fn test_empty_file_uses_defaults() {
+        let config = Config::from_toml("").unwrap();
+        assert_eq!(config, Config::default());
+        assert_eq!(config.bot.prefix, "&");
+        assert_eq!(config.database.url, "sqlite:bot.db");
+        assert_eq!(
+            config.verification.expiry_seconds,
+            VERIFICATION_EXPIRY_SECONDS
+        );
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_partial_file_keeps_other_defaults() {
+        let config = Config::from_toml(
+            r#"
+            [bot]
+            prefix = "!"
+
+            [colors]
+            stats = 0xff00ff
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.bot.prefix, "!");
+        assert_eq!(config.colors.stats, 0xff00ff);
+        assert_eq!(config.colors.info, ColorConfig::default().info);
+        assert_eq!(config.verification, VerificationConfig::default());
+    }
+
+    #[test]
+    fn test_log_format() {
+        let config = Config::from_toml("[logging]\nformat = \"json\"\n").unwrap();
+        assert_eq!(config.logging.format, LogFormat::Json);
+
+        assert!(Config::from_toml("[logging]\nformat = \"xml\"\n").is_err());
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let result = Config::from_toml("[verification]\nexpiry = 30\n");
+        assert!(result.unwrap_err().contains("expiry"));
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config = Config::from_toml("[verification]\nmax_attempts = 3\n").unwrap();
+        config
+            .apply_env(env(&[
+                ("KRUNKER_BOT_VERIFICATION_MAX_ATTEMPTS", "7"),
+                ("KRUNKER_BOT_DATABASE_URL", "sqlite:other.db"),
+                ("KRUNKER_BOT_COLOR_INFO", "#123456"),
+                ("KRUNKER_BOT_ANALYTICS_RETENTION_DAYS", "30"),
+                ("KRUNKER_BOT_MILESTONES_KR", "1000, 5000"),
+                ("KRUNKER_BOT_QUEUE_TEAM_SIZE", "5"),
+                ("KRUNKER_BOT_VERIFICATION_LOCKOUT_SECONDS", "600"),
+                ("KRUNKER_BOT_IDENTITY_BATCH_SIZE", "10"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.verification.max_attempts, 7);
+        assert_eq!(config.verification.lockout_seconds, 600);
+        assert_eq!(config.identity.batch_size, 10);
+        assert_eq!(config.database.url, "sqlite:other.db");
+        assert_eq!(config.colors.info, 0x123456);
+        assert_eq!(config.analytics.retention_days, 30);
+        assert_eq!(config.milestones.kr, vec![1000, 5000]);
+        assert_eq!(config.queue.team_size, 5);
+    }
+
+    #[test]
+    fn test_bad_env_value_names_the_variable() {
+        let mut config = Config::default();
+        let err = config
+            .apply_env(env(&[("KRUNKER_BOT_VERIFICATION_EXPIRY_SECONDS", "soon")]))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("KRUNKER_BOT_VERIFICATION_EXPIRY_SECONDS")
+        );
+    }
+
+    #[test]
+    fn test_validate_reports_every_problem() {
+        let config = Config::from_toml(
+            r#"
+            bot.prefix = ""
+            logging.level = "loud"
+            verification.max_attempts = 0
+            "#,
+        )
+        .unwrap();
+
+        match config.validate() {
+            Err(ConfigError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 3);
+                assert!(problems[0].starts_with("bot.prefix"));
+                assert!(problems[1].starts_with("logging.level"));
+                assert!(problems[2].starts_with("verification.max_attempts"));
+            }
+            other => panic!("Expected validation errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_reload_keeps_startup_settings() {
+        let running = Config::default();
+        let mut new = Config::default();
+        new.bot.prefix = "!".to_string();
+        new.database.url = "sqlite:elsewhere.db".to_string();
+        new.verification.cooldown_seconds = 5;
+        new.identity.batch_size = 10;
+
+        let (merged, restart_required) = running.reload(new);
+        assert_eq!(merged.bot.prefix, "!");
+        assert_eq!(merged.database.url, "sqlite:bot.db");
+        assert_eq!(merged.verification.cooldown_seconds, 5);
+        assert_eq!(merged.identity.batch_size, RESOLVE_BATCH_SIZE);
+        assert_eq!(restart_required, vec!["database.url", "identity"]);
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("15m"), Ok(Duration::from_secs(15 * 60)));
+        assert_eq!(parse_interval("6h"), Ok(Duration::from_secs(6 * 60 * 60)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("15").is_err());
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("").is_err());
+        assert!(parse_interval("5é").is_err());
+        assert!(parse_interval("é").is_err());
+        assert!(parse_interval("99999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_job_config() {
+        let mut config = Config::from_toml(
+            r#"
+            [jobs.usage_retention]
+            enabled = false
+
+            [jobs.resolve_identities]
+            every = "soon"
+            "#,
+        )
+        .unwrap();
+        assert!(!config.jobs.usage_retention.enabled);
+        assert_eq!(config.jobs.usage_retention.every, None);
+        assert!(config.jobs.expire_verifications.enabled);
+
+        match config.validate() {
+            Err(ConfigError::Invalid(problems)) => {
+                assert_eq!(problems.len(), 1);
+                assert!(problems[0].starts_with("jobs.resolve_identities.every"));
+            }
+            other => panic!("Expected validation errors, got {:?}", other),
+        }
+
+        config
+            .apply_env(env(&[("KRUNKER_BOT_JOBS_RESOLVE_IDENTITIES_EVERY", "2h")]))
+            .unwrap();
+        assert!(config.validate().is_ok());
+    }
+}
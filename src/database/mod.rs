@@ -1,12 +1,227 @@
 pub mod models;
 pub mod queries;
 
-use sqlx::{Result, SqlitePool};
+use sqlx::{AnyPool, Result};
 
-pub async fn init_db() -> Result<SqlitePool> {
-    let pool = SqlitePool::connect("sqlite:bot.db").await?;
+/// Which database a URL points at. Queries are written to run on both; only the
+/// migrations and a few maintenance tasks differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Sqlite,
+    Postgres,
+}
+
+impl Backend {
+    pub fn from_url(url: &str) -> Option<Self> {
+        if url.starts_with("sqlite:") {
+            Some(Backend::Sqlite)
+        } else if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            Some(Backend::Postgres)
+        } else {
+            None
+        }
+    }
+
+    fn of(pool: &AnyPool) -> Result<Self> {
+        let url = pool.connect_options().database_url.to_string();
+        Self::from_url(&url)
+            .ok_or_else(|| sqlx::Error::Configuration(format!("unsupported database {url}").into()))
+    }
+}
+
+pub async fn init_db(url: &str) -> Result<AnyPool> {
+    let pool = connect(url).await?;
 
-    sqlx::migrate!("./migrations").run(&pool).await?;
+    migrate(&pool).await?;
 
     Ok(pool)
 }
+
+/// Opens the database without touching the schema
+pub async fn connect(url: &str) -> Result<AnyPool> {
+    sqlx::any::install_default_drivers();
+    AnyPool::connect(url).await
+}
+
+/// Applies the migration set for the pool's backend, then reports any links a
+/// migration had to remove
+pub async fn migrate(pool: &AnyPool) -> Result<()> {
+    let started = chrono::Utc::now().timestamp();
+    match Backend::of(pool)? {
+        Backend::Sqlite => sqlx::migrate!("./migrations/sqlite").run(pool).await?,
+        Backend::Postgres => sqlx::migrate!("./migrations/postgres").run(pool).await?,
+    }
+
+    for event in queries::get_links_removed_by_migration(pool, started).await? {
+        tracing::warn!(
+            discord_id = %event.discord_id,
+            username = %event.krunker_username,
+            "Removed a link: {}",
+            event.details.unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+/// Writes a consistent copy of the live database to `path`, which must not exist yet.
+/// SQLite only, Postgres has pg_dump for this.
+pub async fn backup(pool: &AnyPool, path: &str) -> Result<()> {
+    if Backend::of(pool)? != Backend::Sqlite {
+        return Err(sqlx::Error::Configuration(
+            "backups are only supported for SQLite, use pg_dump for Postgres".into(),
+        ));
+    }
+
+    sqlx::query("VACUUM INTO $1")
+        .bind(path)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// A fresh, migrated database for one test. SQLite in memory by default; with
+/// `TEST_DATABASE_URL` pointing at a Postgres server (a throwaway one, e.g.
+/// `docker run -e POSTGRES_PASSWORD=test -p 5432:5432 postgres`) each test gets
+/// a schema of its own there instead, dropped when the test finishes.
+#[cfg(test)]
+pub(crate) async fn test_pool() -> AnyPool {
+    let url = std::env::var("TEST_DATABASE_URL").unwrap_or_else(|_| "sqlite::memory:".into());
+
+    let pool = match Backend::from_url(&url) {
+        Some(Backend::Postgres) => postgres_test_pool(&url).await,
+        _ => connect(&url).await.expect("test database opens"),
+    };
+    migrate(&pool).await.expect("migrations apply");
+    pool
+}
+
+#[cfg(test)]
+async fn postgres_test_pool(url: &str) -> AnyPool {
+    use sqlx::Connection;
+    use sqlx::any::{AnyConnection, AnyPoolOptions};
+
+    sqlx::any::install_default_drivers();
+    let schema = format!("test_{:016x}", rand::random::<u64>());
+
+    let mut conn = AnyConnection::connect(url)
+        .await
+        .expect("Postgres test server is reachable");
+    sqlx::query(&format!("CREATE SCHEMA {schema}"))
+        .execute(&mut conn)
+        .await
+        .expect("test schema is created");
+    let _ = conn.close().await;
+
+    // tasks are dropped when the test's runtime shuts down, taking the schema with them
+    let drop_schema = DropSchema {
+        url: url.to_string(),
+        schema: schema.clone(),
+    };
+    tokio::spawn(async move {
+        let _drop_schema = drop_schema;
+        std::future::pending::<()>().await
+    });
+
+    AnyPoolOptions::new()
+        .after_connect(move |conn, _| {
+            let search_path = format!("SET search_path TO {schema}");
+            Box::pin(async move {
+                sqlx::query(&search_path).execute(conn).await?;
+                Ok(())
+            })
+        })
+        .connect(url)
+        .await
+        .expect("Postgres test server is reachable")
+}
+
+/// Drops a Postgres test schema when dropped
+#[cfg(test)]
+struct DropSchema {
+    url: String,
+    schema: String,
+}
+
+#[cfg(test)]
+impl Drop for DropSchema {
+    fn drop(&mut self) {
+        use sqlx::Connection;
+        use sqlx::any::AnyConnection;
+
+        let url = self.url.clone();
+        let statement = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.schema);
+        // the test's runtime is going away, so the cleanup gets one of its own
+        let cleanup = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("cleanup runtime starts");
+            runtime.block_on(async {
+                let mut conn = AnyConnection::connect(&url).await?;
+                sqlx::query(&statement).execute(&mut conn).await?;
+                conn.close().await
+            })
+        });
+        match cleanup.join() {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => eprintln!("Failed to drop test schema {}: {}", self.schema, e),
+            Err(_) => eprintln!("Failed to drop test schema {}", self.schema),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(Backend::from_url("sqlite:bot.db"), Some(Backend::Sqlite));
+        assert_eq!(
+            Backend::from_url("postgres://bot@localhost/bot"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(
+            Backend::from_url("postgresql://bot@localhost/bot"),
+            Some(Backend::Postgres)
+        );
+        assert_eq!(Backend::from_url("mysql://localhost/bot"), None);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_links_are_logged_before_removal() {
+        let pool = connect("sqlite::memory:").await.unwrap();
+
+        // stop just before usernames became unique, when these could coexist
+        let mut migrator = sqlx::migrate!("./migrations/sqlite");
+        migrator.migrations = migrator.migrations[..6].to_vec().into();
+        migrator.run(&pool).await.unwrap();
+        sqlx::query(
+            "INSERT INTO users (username, discord_id, is_primary)
+             VALUES ('Alice', '1', TRUE), ('alice', '2', TRUE), ('Bob', '2', FALSE)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrate(&pool).await.unwrap();
+
+        let removed = queries::get_links_removed_by_migration(&pool, 0)
+            .await
+            .unwrap();
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].discord_id, "2");
+        assert_eq!(removed[0].krunker_username, "alice");
+        assert!(
+            queries::get_user_by_username(&pool, "ALICE")
+                .await
+                .unwrap()
+                .is_some()
+        );
+
+        // the user who lost their primary gets their other account as primary
+        let accounts = queries::get_users_by_discord_id(&pool, "2").await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert!(accounts[0].is_primary);
+    }
+}
@@ -1,13 +1,40 @@
-#[derive(Debug, Clone, sqlx::FromRow)]
+use serde::Serialize;
+use sqlx::Row;
+use sqlx::any::AnyRow;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct User {
     pub id: i64,
     pub username: String,
     pub discord_id: String,
     pub country: Option<String>,
     pub day_created: i64,
+    pub is_primary: bool,
+    pub krunker_id: Option<String>,
+    pub last_resolved_at: Option<i64>,
+    /// Set when the account stopped resolving to the stored identity
+    pub stale_since: Option<i64>,
 }
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+// The Any driver hands SQLite booleans back as integers, so the queries select
+// is_primary as one on both backends and it's turned back into a bool here
+impl<'r> sqlx::FromRow<'r, AnyRow> for User {
+    fn from_row(row: &'r AnyRow) -> sqlx::Result<Self> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            username: row.try_get("username")?,
+            discord_id: row.try_get("discord_id")?,
+            country: row.try_get("country")?,
+            day_created: row.try_get("day_created")?,
+            is_primary: row.try_get::<i64, _>("is_primary")? != 0,
+            krunker_id: row.try_get("krunker_id")?,
+            last_resolved_at: row.try_get("last_resolved_at")?,
+            stale_since: row.try_get("stale_since")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
 pub struct Verification {
     pub id: i64,
     pub discord_id: String,
@@ -16,4 +43,377 @@ pub struct Verification {
     pub created_at: i64,
     pub expires_at: i64,
     pub attempts: i32,
+    pub status: String,
+}
+
+/// Lifecycle of a verification. Only pending verifications can change state;
+/// the others are final.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationStatus {
+    Pending,
+    Verified,
+    Expired,
+    Failed,
+    Cancelled,
+}
+
+impl VerificationStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationStatus::Pending => "pending",
+            VerificationStatus::Verified => "verified",
+            VerificationStatus::Expired => "expired",
+            VerificationStatus::Failed => "failed",
+            VerificationStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn can_transition_to(&self, to: VerificationStatus) -> bool {
+        *self == VerificationStatus::Pending && to != VerificationStatus::Pending
+    }
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct VerificationStatusChange {
+    pub id: i64,
+    pub verification_id: i64,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub reason: Option<String>,
+    pub changed_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct VerificationLimits {
+    pub discord_id: String,
+    pub last_started_at: i64,
+    /// Sessions that ran out of attempts or were abandoned after a failed check,
+    /// since the last lockout or successful link
+    pub failed_sessions: i32,
+    pub locked_until: Option<i64>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct UsernameChange {
+    pub id: i64,
+    pub user_id: i64,
+    pub discord_id: String,
+    pub krunker_id: String,
+    pub old_username: String,
+    pub new_username: String,
+    pub changed_at: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct LinkEvent {
+    pub id: i64,
+    pub discord_id: String,
+    pub krunker_username: String,
+    pub event: String,
+    pub actor_id: String,
+    pub details: Option<String>,
+    pub created_at: i64,
+}
+
+/// Kinds of entries written to the `link_events` audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkEventKind {
+    LinkStarted,
+    VerifyAttempt,
+    Verified,
+    VerifyFailed,
+    Expired,
+    Unlinked,
+    ForceLinked,
+    ForceUnlinked,
+    Renamed,
+    Flagged,
+    Imported,
+    LookedUp,
+    RemovedByMigration,
+}
+
+impl LinkEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LinkEventKind::LinkStarted => "link_started",
+            LinkEventKind::VerifyAttempt => "verify_attempt",
+            LinkEventKind::Verified => "verified",
+            LinkEventKind::VerifyFailed => "verify_failed",
+            LinkEventKind::Expired => "expired",
+            LinkEventKind::Unlinked => "unlinked",
+            LinkEventKind::ForceLinked => "force_linked",
+            LinkEventKind::ForceUnlinked => "force_unlinked",
+            LinkEventKind::Renamed => "renamed",
+            LinkEventKind::Flagged => "flagged",
+            LinkEventKind::Imported => "imported",
+            LinkEventKind::LookedUp => "looked_up",
+            LinkEventKind::RemovedByMigration => "removed_by_migration",
+        }
+    }
+}
+
+/// Actor recorded for events the bot triggers on its own (e.g. expiry cleanup)
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Per-command totals for a time window, as shown by &botstats
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CommandStats {
+    pub command: String,
+    pub uses: i64,
+    pub errors: i64,
+    pub p95_latency_ms: i64,
+}
+
+/// Totals across all commands for a time window
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct UsageSummary {
+    pub uses: i64,
+    pub errors: i64,
+    pub p95_latency_ms: Option<i64>,
+    pub active_guilds: i64,
+    pub active_users: i64,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct CommandUsage {
+    pub id: i64,
+    pub command: String,
+    pub guild_id: Option<String>,
+    pub discord_id: String,
+    pub latency_ms: i64,
+    pub error_class: Option<String>,
+    pub created_at: i64,
+}
+
+/// Everything stored about one Discord user, as sent by &mydata
+#[derive(Debug, Clone, Serialize)]
+pub struct UserData {
+    pub discord_id: String,
+    pub exported_at: i64,
+    pub accounts: Vec<User>,
+    pub username_history: Vec<UsernameChange>,
+    pub verifications: Vec<Verification>,
+    pub verification_history: Vec<VerificationStatusChange>,
+    pub verification_limits: Option<VerificationLimits>,
+    /// Entries about the user, and ones where they were the acting moderator
+    pub link_events: Vec<LinkEvent>,
+    pub command_usage: Vec<CommandUsage>,
+    /// When the user opted out of announcements, if they did
+    pub announcements_opted_out_at: Option<i64>,
+    /// Their place in each lobby they were put in by &queue
+    pub lobbies: Vec<LobbyPlayer>,
+    /// How far the match feed has got for each of their accounts
+    pub match_feed_cursors: Vec<MatchFeedCursor>,
+    /// Channels their newest matches already went out to
+    pub match_feed_posts: Vec<MatchFeedPost>,
+    /// Announcement channels they last set as a moderator
+    pub match_feed_channels: Vec<AnnouncementChannel>,
+    pub milestone_channels: Vec<AnnouncementChannel>,
+}
+
+/// A ranked match as stored from `get_match`
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct StoredMatch {
+    pub match_id: i64,
+    pub map: String,
+    pub duration_ms: i64,
+    pub played_at: String,
+    pub fetched_at: i64,
+}
+
+/// One player's line in a stored match
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct StoredParticipant {
+    pub match_id: i64,
+    pub player_name: String,
+    pub team: i64,
+    /// 1 for the winning team
+    pub victory: i64,
+    pub kills: i64,
+    pub deaths: i64,
+    pub assists: i64,
+    pub score: i64,
+    pub damage_done: i64,
+    pub objective_score: i64,
+}
+
+/// A player's rating from the stored ranked matches
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct PlayerRating {
+    /// The name they last played a stored match under
+    pub player_name: String,
+    /// None for players the bot has never looked up
+    pub krunker_id: Option<String>,
+    pub rating: f64,
+    pub games: i64,
+    pub wins: i64,
+    /// The highest the rating has been
+    pub peak: f64,
+    pub computed_at: i64,
+}
+
+impl PlayerRating {
+    /// What a rating is stored under: the krunker_id when known, so a rename keeps
+    /// the rating, otherwise the name in any case
+    pub fn key_for(krunker_id: Option<&str>, player_name: &str) -> String {
+        match krunker_id {
+            Some(krunker_id) => format!("id:{}", krunker_id),
+            None => format!("name:{}", player_name.to_lowercase()),
+        }
+    }
+
+    pub fn key(&self) -> String {
+        Self::key_for(self.krunker_id.as_deref(), &self.player_name)
+    }
+}
+
+/// The kinds of announcement a guild can give a channel to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnouncementFeed {
+    Matches,
+    Milestones,
+}
+
+impl AnnouncementFeed {
+    pub fn table(&self) -> &'static str {
+        match self {
+            AnnouncementFeed::Matches => "match_feed_channels",
+            AnnouncementFeed::Milestones => "milestone_channels",
+        }
+    }
+}
+
+/// The channel a guild picked for one kind of announcement
+#[derive(Debug, Clone, sqlx::FromRow, Serialize)]
+pub struct AnnouncementChannel {
+    pub guild_id: String,
+    pub channel_id: String,
+    pub updated_by: String,
+    pub updated_at: i64,
+}
+
+/// The newest match the feed has seen for one linked account
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct MatchFeedCursor {
+    pub user_id: i64,
+    pub last_match_id: i64,
+    pub checked_at: i64,
+}
+
+/// A match card posted to one channel, kept until the account's cursor passes it
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct MatchFeedPost {
+    pub user_id: i64,
+    pub match_id: i64,
+    pub channel_id: String,
+    pub posted_at: i64,
+}
+
+/// A player's `get_player` numbers at one point in time
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct ProfileSnapshot {
+    pub id: i64,
+    pub krunker_id: String,
+    pub username: String,
+    pub level: i64,
+    pub kr: i64,
+    pub games: i64,
+    pub kdr: f64,
+    pub clan: Option<String>,
+    pub taken_at: i64,
+}
+
+impl ProfileSnapshot {
+    /// True if nothing but the id and time differ
+    pub fn same_profile(&self, other: &ProfileSnapshot) -> bool {
+        self.username == other.username
+            && self.level == other.level
+            && self.kr == other.kr
+            && self.games == other.games
+            && self.kdr == other.kdr
+            && self.clan == other.clan
+    }
+}
+
+/// A player whose latest snapshot shows a given clan tag, with their stored
+/// ranked record
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ClanMember {
+    pub krunker_id: String,
+    pub username: String,
+    pub level: i64,
+    pub kr: i64,
+    pub kdr: f64,
+    /// Set when the account is linked
+    pub discord_id: Option<String>,
+    pub ranked_games: i64,
+    pub ranked_wins: i64,
+    pub taken_at: i64,
+}
+
+/// Where the milestones job left off with a player
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct MilestoneBaseline {
+    pub krunker_id: String,
+    pub level: i64,
+    pub kr: i64,
+    pub games: i64,
+    pub checked_at: i64,
+}
+
+impl MilestoneBaseline {
+    pub fn of(snapshot: &ProfileSnapshot) -> Self {
+        Self {
+            krunker_id: snapshot.krunker_id.clone(),
+            level: snapshot.level,
+            kr: snapshot.kr,
+            games: snapshot.games,
+            checked_at: snapshot.taken_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MilestoneKind {
+    Level,
+    Kr,
+    Games,
+}
+
+impl MilestoneKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MilestoneKind::Level => "level",
+            MilestoneKind::Kr => "kr",
+            MilestoneKind::Games => "games",
+        }
+    }
+}
+
+/// A lobby made by &queue
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct Lobby {
+    pub id: i64,
+    pub guild_id: Option<String>,
+    pub channel_id: String,
+    pub started_by: String,
+    pub team_size: i64,
+    pub rerolls: i64,
+    /// 1 if the players locked the teams in, 0 if the buttons timed out
+    pub locked_in: i64,
+    /// Team 1's total skill minus team 2's
+    pub skill_difference: f64,
+    pub created_at: i64,
+}
+
+/// A player's place in a lobby
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, Serialize)]
+pub struct LobbyPlayer {
+    pub lobby_id: i64,
+    pub discord_id: String,
+    pub username: String,
+    /// 1 or 2
+    pub team: i64,
+    pub skill: f64,
 }
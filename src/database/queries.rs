@@ -1,306 +1,3453 @@
-use crate::database::models::Verification;
+use crate::database::models::{
+    AnnouncementChannel, AnnouncementFeed, ClanMember, CommandStats, CommandUsage, LinkEvent,
+    LinkEventKind, Lobby, LobbyPlayer, MatchFeedCursor, MatchFeedPost, MilestoneBaseline,
+    MilestoneKind, PlayerRating, ProfileSnapshot, SYSTEM_ACTOR, StoredMatch, StoredParticipant,
+    UsageSummary, UserData, UsernameChange, Verification, VerificationLimits, VerificationStatus,
+    VerificationStatusChange,
+};
 
 use super::models::User;
-use sqlx::{Result, SqlitePool};
+use sqlx::any::AnyExecutor;
+use sqlx::{AnyConnection, AnyPool, Result};
 
 // ========= USER SECTION
+
+// is_primary goes out as an integer, see the FromRow impl for User
+const USER_COLUMNS: &str = "id, username, discord_id, country, day_created, \
+     CAST(is_primary AS INTEGER) AS is_primary, krunker_id, last_resolved_at, stale_since";
+
+/// Links a Krunker account. The first account a Discord user links becomes their primary.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
 pub async fn create_user(
-    pool: &SqlitePool,
+    executor: impl AnyExecutor<'_>,
+    username: &str,
+    discord_id: &str,
+    country: Option<&str>,
+) -> Result<i64> {
+    sqlx::query_scalar(
+        "INSERT INTO users (username, discord_id, country, is_primary)
+         VALUES ($1, $2, $3, NOT EXISTS (SELECT 1 FROM users WHERE discord_id = $2 AND is_primary))
+         RETURNING id",
+    )
+    .bind(username)
+    .bind(discord_id)
+    .bind(country)
+    .fetch_one(executor)
+    .await
+}
+
+/// Links an account restored from an export, keeping when it was linked and whether it
+/// was the primary. A primary already in the database wins over the imported flag.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn import_user(
+    executor: impl AnyExecutor<'_>,
     username: &str,
     discord_id: &str,
     country: Option<&str>,
+    is_primary: bool,
+    linked_at: i64,
+) -> Result<i64> {
+    sqlx::query_scalar(
+        "INSERT INTO users (username, discord_id, country, is_primary, day_created)
+         VALUES ($1, $2, $3,
+             $4 AND NOT EXISTS (SELECT 1 FROM users WHERE discord_id = $2 AND is_primary), $5)
+         RETURNING id",
+    )
+    .bind(username)
+    .bind(discord_id)
+    .bind(country)
+    .bind(is_primary)
+    .bind(linked_at)
+    .fetch_one(executor)
+    .await
+}
+
+/// Returns the user's primary account
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_user_by_discord_id(pool: &AnyPool, discord_id: &str) -> Result<Option<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE discord_id = $1 ORDER BY is_primary DESC, id LIMIT 1"
+    ))
+    .bind(discord_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns every account linked to the user, primary first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_users_by_discord_id(pool: &AnyPool, discord_id: &str) -> Result<Vec<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE discord_id = $1 ORDER BY is_primary DESC, id"
+    ))
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_user_by_username(
+    executor: impl AnyExecutor<'_>,
+    username: &str,
+) -> Result<Option<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE LOWER(username) = LOWER($1)"
+    ))
+    .bind(username)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Removes every account linked to the user
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn delete_user(pool: &AnyPool, discord_id: &str) -> Result<()> {
+    sqlx::query("DELETE FROM users WHERE discord_id = $1")
+        .bind(discord_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Removes one of the user's accounts, promoting their oldest remaining account
+/// if it was the primary. Returns false if the account wasn't linked to them.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn delete_user_account(
+    conn: &mut AnyConnection,
+    discord_id: &str,
+    username: &str,
+) -> Result<bool> {
+    let result =
+        sqlx::query("DELETE FROM users WHERE discord_id = $1 AND LOWER(username) = LOWER($2)")
+            .bind(discord_id)
+            .bind(username)
+            .execute(&mut *conn)
+            .await?;
+
+    promote_fallback_primary(conn, discord_id).await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Unlinks `username` from whoever owns it, promoting their next account if it was the
+/// primary. Takes a connection so &forcelink can move the account in one transaction.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn delete_user_by_username(conn: &mut AnyConnection, username: &str) -> Result<()> {
+    let owner: Option<String> =
+        sqlx::query_scalar("SELECT discord_id FROM users WHERE LOWER(username) = LOWER($1)")
+            .bind(username)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+    sqlx::query("DELETE FROM users WHERE LOWER(username) = LOWER($1)")
+        .bind(username)
+        .execute(&mut *conn)
+        .await?;
+
+    if let Some(owner) = owner {
+        promote_fallback_primary(conn, &owner).await?;
+    }
+    Ok(())
+}
+
+/// Makes `username` the user's primary account. Returns false if it isn't linked to them.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_primary_account(pool: &AnyPool, discord_id: &str, username: &str) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let exists: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM users WHERE discord_id = $1 AND LOWER(username) = LOWER($2)",
+    )
+    .bind(discord_id)
+    .bind(username)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let id = match exists {
+        Some(id) => id,
+        None => return Ok(false),
+    };
+
+    // clear first so the one-primary-per-user index never sees two
+    sqlx::query("UPDATE users SET is_primary = FALSE WHERE discord_id = $1")
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("UPDATE users SET is_primary = TRUE WHERE id = $1")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Marks the user's oldest account as primary if none of their accounts is
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+async fn promote_fallback_primary(conn: &mut AnyConnection, discord_id: &str) -> Result<()> {
+    sqlx::query(
+        "UPDATE users SET is_primary = TRUE
+         WHERE id = (SELECT id FROM users WHERE discord_id = $1 ORDER BY id LIMIT 1)
+         AND NOT EXISTS (SELECT 1 FROM users WHERE discord_id = $1 AND is_primary)",
+    )
+    .bind(discord_id)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn user_exists(pool: &AnyPool, discord_id: &str) -> Result<bool> {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE discord_id = $1")
+        .bind(discord_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(count > 0)
+}
+
+/// Every linked account, oldest first, a page at a time
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn list_users(pool: &AnyPool, limit: i64, offset: i64) -> Result<Vec<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users ORDER BY id LIMIT $1 OFFSET $2"
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool)
+    .await
+}
+
+/// Accounts whose username contains `query`, or that belong to the Discord id `query`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn search_users(pool: &AnyPool, query: &str, limit: i64) -> Result<Vec<User>> {
+    // LIKE wildcards in the query are matched literally
+    let pattern = format!(
+        "%{}%",
+        query
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    );
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users
+         WHERE LOWER(username) LIKE LOWER($1) ESCAPE '\\' OR discord_id = $2
+         ORDER BY id LIMIT $3"
+    ))
+    .bind(pattern)
+    .bind(query)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+// ========= USER SECTION OVER
+
+// ========= IDENTITY SECTION
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_user_by_krunker_id(
+    executor: impl AnyExecutor<'_>,
+    krunker_id: &str,
+) -> Result<Option<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE krunker_id = $1"
+    ))
+    .bind(krunker_id)
+    .fetch_optional(executor)
+    .await
+}
+
+/// Linked accounts that haven't been re-resolved since `resolved_before`, oldest first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_users_to_resolve(
+    pool: &AnyPool,
+    resolved_before: i64,
+    limit: i64,
+) -> Result<Vec<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users
+         WHERE last_resolved_at IS NULL OR last_resolved_at < $1
+         ORDER BY last_resolved_at IS NOT NULL, last_resolved_at, id
+         LIMIT $2"
+    ))
+    .bind(resolved_before)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Records a successful resolution, storing the identity and clearing any stale flag
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_user_identity(
+    executor: impl AnyExecutor<'_>,
+    user_id: i64,
+    krunker_id: &str,
+    resolved_at: i64,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE users SET krunker_id = $1, last_resolved_at = $2, stale_since = NULL WHERE id = $3",
+    )
+    .bind(krunker_id)
+    .bind(resolved_at)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Flags a link whose account no longer resolves. Returns true if it wasn't already flagged.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn mark_user_stale(pool: &AnyPool, user_id: i64, now: i64) -> Result<bool> {
+    let result =
+        sqlx::query("UPDATE users SET stale_since = $1 WHERE id = $2 AND stale_since IS NULL")
+            .bind(now)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+
+    sqlx::query("UPDATE users SET last_resolved_at = $1 WHERE id = $2")
+        .bind(now)
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Updates a linked account's stored name and appends it to the name history
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn rename_user(
+    pool: &AnyPool,
+    user_id: i64,
+    new_username: &str,
+    changed_at: i64,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO username_history
+            (user_id, discord_id, krunker_id, old_username, new_username, changed_at)
+         SELECT id, discord_id, COALESCE(krunker_id, ''), username, $1, $2
+         FROM users WHERE id = $3",
+    )
+    .bind(new_username)
+    .bind(changed_at)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "UPDATE users SET username = $1, last_resolved_at = $2, stale_since = NULL WHERE id = $3",
+    )
+    .bind(new_username)
+    .bind(changed_at)
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_username_history(pool: &AnyPool, user_id: i64) -> Result<Vec<UsernameChange>> {
+    sqlx::query_as::<_, UsernameChange>(
+        "SELECT id, user_id, discord_id, krunker_id, old_username, new_username, changed_at
+         FROM username_history
+         WHERE user_id = $1
+         ORDER BY changed_at DESC, id DESC",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+// ========= IDENTITY SECTION OVER
+
+// ========= VERIFICATION SECTION
+
+const VERIFICATION_COLUMNS: &str =
+    "id, discord_id, krunker_username, code, created_at, expires_at, attempts, status";
+
+/// Opens a new pending verification
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn create_verification(
+    pool: &AnyPool,
+    discord_id: &str,
+    krunker_username: &str,
+    code: &str,
+    created_at: i64,
+    expires_at: i64,
 ) -> Result<i64> {
-    let result = sqlx::query!(
-        "INSERT INTO users (username, discord_id, country) VALUES (?, ?, ?)",
-        username,
+    let mut tx = pool.begin().await?;
+    let id = insert_verification(
+        &mut tx,
         discord_id,
-        country
+        krunker_username,
+        code,
+        created_at,
+        expires_at,
     )
-    .execute(pool)
+    .await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+/// Inserts a pending verification and its first history entry on an existing connection,
+/// so callers can do it inside their own transaction
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn insert_verification(
+    conn: &mut AnyConnection,
+    discord_id: &str,
+    krunker_username: &str,
+    code: &str,
+    created_at: i64,
+    expires_at: i64,
+) -> Result<i64> {
+    let id: i64 = sqlx::query_scalar(
+        "INSERT INTO verifications (discord_id, krunker_username, code, created_at, expires_at, status)
+         VALUES ($1, $2, $3, $4, $5, $6)
+         RETURNING id",
+    )
+    .bind(discord_id)
+    .bind(krunker_username)
+    .bind(code)
+    .bind(created_at)
+    .bind(expires_at)
+    .bind(VerificationStatus::Pending.as_str())
+    .fetch_one(&mut *conn)
+    .await?;
+
+    record_status_change(
+        conn,
+        id,
+        None,
+        VerificationStatus::Pending,
+        None,
+        created_at,
+    )
+    .await?;
+
+    Ok(id)
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_verification_by_code(
+    pool: &AnyPool,
+    code: &str,
+    now: i64,
+) -> Result<Option<Verification>> {
+    sqlx::query_as::<_, Verification>(&format!(
+        "SELECT {VERIFICATION_COLUMNS}
+        FROM verifications
+        WHERE code = $1 AND status = 'pending' AND expires_at > $2"
+    ))
+    .bind(code)
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Returns the caller's unexpired pending verification, if any
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_verification_by_discord_id(
+    pool: &AnyPool,
+    discord_id: &str,
+    now: i64,
+) -> Result<Option<Verification>> {
+    sqlx::query_as::<_, Verification>(&format!(
+        "SELECT {VERIFICATION_COLUMNS}
+        FROM verifications
+        WHERE discord_id = $1 AND status = 'pending' AND expires_at > $2"
+    ))
+    .bind(discord_id)
+    .bind(now)
+    .fetch_optional(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn delete_verification(pool: &AnyPool, code: &str) -> Result<()> {
+    sqlx::query("DELETE FROM verifications WHERE code = $1")
+        .bind(code)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Counts a failed check against a pending verification and returns the new total,
+/// or None if the verification is no longer pending
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn increment_verification_attempts(
+    conn: &mut AnyConnection,
+    verification_id: i64,
+) -> Result<Option<i32>> {
+    sqlx::query_scalar(
+        "UPDATE verifications SET attempts = attempts + 1
+         WHERE id = $1 AND status = 'pending'
+         RETURNING attempts",
+    )
+    .bind(verification_id)
+    .fetch_optional(conn)
+    .await
+}
+
+/// Moves the user's pending verification to `to` and logs the change. Returns the
+/// verification as it was while pending, or None if nothing was pending (for example
+/// because a concurrent request already finished it).
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn finish_pending_verification(
+    conn: &mut AnyConnection,
+    discord_id: &str,
+    to: VerificationStatus,
+    reason: Option<&str>,
+    now: i64,
+) -> Result<Option<Verification>> {
+    debug_assert!(VerificationStatus::Pending.can_transition_to(to));
+
+    let verification = sqlx::query_as::<_, Verification>(&format!(
+        "UPDATE verifications SET status = $1, updated_at = $2
+         WHERE discord_id = $3 AND status = 'pending'
+         RETURNING {VERIFICATION_COLUMNS}"
+    ))
+    .bind(to.as_str())
+    .bind(now)
+    .bind(discord_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match verification {
+        Some(mut verification) => {
+            record_status_change(
+                conn,
+                verification.id,
+                Some(VerificationStatus::Pending),
+                to,
+                reason,
+                now,
+            )
+            .await?;
+            verification.status = VerificationStatus::Pending.as_str().to_string();
+            Ok(Some(verification))
+        }
+        None => Ok(None),
+    }
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+async fn record_status_change(
+    conn: &mut AnyConnection,
+    verification_id: i64,
+    from: Option<VerificationStatus>,
+    to: VerificationStatus,
+    reason: Option<&str>,
+    changed_at: i64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO verification_status_history
+            (verification_id, from_status, to_status, reason, changed_at)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(verification_id)
+    .bind(from.map(|s| s.as_str()))
+    .bind(to.as_str())
+    .bind(reason)
+    .bind(changed_at)
+    .execute(conn)
+    .await?;
+    Ok(())
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_verification_history(
+    pool: &AnyPool,
+    verification_id: i64,
+) -> Result<Vec<VerificationStatusChange>> {
+    sqlx::query_as::<_, VerificationStatusChange>(
+        "SELECT id, verification_id, from_status, to_status, reason, changed_at
+         FROM verification_status_history
+         WHERE verification_id = $1
+         ORDER BY id",
+    )
+    .bind(verification_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Moves every pending verification past its expiry to `expired`, logging an
+/// `expired` link event and a status change for each one. Returns how many expired,
+/// and the Discord ids whose expired session had been checked at least once.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn cleanup_expired_verifications(pool: &AnyPool, now: i64) -> Result<(u64, Vec<String>)> {
+    let mut tx = pool.begin().await?;
+
+    let attempted: Vec<String> = sqlx::query_scalar(
+        "SELECT discord_id FROM verifications
+         WHERE status = 'pending' AND expires_at <= $1 AND attempts > 0
+         ORDER BY id",
+    )
+    .bind(now)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // the UPDATE comes last, so both inserts still see these rows as pending
+    sqlx::query(
+        "INSERT INTO verification_status_history
+            (verification_id, from_status, to_status, reason, changed_at)
+         SELECT id, 'pending', 'expired', NULL, $1
+         FROM verifications
+         WHERE status = 'pending' AND expires_at <= $2",
+    )
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
     .await?;
 
-    Ok(result.last_insert_rowid())
+    sqlx::query(
+        "INSERT INTO link_events (discord_id, krunker_username, event, actor_id)
+         SELECT discord_id, krunker_username, $1, $2
+         FROM verifications
+         WHERE status = 'pending' AND expires_at <= $3",
+    )
+    .bind(LinkEventKind::Expired.as_str())
+    .bind(SYSTEM_ACTOR)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    let expired = sqlx::query(
+        "UPDATE verifications SET status = 'expired', updated_at = $1
+         WHERE status = 'pending' AND expires_at <= $2",
+    )
+    .bind(now)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    tx.commit().await?;
+    Ok((expired, attempted))
+}
+
+/// The user's most recent verification session, whatever state it ended in
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_latest_verification(
+    pool: &AnyPool,
+    discord_id: &str,
+) -> Result<Option<Verification>> {
+    sqlx::query_as::<_, Verification>(&format!(
+        "SELECT {VERIFICATION_COLUMNS}
+        FROM verifications
+        WHERE discord_id = $1
+        ORDER BY id DESC
+        LIMIT 1"
+    ))
+    .bind(discord_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Unexpired pending verifications for `krunker_username` held by anyone other than `discord_id`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_pending_claims(
+    pool: &AnyPool,
+    krunker_username: &str,
+    discord_id: &str,
+    now: i64,
+) -> Result<Vec<Verification>> {
+    sqlx::query_as::<_, Verification>(&format!(
+        "SELECT {VERIFICATION_COLUMNS}
+        FROM verifications
+        WHERE LOWER(krunker_username) = LOWER($1) AND discord_id != $2
+            AND status = 'pending' AND expires_at > $3
+        ORDER BY created_at"
+    ))
+    .bind(krunker_username)
+    .bind(discord_id)
+    .bind(now)
+    .fetch_all(pool)
+    .await
 }
 
-pub async fn get_user_by_discord_id(pool: &SqlitePool, discord_id: &str) -> Result<Option<User>> {
-    sqlx::query_as::<_, User>(
-        "SELECT id, username, discord_id, country, day_created FROM users WHERE discord_id = ?",
+// ========= VERIFICATION SECTION OVER
+
+// ========= VERIFICATION LIMITS SECTION
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_verification_limits(
+    pool: &AnyPool,
+    discord_id: &str,
+) -> Result<Option<VerificationLimits>> {
+    sqlx::query_as::<_, VerificationLimits>(
+        "SELECT discord_id, last_started_at, failed_sessions, locked_until
+         FROM verification_limits
+         WHERE discord_id = $1",
     )
     .bind(discord_id)
     .fetch_optional(pool)
     .await
 }
 
-pub async fn get_user_by_username(pool: &SqlitePool, username: &str) -> Result<Option<User>> {
-    sqlx::query_as::<_, User>(
-        "SELECT id, username, discord_id, country, day_created FROM users WHERE username = ?",
-    )
-    .bind(username)
-    .fetch_optional(pool)
-    .await
-}
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_verification_start(pool: &AnyPool, discord_id: &str, now: i64) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO verification_limits (discord_id, last_started_at) VALUES ($1, $2)
+         ON CONFLICT(discord_id) DO UPDATE SET last_started_at = excluded.last_started_at",
+    )
+    .bind(discord_id)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Counts a failed session. Once `max_failed` is reached the user is locked out until
+/// `now + lockout_seconds` and the counter starts over. Returns the lockout end if one began.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_failed_session(
+    pool: &AnyPool,
+    discord_id: &str,
+    now: i64,
+    max_failed: i32,
+    lockout_seconds: i64,
+) -> Result<Option<i64>> {
+    let mut tx = pool.begin().await?;
+
+    let failed: i32 = sqlx::query_scalar(
+        "INSERT INTO verification_limits (discord_id, failed_sessions) VALUES ($1, 1)
+         ON CONFLICT(discord_id) DO UPDATE
+         SET failed_sessions = verification_limits.failed_sessions + 1
+         RETURNING failed_sessions",
+    )
+    .bind(discord_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let locked_until = if failed >= max_failed {
+        let until = now + lockout_seconds;
+        sqlx::query(
+            "UPDATE verification_limits SET failed_sessions = 0, locked_until = $1
+             WHERE discord_id = $2",
+        )
+        .bind(until)
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+        Some(until)
+    } else {
+        None
+    };
+
+    tx.commit().await?;
+    Ok(locked_until)
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn reset_failed_sessions(executor: impl AnyExecutor<'_>, discord_id: &str) -> Result<()> {
+    sqlx::query("UPDATE verification_limits SET failed_sessions = 0 WHERE discord_id = $1")
+        .bind(discord_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+// ========= VERIFICATION LIMITS SECTION OVER
+
+// ========= LINK EVENT SECTION
+
+/// Appends an entry to the link audit log. Entries are never updated or deleted.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_link_event(
+    executor: impl AnyExecutor<'_>,
+    discord_id: &str,
+    krunker_username: &str,
+    event: LinkEventKind,
+    actor_id: &str,
+    details: Option<&str>,
+) -> Result<i64> {
+    sqlx::query_scalar(
+        "INSERT INTO link_events (discord_id, krunker_username, event, actor_id, details)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id",
+    )
+    .bind(discord_id)
+    .bind(krunker_username)
+    .bind(event.as_str())
+    .bind(actor_id)
+    .bind(details)
+    .fetch_one(executor)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_link_events_by_discord_id(
+    pool: &AnyPool,
+    discord_id: &str,
+    limit: i64,
+) -> Result<Vec<LinkEvent>> {
+    sqlx::query_as::<_, LinkEvent>(
+        "SELECT id, discord_id, krunker_username, event, actor_id, details, created_at
+         FROM link_events
+         WHERE discord_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2",
+    )
+    .bind(discord_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_link_events_by_username(
+    pool: &AnyPool,
+    krunker_username: &str,
+    limit: i64,
+) -> Result<Vec<LinkEvent>> {
+    sqlx::query_as::<_, LinkEvent>(
+        "SELECT id, discord_id, krunker_username, event, actor_id, details, created_at
+         FROM link_events
+         WHERE LOWER(krunker_username) = LOWER($1)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $2",
+    )
+    .bind(krunker_username)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Links a migration removed at or after `since`, as logged by the migration
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_links_removed_by_migration(pool: &AnyPool, since: i64) -> Result<Vec<LinkEvent>> {
+    sqlx::query_as::<_, LinkEvent>(
+        "SELECT id, discord_id, krunker_username, event, actor_id, details, created_at
+         FROM link_events
+         WHERE event = $1 AND created_at >= $2
+         ORDER BY id",
+    )
+    .bind(LinkEventKind::RemovedByMigration.as_str())
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+// ========= LINK EVENT SECTION OVER
+
+// ========= COMMAND USAGE SECTION
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_command_usage(
+    pool: &AnyPool,
+    command: &str,
+    guild_id: Option<&str>,
+    discord_id: &str,
+    latency_ms: i64,
+    error_class: Option<&str>,
+    now: i64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO command_usage (command, guild_id, discord_id, latency_ms, error_class, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(command)
+    .bind(guild_id)
+    .bind(discord_id)
+    .bind(latency_ms)
+    .bind(error_class)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Most used commands since `since`, with nearest-rank p95 latency
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_command_stats(
+    pool: &AnyPool,
+    since: i64,
+    limit: i64,
+) -> Result<Vec<CommandStats>> {
+    sqlx::query_as::<_, CommandStats>(
+        "WITH ranked AS (
+            SELECT command, latency_ms, error_class,
+                ROW_NUMBER() OVER (PARTITION BY command ORDER BY latency_ms) AS rank,
+                COUNT(*) OVER (PARTITION BY command) AS total
+            FROM command_usage
+            WHERE created_at >= $1
+        )
+        SELECT command,
+            MAX(total) AS uses,
+            SUM(CASE WHEN error_class IS NULL THEN 0 ELSE 1 END) AS errors,
+            MIN(CASE WHEN rank * 100 >= total * 95 THEN latency_ms END) AS p95_latency_ms
+        FROM ranked
+        GROUP BY command
+        ORDER BY uses DESC, command
+        LIMIT $2",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_usage_summary(pool: &AnyPool, since: i64) -> Result<UsageSummary> {
+    sqlx::query_as::<_, UsageSummary>(
+        "WITH ranked AS (
+            SELECT guild_id, discord_id, latency_ms, error_class,
+                ROW_NUMBER() OVER (ORDER BY latency_ms) AS rank,
+                COUNT(*) OVER () AS total
+            FROM command_usage
+            WHERE created_at >= $1
+        )
+        SELECT COUNT(*) AS uses,
+            COALESCE(SUM(CASE WHEN error_class IS NULL THEN 0 ELSE 1 END), 0) AS errors,
+            MIN(CASE WHEN rank * 100 >= total * 95 THEN latency_ms END) AS p95_latency_ms,
+            COUNT(DISTINCT guild_id) AS active_guilds,
+            COUNT(DISTINCT discord_id) AS active_users
+        FROM ranked",
+    )
+    .bind(since)
+    .fetch_one(pool)
+    .await
+}
+
+/// Deletes usage rows older than `before`, returning how many went
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn purge_command_usage(pool: &AnyPool, before: i64) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM command_usage WHERE created_at < $1")
+        .bind(before)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// Discord users with at least one link, and linked Krunker accounts in total
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn count_linked_users(pool: &AnyPool) -> Result<(i64, i64)> {
+    sqlx::query_as("SELECT COUNT(DISTINCT discord_id), COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+}
+
+/// Links made since `since`, whether verified or forced by a moderator
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn count_new_links(pool: &AnyPool, since: i64) -> Result<i64> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM link_events WHERE event IN ($1, $2) AND created_at >= $3",
+    )
+    .bind(LinkEventKind::Verified.as_str())
+    .bind(LinkEventKind::ForceLinked.as_str())
+    .bind(since)
+    .fetch_one(pool)
+    .await
+}
+
+// ========= COMMAND USAGE SECTION OVER
+
+// ========= MATCH SECTION
+
+const PARTICIPANT_COLUMNS: &str = "match_id, player_name, team, victory, kills, deaths, assists, \
+     score, damage_done, objective_score";
+
+/// Stores a match and its participants. Returns false, writing nothing, if the
+/// match was already stored.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn store_match(
+    pool: &AnyPool,
+    stored: &StoredMatch,
+    participants: &[StoredParticipant],
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let inserted = sqlx::query(
+        "INSERT INTO matches (match_id, map, duration_ms, played_at, fetched_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (match_id) DO NOTHING",
+    )
+    .bind(stored.match_id)
+    .bind(&stored.map)
+    .bind(stored.duration_ms)
+    .bind(&stored.played_at)
+    .bind(stored.fetched_at)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if inserted == 0 {
+        return Ok(false);
+    }
+
+    for participant in participants {
+        sqlx::query(&format!(
+            "INSERT INTO match_participants ({PARTICIPANT_COLUMNS})
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             ON CONFLICT (match_id, player_name) DO NOTHING"
+        ))
+        .bind(stored.match_id)
+        .bind(&participant.player_name)
+        .bind(participant.team)
+        .bind(participant.victory)
+        .bind(participant.kills)
+        .bind(participant.deaths)
+        .bind(participant.assists)
+        .bind(participant.score)
+        .bind(participant.damage_done)
+        .bind(participant.objective_score)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(true)
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_stored_match(pool: &AnyPool, match_id: i64) -> Result<Option<StoredMatch>> {
+    sqlx::query_as::<_, StoredMatch>(
+        "SELECT match_id, map, duration_ms, played_at, fetched_at FROM matches WHERE match_id = $1",
+    )
+    .bind(match_id)
+    .fetch_optional(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_match_participants(
+    pool: &AnyPool,
+    match_id: i64,
+) -> Result<Vec<StoredParticipant>> {
+    sqlx::query_as::<_, StoredParticipant>(&format!(
+        "SELECT {PARTICIPANT_COLUMNS}
+         FROM match_participants
+         WHERE match_id = $1
+         ORDER BY team, score DESC"
+    ))
+    .bind(match_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Every participant of the stored matches `player_name` took part in, any case,
+/// newest match first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_participants_alongside(
+    pool: &AnyPool,
+    player_name: &str,
+) -> Result<Vec<StoredParticipant>> {
+    sqlx::query_as::<_, StoredParticipant>(&format!(
+        "SELECT {PARTICIPANT_COLUMNS}
+         FROM match_participants
+         WHERE match_id IN (
+             SELECT match_id FROM match_participants WHERE LOWER(player_name) = LOWER($1)
+         )
+         ORDER BY match_id DESC, team, score DESC"
+    ))
+    .bind(player_name)
+    .fetch_all(pool)
+    .await
+}
+
+/// Stored matches every one of `player_names` took part in, any case, newest first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_shared_match_ids(pool: &AnyPool, player_names: &[&str]) -> Result<Vec<i64>> {
+    if player_names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let placeholders = (1..=player_names.len())
+        .map(|i| format!("LOWER(${i})"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT match_id
+         FROM match_participants
+         WHERE LOWER(player_name) IN ({placeholders})
+         GROUP BY match_id
+         HAVING COUNT(DISTINCT LOWER(player_name)) = {}
+         ORDER BY match_id DESC",
+        player_names.len()
+    );
+
+    let mut query = sqlx::query_scalar::<_, i64>(&sql);
+    for name in player_names {
+        query = query.bind(*name);
+    }
+    query.fetch_all(pool).await
+}
+
+/// Every stored participant, in the order the matches were played. Match ids count
+/// up, so that's match id order; `played_at` is the API's text and doesn't sort.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_all_participants(pool: &AnyPool) -> Result<Vec<StoredParticipant>> {
+    sqlx::query_as::<_, StoredParticipant>(
+        "SELECT match_id, player_name, team, victory, kills, deaths,
+             assists, score, damage_done, objective_score
+         FROM match_participants
+         ORDER BY match_id, player_name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+// ========= MATCH SECTION OVER
+
+// ========= RATING SECTION
+
+const RATING_COLUMNS: &str = "player_name, krunker_id, rating, games, wins, peak, computed_at";
+
+/// Every username the bot has seen a krunker_id for, as (username, krunker_id)
+/// pairs oldest first, from profile snapshots, renames and resolved links
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_known_player_ids(pool: &AnyPool) -> Result<Vec<(String, String)>> {
+    let rows: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT username, krunker_id, taken_at AS seen_at FROM profile_snapshots
+         UNION ALL
+         SELECT old_username, krunker_id, changed_at FROM username_history
+         WHERE krunker_id != ''
+         UNION ALL
+         SELECT new_username, krunker_id, changed_at FROM username_history
+         WHERE krunker_id != ''
+         UNION ALL
+         SELECT username, krunker_id, COALESCE(last_resolved_at, 0) FROM users
+         WHERE krunker_id IS NOT NULL
+         ORDER BY seen_at",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(username, krunker_id, _)| (username, krunker_id))
+        .collect())
+}
+
+/// Swaps the stored ratings for `ratings` in one transaction
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn replace_ratings(pool: &AnyPool, ratings: &[PlayerRating]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM player_ratings")
+        .execute(&mut *tx)
+        .await?;
+    for rating in ratings {
+        sqlx::query(&format!(
+            "INSERT INTO player_ratings (player_key, {RATING_COLUMNS})
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)"
+        ))
+        .bind(rating.key())
+        .bind(&rating.player_name)
+        .bind(&rating.krunker_id)
+        .bind(rating.rating)
+        .bind(rating.games)
+        .bind(rating.wins)
+        .bind(rating.peak)
+        .bind(rating.computed_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// A player's rating and their place among players with at least `min_games`,
+/// no place if they have fewer. Found by `krunker_id` when given, otherwise by the
+/// name they last played under.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_rating(
+    pool: &AnyPool,
+    krunker_id: Option<&str>,
+    player_name: &str,
+    min_games: i64,
+) -> Result<Option<(PlayerRating, Option<i64>)>> {
+    let Some(rating) = sqlx::query_as::<_, PlayerRating>(&format!(
+        "SELECT {RATING_COLUMNS} FROM player_ratings
+         WHERE player_key = $1 OR LOWER(player_name) = LOWER($2)
+         ORDER BY CASE WHEN player_key = $1 THEN 0 ELSE 1 END
+         LIMIT 1"
+    ))
+    .bind(PlayerRating::key_for(krunker_id, player_name))
+    .bind(player_name)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+    if rating.games < min_games {
+        return Ok(Some((rating, None)));
+    }
+
+    let above: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM player_ratings WHERE games >= $1 AND rating > $2")
+            .bind(min_games)
+            .bind(rating.rating)
+            .fetch_one(pool)
+            .await?;
+    Ok(Some((rating, Some(above + 1))))
+}
+
+/// The highest rated players with at least `min_games`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_top_ratings(
+    pool: &AnyPool,
+    min_games: i64,
+    limit: i64,
+) -> Result<Vec<PlayerRating>> {
+    sqlx::query_as::<_, PlayerRating>(&format!(
+        "SELECT {RATING_COLUMNS}
+         FROM player_ratings
+         WHERE games >= $1
+         ORDER BY rating DESC, player_key
+         LIMIT $2"
+    ))
+    .bind(min_games)
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// How many players have a rating, and how many have at least `min_games`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn count_ratings(pool: &AnyPool, min_games: i64) -> Result<(i64, i64)> {
+    sqlx::query_as("SELECT COUNT(*), COUNT(CASE WHEN games >= $1 THEN 1 END) FROM player_ratings")
+        .bind(min_games)
+        .fetch_one(pool)
+        .await
+}
+
+// ========= RATING SECTION OVER
+
+// ========= PROFILE SNAPSHOT SECTION
+
+const SNAPSHOT_COLUMNS: &str = "id, krunker_id, username, level, kr, games, kdr, clan, taken_at";
+
+/// Stores a snapshot, ignoring its `id`. Returns the new row's id.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_profile_snapshot(pool: &AnyPool, snapshot: &ProfileSnapshot) -> Result<i64> {
+    sqlx::query_scalar(
+        "INSERT INTO profile_snapshots (krunker_id, username, level, kr, games, kdr, clan, taken_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id",
+    )
+    .bind(&snapshot.krunker_id)
+    .bind(&snapshot.username)
+    .bind(snapshot.level)
+    .bind(snapshot.kr)
+    .bind(snapshot.games)
+    .bind(snapshot.kdr)
+    .bind(&snapshot.clan)
+    .bind(snapshot.taken_at)
+    .fetch_one(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_latest_snapshot(
+    pool: &AnyPool,
+    krunker_id: &str,
+) -> Result<Option<ProfileSnapshot>> {
+    sqlx::query_as::<_, ProfileSnapshot>(&format!(
+        "SELECT {SNAPSHOT_COLUMNS}
+         FROM profile_snapshots
+         WHERE krunker_id = $1
+         ORDER BY taken_at DESC, id DESC
+         LIMIT 1"
+    ))
+    .bind(krunker_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// The last snapshot taken at or before `at`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_snapshot_at(
+    pool: &AnyPool,
+    krunker_id: &str,
+    at: i64,
+) -> Result<Option<ProfileSnapshot>> {
+    sqlx::query_as::<_, ProfileSnapshot>(&format!(
+        "SELECT {SNAPSHOT_COLUMNS}
+         FROM profile_snapshots
+         WHERE krunker_id = $1 AND taken_at <= $2
+         ORDER BY taken_at DESC, id DESC
+         LIMIT 1"
+    ))
+    .bind(krunker_id)
+    .bind(at)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Snapshots taken after `since`, oldest first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_snapshots_since(
+    pool: &AnyPool,
+    krunker_id: &str,
+    since: i64,
+) -> Result<Vec<ProfileSnapshot>> {
+    sqlx::query_as::<_, ProfileSnapshot>(&format!(
+        "SELECT {SNAPSHOT_COLUMNS}
+         FROM profile_snapshots
+         WHERE krunker_id = $1 AND taken_at > $2
+         ORDER BY taken_at, id"
+    ))
+    .bind(krunker_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Notes that a player was looked up, so the daily snapshots include them
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn track_player(
+    pool: &AnyPool,
+    krunker_id: &str,
+    username: &str,
+    now: i64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO tracked_players (krunker_id, username, looked_up_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (krunker_id) DO UPDATE SET
+             username = excluded.username,
+             looked_up_at = excluded.looked_up_at",
+    )
+    .bind(krunker_id)
+    .bind(username)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Usernames the daily snapshots cover: every linked account, and players looked
+/// up after `looked_up_since`
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_snapshot_usernames(pool: &AnyPool, looked_up_since: i64) -> Result<Vec<String>> {
+    sqlx::query_scalar(
+        "SELECT username FROM users
+         UNION
+         SELECT username FROM tracked_players WHERE looked_up_at >= $1
+         ORDER BY username",
+    )
+    .bind(looked_up_since)
+    .fetch_all(pool)
+    .await
+}
+
+/// Names from stored ranked matches that have never been snapshotted, from the
+/// latest match back, so the daily snapshots can pick them up for &clan
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_unsnapshotted_players(pool: &AnyPool, limit: i64) -> Result<Vec<String>> {
+    sqlx::query_scalar(
+        "SELECT MIN(mp.player_name) FROM match_participants mp
+         WHERE NOT EXISTS (
+             SELECT 1 FROM profile_snapshots s WHERE LOWER(s.username) = LOWER(mp.player_name)
+         )
+         GROUP BY LOWER(mp.player_name)
+         ORDER BY MAX(mp.match_id) DESC, MIN(mp.player_name)
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Players whose latest snapshot has clan `tag`, any case. Only linked players and
+/// ones in stored ranked matches count, a player merely looked up doesn't.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_clan_members(pool: &AnyPool, tag: &str) -> Result<Vec<ClanMember>> {
+    sqlx::query_as::<_, ClanMember>(
+        "WITH played AS (
+             SELECT LOWER(player_name) AS name, COUNT(*) AS games,
+                 SUM(CASE WHEN victory = 1 THEN 1 ELSE 0 END) AS wins
+             FROM match_participants
+             GROUP BY LOWER(player_name)
+         )
+         SELECT s.krunker_id, s.username, s.level, s.kr, s.kdr, u.discord_id,
+             COALESCE(p.games, 0) AS ranked_games, COALESCE(p.wins, 0) AS ranked_wins,
+             s.taken_at
+         FROM profile_snapshots s
+         LEFT JOIN users u ON LOWER(u.username) = LOWER(s.username)
+         LEFT JOIN played p ON p.name = LOWER(s.username)
+         WHERE s.id = (
+                 SELECT latest.id FROM profile_snapshots latest
+                 WHERE latest.krunker_id = s.krunker_id
+                 ORDER BY latest.taken_at DESC, latest.id DESC
+                 LIMIT 1
+             )
+             AND LOWER(s.clan) = LOWER($1)
+             AND (u.id IS NOT NULL OR p.name IS NOT NULL)
+         ORDER BY s.level DESC, s.username",
+    )
+    .bind(tag)
+    .fetch_all(pool)
+    .await
+}
+
+// ========= PROFILE SNAPSHOT SECTION OVER
+
+// ========= ANNOUNCEMENT SECTION
+
+/// Points a guild's announcements of one kind at `channel_id`, replacing any earlier
+/// channel
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_announcement_channel(
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+    guild_id: &str,
+    channel_id: &str,
+    updated_by: &str,
+    now: i64,
+) -> Result<()> {
+    sqlx::query(&format!(
+        "INSERT INTO {} (guild_id, channel_id, updated_by, updated_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (guild_id) DO UPDATE SET
+             channel_id = excluded.channel_id,
+             updated_by = excluded.updated_by,
+             updated_at = excluded.updated_at",
+        feed.table()
+    ))
+    .bind(guild_id)
+    .bind(channel_id)
+    .bind(updated_by)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Turns a guild's announcements of one kind off. Returns false if they weren't on.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn clear_announcement_channel(
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+    guild_id: &str,
+) -> Result<bool> {
+    let result = sqlx::query(&format!("DELETE FROM {} WHERE guild_id = $1", feed.table()))
+        .bind(guild_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_announcement_channel(
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+    guild_id: &str,
+) -> Result<Option<AnnouncementChannel>> {
+    sqlx::query_as::<_, AnnouncementChannel>(&format!(
+        "SELECT guild_id, channel_id, updated_by, updated_at FROM {} WHERE guild_id = $1",
+        feed.table()
+    ))
+    .bind(guild_id)
+    .fetch_optional(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_announcement_channels(
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+) -> Result<Vec<AnnouncementChannel>> {
+    sqlx::query_as::<_, AnnouncementChannel>(&format!(
+        "SELECT guild_id, channel_id, updated_by, updated_at FROM {} ORDER BY guild_id",
+        feed.table()
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// The channels of one kind that `discord_id` was the last to set
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_announcement_channels_set_by(
+    pool: &AnyPool,
+    feed: AnnouncementFeed,
+    discord_id: &str,
+) -> Result<Vec<AnnouncementChannel>> {
+    sqlx::query_as::<_, AnnouncementChannel>(&format!(
+        "SELECT guild_id, channel_id, updated_by, updated_at FROM {}
+         WHERE updated_by = $1
+         ORDER BY guild_id",
+        feed.table()
+    ))
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Opts a member out of announcements about them, or back in
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_announcement_opt_out(
+    pool: &AnyPool,
+    discord_id: &str,
+    opted_out: bool,
+    now: i64,
+) -> Result<()> {
+    if opted_out {
+        sqlx::query(
+            "INSERT INTO match_feed_optouts (discord_id, created_at)
+             VALUES ($1, $2)
+             ON CONFLICT (discord_id) DO NOTHING",
+        )
+        .bind(discord_id)
+        .bind(now)
+        .execute(pool)
+        .await?;
+    } else {
+        sqlx::query("DELETE FROM match_feed_optouts WHERE discord_id = $1")
+            .bind(discord_id)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
+
+/// When the member opted out of announcements, or None if they haven't
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_announcement_opt_out(pool: &AnyPool, discord_id: &str) -> Result<Option<i64>> {
+    sqlx::query_scalar("SELECT created_at FROM match_feed_optouts WHERE discord_id = $1")
+        .bind(discord_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Linked accounts that can be announced: still resolving, and owned by members who
+/// haven't opted out
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_announced_users(pool: &AnyPool) -> Result<Vec<User>> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS}
+         FROM users
+         WHERE stale_since IS NULL
+           AND discord_id NOT IN (SELECT discord_id FROM match_feed_optouts)
+         ORDER BY id"
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// The newest match already seen for a linked account, None before its first check
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_match_feed_cursor(pool: &AnyPool, user_id: i64) -> Result<Option<i64>> {
+    sqlx::query_scalar("SELECT last_match_id FROM match_feed_cursors WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Moves an account's cursor, dropping the posts it no longer needs to remember
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_match_feed_cursor(
+    pool: &AnyPool,
+    user_id: i64,
+    last_match_id: i64,
+    now: i64,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "INSERT INTO match_feed_cursors (user_id, last_match_id, checked_at)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id) DO UPDATE SET
+             last_match_id = excluded.last_match_id,
+             checked_at = excluded.checked_at",
+    )
+    .bind(user_id)
+    .bind(last_match_id)
+    .bind(now)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM match_feed_posts WHERE user_id = $1 AND match_id <= $2")
+        .bind(user_id)
+        .bind(last_match_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The (match_id, channel_id) pairs already posted for an account past its cursor
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_match_feed_posts(pool: &AnyPool, user_id: i64) -> Result<Vec<(i64, String)>> {
+    sqlx::query_as(
+        "SELECT match_id, channel_id FROM match_feed_posts
+         WHERE user_id = $1
+         ORDER BY match_id, channel_id",
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_match_feed_post(
+    pool: &AnyPool,
+    user_id: i64,
+    match_id: i64,
+    channel_id: &str,
+    now: i64,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO match_feed_posts (user_id, match_id, channel_id, posted_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (user_id, match_id, channel_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(match_id)
+    .bind(channel_id)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Remembers that a milestone was announced. Returns false if it already had been.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_milestone(
+    pool: &AnyPool,
+    krunker_id: &str,
+    kind: MilestoneKind,
+    value: i64,
+    now: i64,
+) -> Result<bool> {
+    let result = sqlx::query(
+        "INSERT INTO milestone_announcements (krunker_id, kind, value, announced_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (krunker_id, kind, value) DO NOTHING",
+    )
+    .bind(krunker_id)
+    .bind(kind.as_str())
+    .bind(value)
+    .bind(now)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Where the milestones job left off with a player, None before its first check
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_milestone_baseline(
+    pool: &AnyPool,
+    krunker_id: &str,
+) -> Result<Option<MilestoneBaseline>> {
+    sqlx::query_as::<_, MilestoneBaseline>(
+        "SELECT krunker_id, level, kr, games, checked_at
+         FROM milestone_baselines
+         WHERE krunker_id = $1",
+    )
+    .bind(krunker_id)
+    .fetch_optional(pool)
+    .await
+}
+
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn set_milestone_baseline(pool: &AnyPool, baseline: &MilestoneBaseline) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO milestone_baselines (krunker_id, level, kr, games, checked_at)
+         VALUES ($1, $2, $3, $4, $5)
+         ON CONFLICT (krunker_id) DO UPDATE SET
+             level = excluded.level,
+             kr = excluded.kr,
+             games = excluded.games,
+             checked_at = excluded.checked_at",
+    )
+    .bind(&baseline.krunker_id)
+    .bind(baseline.level)
+    .bind(baseline.kr)
+    .bind(baseline.games)
+    .bind(baseline.checked_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ========= ANNOUNCEMENT SECTION OVER
+
+// ========= LOBBY SECTION
+
+const LOBBY_COLUMNS: &str = "id, guild_id, channel_id, started_by, team_size, rerolls, \
+     locked_in, skill_difference, created_at";
+const LOBBY_PLAYER_COLUMNS: &str = "lobby_id, discord_id, username, team, skill";
+
+/// Stores a lobby and its players in one transaction, ignoring their ids.
+/// Returns the new lobby's id.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn record_lobby(pool: &AnyPool, lobby: &Lobby, players: &[LobbyPlayer]) -> Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let lobby_id: i64 = sqlx::query_scalar(
+        "INSERT INTO lobbies (guild_id, channel_id, started_by, team_size, rerolls, locked_in,
+                              skill_difference, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+         RETURNING id",
+    )
+    .bind(&lobby.guild_id)
+    .bind(&lobby.channel_id)
+    .bind(&lobby.started_by)
+    .bind(lobby.team_size)
+    .bind(lobby.rerolls)
+    .bind(lobby.locked_in)
+    .bind(lobby.skill_difference)
+    .bind(lobby.created_at)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for player in players {
+        sqlx::query(&format!(
+            "INSERT INTO lobby_players ({LOBBY_PLAYER_COLUMNS}) VALUES ($1, $2, $3, $4, $5)"
+        ))
+        .bind(lobby_id)
+        .bind(&player.discord_id)
+        .bind(&player.username)
+        .bind(player.team)
+        .bind(player.skill)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(lobby_id)
+}
+
+/// The newest lobby made in `channel_id` with its players, by team and then
+/// strongest first
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn get_last_lobby(
+    pool: &AnyPool,
+    channel_id: &str,
+) -> Result<Option<(Lobby, Vec<LobbyPlayer>)>> {
+    let Some(lobby) = sqlx::query_as::<_, Lobby>(&format!(
+        "SELECT {LOBBY_COLUMNS}
+         FROM lobbies
+         WHERE channel_id = $1
+         ORDER BY created_at DESC, id DESC
+         LIMIT 1"
+    ))
+    .bind(channel_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let players = sqlx::query_as::<_, LobbyPlayer>(&format!(
+        "SELECT {LOBBY_PLAYER_COLUMNS}
+         FROM lobby_players
+         WHERE lobby_id = $1
+         ORDER BY team, skill DESC, discord_id"
+    ))
+    .bind(lobby.id)
+    .fetch_all(pool)
+    .await?;
+    Ok(Some((lobby, players)))
+}
+
+// ========= LOBBY SECTION OVER
+
+// ========= USER DATA SECTION
+
+/// Actor id left behind in other users' audit entries once a moderator is forgotten
+pub const FORGOTTEN_ACTOR: &str = "forgotten";
+
+/// Collects every row stored for `discord_id`, across all tables
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn export_user_data(pool: &AnyPool, discord_id: &str, now: i64) -> Result<UserData> {
+    let accounts = sqlx::query_as::<_, User>(&format!(
+        "SELECT {USER_COLUMNS} FROM users WHERE discord_id = $1 ORDER BY id"
+    ))
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let username_history = sqlx::query_as::<_, UsernameChange>(
+        "SELECT id, user_id, discord_id, krunker_id, old_username, new_username, changed_at
+         FROM username_history
+         WHERE discord_id = $1
+         ORDER BY id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let verifications = sqlx::query_as::<_, Verification>(&format!(
+        "SELECT {VERIFICATION_COLUMNS} FROM verifications WHERE discord_id = $1 ORDER BY id"
+    ))
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let verification_history = sqlx::query_as::<_, VerificationStatusChange>(
+        "SELECT id, verification_id, from_status, to_status, reason, changed_at
+         FROM verification_status_history
+         WHERE verification_id IN (SELECT id FROM verifications WHERE discord_id = $1)
+         ORDER BY id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let link_events = sqlx::query_as::<_, LinkEvent>(
+        "SELECT id, discord_id, krunker_username, event, actor_id, details, created_at
+         FROM link_events
+         WHERE discord_id = $1 OR actor_id = $1
+         ORDER BY id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let command_usage = sqlx::query_as::<_, CommandUsage>(
+        "SELECT id, command, guild_id, discord_id, latency_ms, error_class, created_at
+         FROM command_usage
+         WHERE discord_id = $1
+         ORDER BY id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let lobbies = sqlx::query_as::<_, LobbyPlayer>(&format!(
+        "SELECT {LOBBY_PLAYER_COLUMNS}
+         FROM lobby_players
+         WHERE discord_id = $1
+         ORDER BY lobby_id"
+    ))
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let match_feed_cursors = sqlx::query_as::<_, MatchFeedCursor>(
+        "SELECT user_id, last_match_id, checked_at
+         FROM match_feed_cursors
+         WHERE user_id IN (SELECT id FROM users WHERE discord_id = $1)
+         ORDER BY user_id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    let match_feed_posts = sqlx::query_as::<_, MatchFeedPost>(
+        "SELECT user_id, match_id, channel_id, posted_at
+         FROM match_feed_posts
+         WHERE user_id IN (SELECT id FROM users WHERE discord_id = $1)
+         ORDER BY user_id, match_id, channel_id",
+    )
+    .bind(discord_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(UserData {
+        discord_id: discord_id.to_string(),
+        exported_at: now,
+        accounts,
+        username_history,
+        verifications,
+        verification_history,
+        verification_limits: get_verification_limits(pool, discord_id).await?,
+        link_events,
+        command_usage,
+        announcements_opted_out_at: get_announcement_opt_out(pool, discord_id).await?,
+        lobbies,
+        match_feed_cursors,
+        match_feed_posts,
+        match_feed_channels: get_announcement_channels_set_by(
+            pool,
+            AnnouncementFeed::Matches,
+            discord_id,
+        )
+        .await?,
+        milestone_channels: get_announcement_channels_set_by(
+            pool,
+            AnnouncementFeed::Milestones,
+            discord_id,
+        )
+        .await?,
+    })
+}
+
+/// Deletes every row stored for `discord_id` in one transaction. Audit entries the
+/// user made as a moderator stay with the other user, lobbies they started stay
+/// with their other players and announcement channels they set stay with the
+/// guild, all under `FORGOTTEN_ACTOR`. The verification limits row stays, so
+/// forgetting can't be used to lift a lockout or skip the &link cooldown.
+/// Returns how many rows went.
+#[tracing::instrument(target = "db", level = "debug", skip_all)]
+pub async fn forget_user(pool: &AnyPool, discord_id: &str) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let mut removed = 0;
+
+    // history rows hang off the verifications, so they go first
+    let statements = [
+        "DELETE FROM verification_status_history
+         WHERE verification_id IN (SELECT id FROM verifications WHERE discord_id = $1)",
+        "DELETE FROM verifications WHERE discord_id = $1",
+        "DELETE FROM username_history WHERE discord_id = $1",
+        "DELETE FROM match_feed_cursors
+         WHERE user_id IN (SELECT id FROM users WHERE discord_id = $1)",
+        "DELETE FROM match_feed_posts
+         WHERE user_id IN (SELECT id FROM users WHERE discord_id = $1)",
+        "DELETE FROM users WHERE discord_id = $1",
+        "DELETE FROM link_events WHERE discord_id = $1",
+        "DELETE FROM command_usage WHERE discord_id = $1",
+        "DELETE FROM match_feed_optouts WHERE discord_id = $1",
+        "DELETE FROM lobby_players WHERE discord_id = $1",
+    ];
+    for statement in statements {
+        removed += sqlx::query(statement)
+            .bind(discord_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+    }
+
+    sqlx::query("UPDATE link_events SET actor_id = $1 WHERE actor_id = $2")
+        .bind(FORGOTTEN_ACTOR)
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("UPDATE lobbies SET started_by = $1 WHERE started_by = $2")
+        .bind(FORGOTTEN_ACTOR)
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+    for feed in [AnnouncementFeed::Matches, AnnouncementFeed::Milestones] {
+        sqlx::query(&format!(
+            "UPDATE {} SET updated_by = $1 WHERE updated_by = $2",
+            feed.table()
+        ))
+        .bind(FORGOTTEN_ACTOR)
+        .bind(discord_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    Ok(removed)
+}
+
+// ========= USER DATA SECTION OVER
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_pool;
+
+    async fn setup_test_db() -> AnyPool {
+        test_pool().await
+    }
+
+    // User tests
+    #[tokio::test]
+    async fn test_create_and_get_user() {
+        let pool = setup_test_db().await;
+
+        let user_id = create_user(&pool, "TestPlayer", "123456789", Some("US"))
+            .await
+            .unwrap();
+
+        assert!(user_id > 0);
+
+        let user = get_user_by_discord_id(&pool, "123456789")
+            .await
+            .unwrap()
+            .expect("User should exist");
+
+        assert_eq!(user.username, "TestPlayer");
+        assert_eq!(user.discord_id, "123456789");
+        assert_eq!(user.country, Some("US".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_get_user_by_username() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Player123", "999", None).await.unwrap();
+
+        let user = get_user_by_username(&pool, "Player123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+
+        assert_eq!(user.discord_id, "999");
+
+        // names match in any case, like the unique index
+        let user = get_user_by_username(&pool, "PLAYER123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(user.username, "Player123");
+    }
+
+    #[tokio::test]
+    async fn test_user_exists() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Test", "111", None).await.unwrap();
+
+        assert!(user_exists(&pool, "111").await.unwrap());
+        assert!(!user_exists(&pool, "999").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_user() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "ToDelete", "333", None).await.unwrap();
+        delete_user(&pool, "333").await.unwrap();
+
+        assert!(!user_exists(&pool, "333").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_multiple_accounts_first_is_primary() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Main", "123", None).await.unwrap();
+        create_user(&pool, "Alt", "123", None).await.unwrap();
+
+        let accounts = get_users_by_discord_id(&pool, "123").await.unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].username, "Main");
+        assert!(accounts[0].is_primary);
+        assert!(!accounts[1].is_primary);
+
+        let primary = get_user_by_discord_id(&pool, "123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(primary.username, "Main");
+    }
+
+    #[tokio::test]
+    async fn test_import_user_keeps_link_details() {
+        let pool = setup_test_db().await;
+
+        import_user(&pool, "Alt", "123", None, false, 1000)
+            .await
+            .unwrap();
+        import_user(&pool, "Main", "123", Some("NL"), true, 2000)
+            .await
+            .unwrap();
+        // an existing primary wins
+        import_user(&pool, "Other", "123", None, true, 3000)
+            .await
+            .unwrap();
+
+        let accounts = get_users_by_discord_id(&pool, "123").await.unwrap();
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[0].username, "Main");
+        assert!(accounts[0].is_primary);
+        assert_eq!(accounts[0].day_created, 2000);
+        assert_eq!(accounts[1].username, "Alt");
+        assert!(!accounts[1].is_primary);
+        assert_eq!(accounts[1].day_created, 1000);
+        assert!(!accounts[2].is_primary);
+    }
+
+    #[tokio::test]
+    async fn test_set_primary_account() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Main", "123", None).await.unwrap();
+        create_user(&pool, "Alt", "123", None).await.unwrap();
+
+        assert!(set_primary_account(&pool, "123", "Alt").await.unwrap());
+
+        let primary = get_user_by_discord_id(&pool, "123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(primary.username, "Alt");
+
+        // Someone else's account can't be made primary
+        create_user(&pool, "Other", "999", None).await.unwrap();
+        assert!(!set_primary_account(&pool, "123", "Other").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_delete_primary_account_promotes_next() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Main", "123", None).await.unwrap();
+        create_user(&pool, "Alt", "123", None).await.unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        assert!(delete_user_account(&mut conn, "123", "Main").await.unwrap());
+        assert!(!delete_user_account(&mut conn, "123", "Main").await.unwrap());
+
+        let accounts = get_users_by_discord_id(&pool, "123").await.unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].username, "Alt");
+        assert!(accounts[0].is_primary);
+    }
+
+    #[tokio::test]
+    async fn test_delete_user_by_username() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Owned", "444", None).await.unwrap();
+        create_user(&pool, "Kept", "555", None).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+        delete_user_by_username(&mut conn, "Owned").await.unwrap();
+
+        assert!(
+            get_user_by_username(&pool, "Owned")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(user_exists(&pool, "555").await.unwrap());
+    }
+
+    // Identity tests
+    #[tokio::test]
+    async fn test_rename_user_records_history() {
+        let pool = setup_test_db().await;
+
+        let id = create_user(&pool, "OldName", "123", None).await.unwrap();
+        set_user_identity(&pool, id, "krunker-1", 1000)
+            .await
+            .unwrap();
+
+        rename_user(&pool, id, "NewName", 2000).await.unwrap();
+
+        let user = get_user_by_krunker_id(&pool, "krunker-1")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(user.username, "NewName");
+        assert_eq!(user.last_resolved_at, Some(2000));
+
+        let history = get_username_history(&pool, id).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].old_username, "OldName");
+        assert_eq!(history[0].new_username, "NewName");
+        assert_eq!(history[0].krunker_id, "krunker-1");
+    }
+
+    #[tokio::test]
+    async fn test_mark_user_stale_only_flags_once() {
+        let pool = setup_test_db().await;
+
+        let id = create_user(&pool, "Gone", "123", None).await.unwrap();
+
+        assert!(mark_user_stale(&pool, id, 1000).await.unwrap());
+        assert!(!mark_user_stale(&pool, id, 2000).await.unwrap());
+
+        let user = get_user_by_discord_id(&pool, "123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(user.stale_since, Some(1000));
+        assert_eq!(user.last_resolved_at, Some(2000));
+
+        // resolving again clears the flag
+        set_user_identity(&pool, id, "krunker-1", 3000)
+            .await
+            .unwrap();
+        let user = get_user_by_discord_id(&pool, "123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(user.stale_since, None);
+    }
+
+    #[tokio::test]
+    async fn test_get_users_to_resolve_oldest_first() {
+        let pool = setup_test_db().await;
+
+        let fresh = create_user(&pool, "Fresh", "1", None).await.unwrap();
+        let old = create_user(&pool, "Old", "2", None).await.unwrap();
+        create_user(&pool, "Never", "3", None).await.unwrap();
+
+        set_user_identity(&pool, fresh, "k1", 5000).await.unwrap();
+        set_user_identity(&pool, old, "k2", 1000).await.unwrap();
+
+        let users = get_users_to_resolve(&pool, 4000, 10).await.unwrap();
+        let names: Vec<_> = users.iter().map(|u| u.username.as_str()).collect();
+        assert_eq!(names, vec!["Never", "Old"]);
+    }
+
+    // Verification tests
+    #[tokio::test]
+    async fn test_create_and_get_verification() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+        let expires = now + 600; // 10 minutes from now
+
+        let verification_id = create_verification(
+            &pool,
+            "discord123",
+            "KrunkerPlayer",
+            "VERIFY-ABC123",
+            now,
+            expires,
+        )
+        .await
+        .unwrap();
+
+        assert!(verification_id > 0);
+
+        let verification = get_verification_by_code(&pool, "VERIFY-ABC123", now)
+            .await
+            .unwrap()
+            .expect("Verification should exist");
+
+        assert_eq!(verification.discord_id, "discord123");
+        assert_eq!(verification.krunker_username, "KrunkerPlayer");
+        assert_eq!(verification.code, "VERIFY-ABC123");
+        assert_eq!(verification.attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_expired_verification_not_returned() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+        let expires = now - 100; // Expired 100 seconds ago
+
+        create_verification(
+            &pool,
+            "discord456",
+            "Player",
+            "VERIFY-EXPIRED",
+            now,
+            expires,
+        )
+        .await
+        .unwrap();
+
+        // Should not return expired verification
+        let result = get_verification_by_code(&pool, "VERIFY-EXPIRED", now)
+            .await
+            .unwrap();
+
+        assert!(
+            result.is_none(),
+            "Expired verification should not be returned"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_verification_by_discord_id() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+
+        create_verification(&pool, "active", "Player", "CODE-ACTIVE", now, now + 600)
+            .await
+            .unwrap();
+        create_verification(&pool, "stale", "Player", "CODE-STALE", now, now - 100)
+            .await
+            .unwrap();
+
+        let verification = get_verification_by_discord_id(&pool, "active", now)
+            .await
+            .unwrap()
+            .expect("Verification should exist");
+        assert_eq!(verification.code, "CODE-ACTIVE");
+
+        assert!(
+            get_verification_by_discord_id(&pool, "stale", now)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_verification() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+        let expires = now + 600;
+
+        create_verification(&pool, "discord789", "Player", "VERIFY-DELETE", now, expires)
+            .await
+            .unwrap();
+
+        delete_verification(&pool, "VERIFY-DELETE").await.unwrap();
+
+        let result = get_verification_by_code(&pool, "VERIFY-DELETE", now)
+            .await
+            .unwrap();
+
+        assert!(result.is_none(), "Deleted verification should not exist");
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_expired_verifications() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+
+        // Create expired verification
+        create_verification(&pool, "d1", "p1", "CODE1", now, now - 100)
+            .await
+            .unwrap();
+
+        // Create valid verification
+        create_verification(&pool, "d2", "p2", "CODE2", now, now + 600)
+            .await
+            .unwrap();
+
+        // Cleanup expired, never checked so not a failure
+        let (expired, attempted) = cleanup_expired_verifications(&pool, now).await.unwrap();
+        assert_eq!(expired, 1);
+        assert!(attempted.is_empty());
+
+        // Expired should be gone
+        assert!(
+            get_verification_by_code(&pool, "CODE1", now)
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // Valid should still exist
+        assert!(
+            get_verification_by_code(&pool, "CODE2", now)
+                .await
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_logs_expired_events() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+
+        create_verification(&pool, "d1", "p1", "CODE1", now, now - 100)
+            .await
+            .unwrap();
+        create_verification(&pool, "d2", "p2", "CODE2", now, now + 600)
+            .await
+            .unwrap();
+
+        cleanup_expired_verifications(&pool, now).await.unwrap();
+
+        let events = get_link_events_by_discord_id(&pool, "d1", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, LinkEventKind::Expired.as_str());
+        assert_eq!(events[0].actor_id, SYSTEM_ACTOR);
+
+        // Still pending, so nothing should be logged
+        let events = get_link_events_by_discord_id(&pool, "d2", 10)
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+    }
+
+    // Link event tests
+    #[tokio::test]
+    async fn test_link_events_by_discord_id_and_username() {
+        let pool = setup_test_db().await;
+
+        record_link_event(
+            &pool,
+            "111",
+            "Player",
+            LinkEventKind::LinkStarted,
+            "111",
+            None,
+        )
+        .await
+        .unwrap();
+        record_link_event(&pool, "111", "Player", LinkEventKind::Verified, "111", None)
+            .await
+            .unwrap();
+        record_link_event(
+            &pool,
+            "222",
+            "Other",
+            LinkEventKind::LinkStarted,
+            "222",
+            None,
+        )
+        .await
+        .unwrap();
+
+        let events = get_link_events_by_discord_id(&pool, "111", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        // Newest first
+        assert_eq!(events[0].event, LinkEventKind::Verified.as_str());
+
+        // Username lookup is case insensitive
+        let events = get_link_events_by_username(&pool, "player", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| e.discord_id == "111"));
+
+        let events = get_link_events_by_discord_id(&pool, "111", 1)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_pending_claims_excludes_own_and_expired() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+
+        create_verification(&pool, "me", "Target", "C1", now, now + 600)
+            .await
+            .unwrap();
+        create_verification(&pool, "other", "target", "C2", now, now + 600)
+            .await
+            .unwrap();
+        create_verification(&pool, "old", "Target", "C3", now, now - 100)
+            .await
+            .unwrap();
+
+        let claims = get_pending_claims(&pool, "TARGET", "me", now)
+            .await
+            .unwrap();
+        assert_eq!(claims.len(), 1);
+        assert_eq!(claims[0].discord_id, "other");
+    }
+
+    // Verification limit tests
+    #[tokio::test]
+    async fn test_record_verification_start() {
+        let pool = setup_test_db().await;
+
+        assert!(get_verification_limits(&pool, "1").await.unwrap().is_none());
+
+        record_verification_start(&pool, "1", 1000).await.unwrap();
+        record_verification_start(&pool, "1", 2000).await.unwrap();
+
+        let limits = get_verification_limits(&pool, "1")
+            .await
+            .unwrap()
+            .expect("Limits should exist");
+        assert_eq!(limits.last_started_at, 2000);
+        assert_eq!(limits.failed_sessions, 0);
+        assert_eq!(limits.locked_until, None);
+    }
+
+    #[tokio::test]
+    async fn test_record_failed_session_locks_out() {
+        let pool = setup_test_db().await;
+
+        assert_eq!(
+            record_failed_session(&pool, "1", 1000, 3, 60)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            record_failed_session(&pool, "1", 1000, 3, 60)
+                .await
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            record_failed_session(&pool, "1", 1000, 3, 60)
+                .await
+                .unwrap(),
+            Some(1060)
+        );
+
+        let limits = get_verification_limits(&pool, "1")
+            .await
+            .unwrap()
+            .expect("Limits should exist");
+        assert_eq!(limits.failed_sessions, 0);
+        assert_eq!(limits.locked_until, Some(1060));
+
+        record_failed_session(&pool, "1", 2000, 3, 60)
+            .await
+            .unwrap();
+        reset_failed_sessions(&pool, "1").await.unwrap();
+        let limits = get_verification_limits(&pool, "1")
+            .await
+            .unwrap()
+            .expect("Limits should exist");
+        assert_eq!(limits.failed_sessions, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unique_verification_code() {
+        let pool = setup_test_db().await;
+
+        let now = chrono::Utc::now().timestamp();
+        let expires = now + 600;
+
+        // Create first verification
+        create_verification(&pool, "d1", "p1", "SAME-CODE", now, expires)
+            .await
+            .unwrap();
+
+        // Try to create duplicate code - should fail
+        let result = create_verification(&pool, "d2", "p2", "SAME-CODE", now, expires).await;
+
+        assert!(result.is_err(), "Duplicate code should fail");
+    }
+
+    #[tokio::test]
+    async fn test_finish_pending_verification_records_history() {
+        let pool = setup_test_db().await;
+        let now = chrono::Utc::now().timestamp();
+
+        let id = create_verification(&pool, "d1", "p1", "CODE1", now, now + 600)
+            .await
+            .unwrap();
+
+        let mut conn = pool.acquire().await.unwrap();
+        let finished = finish_pending_verification(
+            &mut conn,
+            "d1",
+            VerificationStatus::Failed,
+            Some("too many attempts"),
+            now,
+        )
+        .await
+        .unwrap()
+        .expect("Verification should have been pending");
+        assert_eq!(finished.id, id);
+
+        // a finished verification can't move again
+        let again =
+            finish_pending_verification(&mut conn, "d1", VerificationStatus::Verified, None, now)
+                .await
+                .unwrap();
+        assert!(again.is_none());
+        drop(conn);
+
+        let history = get_verification_history(&pool, id).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].from_status, None);
+        assert_eq!(history[0].to_status, "pending");
+        assert_eq!(history[1].from_status.as_deref(), Some("pending"));
+        assert_eq!(history[1].to_status, "failed");
+        assert_eq!(history[1].reason.as_deref(), Some("too many attempts"));
+
+        let latest = get_latest_verification(&pool, "d1").await.unwrap().unwrap();
+        assert_eq!(latest.status, VerificationStatus::Failed.as_str());
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_marks_verifications_expired() {
+        let pool = setup_test_db().await;
+        let now = chrono::Utc::now().timestamp();
+
+        let id = create_verification(&pool, "d1", "p1", "CODE1", now, now - 100)
+            .await
+            .unwrap();
+        cleanup_expired_verifications(&pool, now).await.unwrap();
+
+        let latest = get_latest_verification(&pool, "d1").await.unwrap().unwrap();
+        assert_eq!(latest.id, id);
+        assert_eq!(latest.status, VerificationStatus::Expired.as_str());
+
+        let history = get_verification_history(&pool, id).await.unwrap();
+        assert_eq!(history.last().unwrap().to_status, "expired");
+    }
+
+    #[tokio::test]
+    async fn test_one_pending_verification_per_user() {
+        let pool = setup_test_db().await;
+        let now = chrono::Utc::now().timestamp();
+
+        create_verification(&pool, "d1", "p1", "CODE1", now, now + 600)
+            .await
+            .unwrap();
+        let result = create_verification(&pool, "d1", "p2", "CODE2", now, now + 600).await;
+        assert!(result.is_err(), "Second pending verification should fail");
+
+        // once the first one is finished a new one can start
+        let mut conn = pool.acquire().await.unwrap();
+        finish_pending_verification(&mut conn, "d1", VerificationStatus::Cancelled, None, now)
+            .await
+            .unwrap();
+        drop(conn);
+
+        create_verification(&pool, "d1", "p2", "CODE2", now, now + 600)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_usernames_unique_ignoring_case() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Player", "d1", None).await.unwrap();
+        let result = create_user(&pool, "PLAYER", "d2", None).await;
+
+        match result {
+            Err(sqlx::Error::Database(e)) => assert!(e.is_unique_violation()),
+            other => panic!("Expected a unique violation, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verification_status_transitions() {
+        use VerificationStatus::*;
+
+        for to in [Verified, Expired, Failed, Cancelled] {
+            assert!(Pending.can_transition_to(to));
+        }
+        assert!(!Pending.can_transition_to(Pending));
+        for from in [Verified, Expired, Failed, Cancelled] {
+            assert!(!from.can_transition_to(Pending));
+            assert!(!from.can_transition_to(Verified));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_stats_and_p95() {
+        let pool = setup_test_db().await;
+
+        // 20 uses of stats with latencies 10..=200, the last one failing
+        for i in 1..=20 {
+            let error = if i == 20 { Some("database") } else { None };
+            record_command_usage(&pool, "stats", Some("g1"), "u1", i * 10, error, 1000)
+                .await
+                .unwrap();
+        }
+        record_command_usage(&pool, "ping", None, "u2", 5, None, 1000)
+            .await
+            .unwrap();
+        // outside the window
+        record_command_usage(&pool, "ping", Some("g2"), "u3", 5, None, 10)
+            .await
+            .unwrap();
+
+        let stats = get_command_stats(&pool, 500, 10).await.unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].command, "stats");
+        assert_eq!(stats[0].uses, 20);
+        assert_eq!(stats[0].errors, 1);
+        assert_eq!(stats[0].p95_latency_ms, 190);
+        assert_eq!(stats[1].command, "ping");
+        assert_eq!(stats[1].p95_latency_ms, 5);
+
+        let summary = get_usage_summary(&pool, 500).await.unwrap();
+        assert_eq!(summary.uses, 21);
+        assert_eq!(summary.errors, 1);
+        assert_eq!(summary.active_guilds, 1);
+        assert_eq!(summary.active_users, 2);
+        assert_eq!(summary.p95_latency_ms, Some(190));
+    }
+
+    #[tokio::test]
+    async fn test_usage_summary_empty_window() {
+        let pool = setup_test_db().await;
+
+        let summary = get_usage_summary(&pool, 0).await.unwrap();
+        assert_eq!(summary.uses, 0);
+        assert_eq!(summary.errors, 0);
+        assert_eq!(summary.p95_latency_ms, None);
+    }
+
+    #[tokio::test]
+    async fn test_purge_command_usage() {
+        let pool = setup_test_db().await;
+
+        record_command_usage(&pool, "ping", None, "u1", 5, None, 100)
+            .await
+            .unwrap();
+        record_command_usage(&pool, "ping", None, "u1", 5, None, 200)
+            .await
+            .unwrap();
+
+        assert_eq!(purge_command_usage(&pool, 150).await.unwrap(), 1);
+        assert_eq!(get_usage_summary(&pool, 0).await.unwrap().uses, 1);
+    }
+
+    #[tokio::test]
+    async fn test_count_linked_users() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "A", "d1", None).await.unwrap();
+        create_user(&pool, "B", "d1", None).await.unwrap();
+        create_user(&pool, "C", "d2", None).await.unwrap();
+        record_link_event(&pool, "d1", "A", LinkEventKind::Verified, "d1", None)
+            .await
+            .unwrap();
+
+        assert_eq!(count_linked_users(&pool).await.unwrap(), (2, 3));
+        assert_eq!(count_new_links(&pool, 0).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_list_and_search_users() {
+        let pool = setup_test_db().await;
+
+        create_user(&pool, "Sniper_One", "111", None).await.unwrap();
+        create_user(&pool, "SniperTwo", "222", None).await.unwrap();
+        create_user(&pool, "Rusher", "333", None).await.unwrap();
+
+        let page = list_users(&pool, 2, 1).await.unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[0].username, "SniperTwo");
+        assert_eq!(page[1].username, "Rusher");
+
+        let found = search_users(&pool, "sniper", 10).await.unwrap();
+        assert_eq!(found.len(), 2);
+
+        // the underscore is matched literally, not as a wildcard
+        let found = search_users(&pool, "r_", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].username, "Sniper_One");
+
+        let found = search_users(&pool, "333", 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].username, "Rusher");
+    }
+
+    #[tokio::test]
+    async fn test_export_user_data() {
+        let pool = setup_test_db().await;
+
+        let main = create_user(&pool, "Main", "111", None).await.unwrap();
+        create_user(&pool, "Alt", "111", None).await.unwrap();
+        create_user(&pool, "Other", "222", None).await.unwrap();
+        create_verification(&pool, "111", "Third", "ABC123", 1000, 2000)
+            .await
+            .unwrap();
+        record_link_event(&pool, "111", "Main", LinkEventKind::Verified, "111", None)
+            .await
+            .unwrap();
+        record_link_event(
+            &pool,
+            "222",
+            "Other",
+            LinkEventKind::ForceLinked,
+            "111",
+            None,
+        )
+        .await
+        .unwrap();
+        record_command_usage(&pool, "stats", None, "111", 10, None, 1000)
+            .await
+            .unwrap();
+        set_match_feed_cursor(&pool, main, 42, 1000).await.unwrap();
+        set_announcement_channel(&pool, AnnouncementFeed::Milestones, "g1", "c1", "111", 1000)
+            .await
+            .unwrap();
+        set_announcement_channel(&pool, AnnouncementFeed::Matches, "g1", "c1", "222", 1000)
+            .await
+            .unwrap();
+
+        let data = export_user_data(&pool, "111", 5000).await.unwrap();
+        assert_eq!(data.accounts.len(), 2);
+        assert_eq!(data.verifications.len(), 1);
+        assert_eq!(data.verification_history.len(), 1);
+        assert_eq!(data.link_events.len(), 2);
+        assert_eq!(data.command_usage.len(), 1);
+        assert_eq!(data.match_feed_cursors.len(), 1);
+        assert_eq!(data.match_feed_cursors[0].last_match_id, 42);
+        assert!(data.match_feed_channels.is_empty());
+        assert_eq!(data.milestone_channels.len(), 1);
+        assert!(
+            serde_json::to_string(&data)
+                .unwrap()
+                .contains("\"exported_at\":5000")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_forget_user_leaves_no_rows() {
+        let pool = setup_test_db().await;
+
+        let user_id = create_user(&pool, "Main", "111", None).await.unwrap();
+        create_user(&pool, "Other", "222", None).await.unwrap();
+        set_user_identity(&pool, user_id, "k1", 1000).await.unwrap();
+        rename_user(&pool, user_id, "Renamed", 1100).await.unwrap();
+        create_verification(&pool, "111", "Third", "ABC123", 1000, 2000)
+            .await
+            .unwrap();
+        record_verification_start(&pool, "111", 1000).await.unwrap();
+        record_link_event(&pool, "111", "Main", LinkEventKind::Verified, "111", None)
+            .await
+            .unwrap();
+        // acting as a moderator on someone else
+        record_link_event(
+            &pool,
+            "222",
+            "Other",
+            LinkEventKind::ForceLinked,
+            "111",
+            None,
+        )
+        .await
+        .unwrap();
+        record_command_usage(&pool, "stats", Some("g1"), "111", 10, None, 1000)
+            .await
+            .unwrap();
+        set_announcement_opt_out(&pool, "111", true, 1000)
+            .await
+            .unwrap();
+        set_match_feed_cursor(&pool, user_id, 42, 1000)
+            .await
+            .unwrap();
+        let (lobby, players) = test_lobby("111", &["111", "222"]);
+        let lobby_id = record_lobby(&pool, &lobby, &players).await.unwrap();
+        set_announcement_channel(&pool, AnnouncementFeed::Matches, "g1", "c1", "111", 1000)
+            .await
+            .unwrap();
+        set_announcement_channel(&pool, AnnouncementFeed::Milestones, "g1", "c1", "111", 1000)
+            .await
+            .unwrap();
+
+        assert!(forget_user(&pool, "111").await.unwrap() > 0);
 
-pub async fn delete_user(pool: &SqlitePool, discord_id: &str) -> Result<()> {
-    sqlx::query!("DELETE FROM users WHERE discord_id = ?", discord_id)
-        .execute(pool)
-        .await?;
-    Ok(())
-}
+        // every table and every column that can hold a Discord id
+        let checks = [
+            "SELECT COUNT(*) FROM users WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM username_history WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM verifications WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM verification_status_history",
+            "SELECT COUNT(*) FROM link_events WHERE discord_id = '111' OR actor_id = '111'",
+            "SELECT COUNT(*) FROM command_usage WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM match_feed_optouts WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM match_feed_cursors",
+            "SELECT COUNT(*) FROM lobby_players WHERE discord_id = '111'",
+            "SELECT COUNT(*) FROM lobbies WHERE started_by = '111'",
+            "SELECT COUNT(*) FROM match_feed_channels WHERE updated_by = '111'",
+            "SELECT COUNT(*) FROM milestone_channels WHERE updated_by = '111'",
+        ];
+        for check in checks {
+            let count: i64 = sqlx::query_scalar(check).fetch_one(&pool).await.unwrap();
+            assert_eq!(count, 0, "{}", check);
+        }
 
-pub async fn user_exists(pool: &SqlitePool, discord_id: &str) -> Result<bool> {
-    let result = sqlx::query!(
-        "SELECT COUNT(*) as count FROM users WHERE discord_id = ?",
-        discord_id
-    )
-    .fetch_one(pool)
-    .await?;
-    Ok(result.count > 0)
-}
+        // the lockout and cooldown outlive the rest
+        let limits = get_verification_limits(&pool, "111")
+            .await
+            .unwrap()
+            .expect("Limits should stay");
+        assert_eq!(limits.last_started_at, 1000);
 
-// ========= USER SECTION OVER
+        // the other user's data and audit trail are untouched
+        assert!(user_exists(&pool, "222").await.unwrap());
+        let events = get_link_events_by_discord_id(&pool, "222", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].actor_id, FORGOTTEN_ACTOR);
+        let (lobby, players) = get_last_lobby(&pool, "c1").await.unwrap().unwrap();
+        assert_eq!(lobby.id, lobby_id);
+        assert_eq!(lobby.started_by, FORGOTTEN_ACTOR);
+        assert_eq!(players.len(), 1);
+        // the guild keeps its channels
+        let channel = get_announcement_channel(&pool, AnnouncementFeed::Milestones, "g1")
+            .await
+            .unwrap()
+            .expect("Channel should stay set");
+        assert_eq!(channel.updated_by, FORGOTTEN_ACTOR);
+    }
 
-// ========= VERIFICATION SECTION
+    fn test_lobby(started_by: &str, discord_ids: &[&str]) -> (Lobby, Vec<LobbyPlayer>) {
+        let lobby = Lobby {
+            id: 0,
+            guild_id: Some("g1".to_string()),
+            channel_id: "c1".to_string(),
+            started_by: started_by.to_string(),
+            team_size: 4,
+            rerolls: 2,
+            locked_in: 1,
+            skill_difference: -12.5,
+            created_at: 1000,
+        };
+        let players = discord_ids
+            .iter()
+            .enumerate()
+            .map(|(i, discord_id)| LobbyPlayer {
+                lobby_id: 0,
+                discord_id: discord_id.to_string(),
+                username: format!("Player{}", i),
+                team: (i % 2 + 1) as i64,
+                skill: 1500.0 + i as f64,
+            })
+            .collect();
+        (lobby, players)
+    }
 
-pub async fn create_verification(
-    pool: &SqlitePool,
-    discord_id: &str,
-    krunker_username: &str,
-    code: &str,
-    expires_at: i64,
-) -> Result<i64> {
-    let result =sqlx::query!(
-        "INSERT INTO verifications (discord_id, krunker_username, code, expires_at) VALUES (?, ?, ?, ?)",
-        discord_id,
-        krunker_username,
-        code,
-        expires_at
-    )
-    .execute(pool)
-    .await?;
+    #[tokio::test]
+    async fn test_record_and_get_last_lobby() {
+        let pool = setup_test_db().await;
+        assert!(get_last_lobby(&pool, "c1").await.unwrap().is_none());
 
-    Ok(result.last_insert_rowid())
-}
+        let (lobby, players) = test_lobby("111", &["111", "222", "333", "444"]);
+        record_lobby(&pool, &lobby, &players).await.unwrap();
+        let newer = Lobby {
+            created_at: 2000,
+            ..lobby.clone()
+        };
+        let newer_id = record_lobby(&pool, &newer, &players[..2]).await.unwrap();
+        let elsewhere = Lobby {
+            channel_id: "c2".to_string(),
+            created_at: 3000,
+            ..lobby
+        };
+        record_lobby(&pool, &elsewhere, &players).await.unwrap();
 
-pub async fn get_verification_by_code(
-    pool: &SqlitePool,
-    code: &str,
-) -> Result<Option<Verification>> {
-    sqlx::query_as::<_, Verification>(
-        "SELECT id, discord_id, krunker_username, code, created_at, expires_at, attempts
-        FROM verifications
-        WHERE code = ? AND expires_at > strftime('%s', 'now')",
-    )
-    .bind(code)
-    .fetch_optional(pool)
-    .await
-}
+        let (last, last_players) = get_last_lobby(&pool, "c1").await.unwrap().unwrap();
+        assert_eq!(last.id, newer_id);
+        assert_eq!(last.skill_difference, -12.5);
+        assert_eq!(last.locked_in, 1);
+        // team 1 first, then strongest first
+        let ids: Vec<_> = last_players.iter().map(|p| p.discord_id.as_str()).collect();
+        assert_eq!(ids, vec!["111", "222"]);
+        assert!(last_players.iter().all(|p| p.lobby_id == newer_id));
 
-pub async fn delete_verification(pool: &SqlitePool, code: &str) -> Result<()> {
-    sqlx::query!("DELETE FROM verifications WHERE code = ?", code)
-        .execute(pool)
-        .await?;
-    Ok(())
-}
+        let data = export_user_data(&pool, "222", 5000).await.unwrap();
+        assert_eq!(data.lobbies.len(), 3);
+    }
 
-pub async fn cleanup_expired_verifications(pool: &SqlitePool) -> Result<()> {
-    sqlx::query!("DELETE FROM verifications WHERE expires_at < strftime('%s', 'now')")
-        .execute(pool)
-        .await?;
-    Ok(())
-}
+    fn test_match(match_id: i64) -> (StoredMatch, Vec<StoredParticipant>) {
+        let participant = |name: &str, team: i64, victory: i64| StoredParticipant {
+            match_id,
+            player_name: name.to_string(),
+            team,
+            victory,
+            kills: 20,
+            deaths: 10,
+            assists: 5,
+            score: 3000,
+            damage_done: 2500,
+            objective_score: 100,
+        };
+        (
+            StoredMatch {
+                match_id,
+                map: "Sandstorm".to_string(),
+                duration_ms: 240_000,
+                played_at: "2024-05-01T12:00:00.000Z".to_string(),
+                fetched_at: 1000,
+            },
+            vec![participant("Alpha", 1, 1), participant("Bravo", 2, 0)],
+        )
+    }
 
-// ========= VERIFICATION SECTION OVER
+    #[tokio::test]
+    async fn test_store_match_once() {
+        let pool = setup_test_db().await;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use sqlx::SqlitePool;
+        let (stored, participants) = test_match(500);
+        assert!(store_match(&pool, &stored, &participants).await.unwrap());
+        assert!(!store_match(&pool, &stored, &participants).await.unwrap());
 
-    async fn setup_test_db() -> SqlitePool {
-        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
-        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
-        pool
+        assert_eq!(get_stored_match(&pool, 500).await.unwrap(), Some(stored));
+        let read = get_match_participants(&pool, 500).await.unwrap();
+        assert_eq!(read, participants);
+        assert!(get_stored_match(&pool, 501).await.unwrap().is_none());
     }
 
-    // User tests
     #[tokio::test]
-    async fn test_create_and_get_user() {
+    async fn test_announcement_channel() {
         let pool = setup_test_db().await;
 
-        let user_id = create_user(&pool, "TestPlayer", "123456789", Some("US"))
+        let feed = AnnouncementFeed::Matches;
+        set_announcement_channel(&pool, feed, "g1", "c1", "mod", 1000)
             .await
             .unwrap();
-
-        assert!(user_id > 0);
-
-        let user = get_user_by_discord_id(&pool, "123456789")
+        set_announcement_channel(&pool, feed, "g1", "c2", "mod", 1100)
+            .await
+            .unwrap();
+        let channel = get_announcement_channel(&pool, feed, "g1")
             .await
             .unwrap()
-            .expect("User should exist");
+            .expect("Channel should be set");
+        assert_eq!(channel.channel_id, "c2");
+        assert_eq!(
+            get_announcement_channels(&pool, feed).await.unwrap().len(),
+            1
+        );
 
-        assert_eq!(user.username, "TestPlayer");
-        assert_eq!(user.discord_id, "123456789");
-        assert_eq!(user.country, Some("US".to_string()));
+        // each kind of announcement has its own channel
+        assert!(
+            get_announcement_channel(&pool, AnnouncementFeed::Milestones, "g1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        assert!(clear_announcement_channel(&pool, feed, "g1").await.unwrap());
+        assert!(!clear_announcement_channel(&pool, feed, "g1").await.unwrap());
+        assert!(
+            get_announcement_channel(&pool, feed, "g1")
+                .await
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[tokio::test]
-    async fn test_get_user_by_username() {
+    async fn test_announced_users_and_cursor() {
         let pool = setup_test_db().await;
 
-        create_user(&pool, "Player123", "999", None).await.unwrap();
+        let main = create_user(&pool, "Main", "111", None).await.unwrap();
+        create_user(&pool, "Quiet", "222", None).await.unwrap();
+        let stale = create_user(&pool, "Gone", "333", None).await.unwrap();
+        mark_user_stale(&pool, stale, 1000).await.unwrap();
+        set_announcement_opt_out(&pool, "222", true, 1000)
+            .await
+            .unwrap();
 
-        let user = get_user_by_username(&pool, "Player123")
+        let users = get_announced_users(&pool).await.unwrap();
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].username, "Main");
+
+        set_announcement_opt_out(&pool, "222", false, 1100)
             .await
-            .unwrap()
-            .expect("User should exist");
+            .unwrap();
+        assert!(
+            get_announcement_opt_out(&pool, "222")
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(get_announced_users(&pool).await.unwrap().len(), 2);
 
-        assert_eq!(user.discord_id, "999");
+        assert!(get_match_feed_cursor(&pool, main).await.unwrap().is_none());
+        set_match_feed_cursor(&pool, main, 10, 1000).await.unwrap();
+        set_match_feed_cursor(&pool, main, 12, 1100).await.unwrap();
+        assert_eq!(get_match_feed_cursor(&pool, main).await.unwrap(), Some(12));
     }
 
     #[tokio::test]
-    async fn test_user_exists() {
+    async fn test_match_feed_posts_clear_with_cursor() {
         let pool = setup_test_db().await;
+        let main = create_user(&pool, "Main", "111", None).await.unwrap();
 
-        create_user(&pool, "Test", "111", None).await.unwrap();
+        record_match_feed_post(&pool, main, 11, "c1", 1000)
+            .await
+            .unwrap();
+        record_match_feed_post(&pool, main, 11, "c1", 1000)
+            .await
+            .unwrap();
+        record_match_feed_post(&pool, main, 12, "c2", 1000)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_match_feed_posts(&pool, main).await.unwrap(),
+            vec![(11, "c1".to_string()), (12, "c2".to_string())]
+        );
 
-        assert!(user_exists(&pool, "111").await.unwrap());
-        assert!(!user_exists(&pool, "999").await.unwrap());
+        set_match_feed_cursor(&pool, main, 11, 1100).await.unwrap();
+        assert_eq!(
+            get_match_feed_posts(&pool, main).await.unwrap(),
+            vec![(12, "c2".to_string())]
+        );
+    }
+
+    fn snapshot(kr: i64, taken_at: i64) -> ProfileSnapshot {
+        ProfileSnapshot {
+            id: 0,
+            krunker_id: "k1".to_string(),
+            username: "Player".to_string(),
+            level: 40,
+            kr,
+            games: 900,
+            kdr: 1.75,
+            clan: Some("ABC".to_string()),
+            taken_at,
+        }
     }
 
     #[tokio::test]
-    async fn test_delete_user() {
+    async fn test_latest_snapshot() {
         let pool = setup_test_db().await;
 
-        create_user(&pool, "ToDelete", "333", None).await.unwrap();
-        delete_user(&pool, "333").await.unwrap();
+        assert!(get_latest_snapshot(&pool, "k1").await.unwrap().is_none());
+        record_profile_snapshot(&pool, &snapshot(1000, 100))
+            .await
+            .unwrap();
+        let id = record_profile_snapshot(&pool, &snapshot(1500, 200))
+            .await
+            .unwrap();
 
-        assert!(!user_exists(&pool, "333").await.unwrap());
+        let latest = get_latest_snapshot(&pool, "k1")
+            .await
+            .unwrap()
+            .expect("Snapshot should exist");
+        assert_eq!(latest.id, id);
+        assert_eq!(latest.kr, 1500);
+        assert!(latest.same_profile(&snapshot(1500, 300)));
+        assert!(!latest.same_profile(&snapshot(1600, 300)));
     }
 
-    // Verification tests
     #[tokio::test]
-    async fn test_create_and_get_verification() {
+    async fn test_snapshots_over_time() {
         let pool = setup_test_db().await;
 
-        let now = chrono::Utc::now().timestamp();
-        let expires = now + 600; // 10 minutes from now
-
-        let verification_id = create_verification(
-            &pool,
-            "discord123",
-            "KrunkerPlayer",
-            "VERIFY-ABC123",
-            expires,
-        )
-        .await
-        .unwrap();
-
-        assert!(verification_id > 0);
+        for (kr, taken_at) in [(1000, 100), (1200, 200), (1500, 300)] {
+            record_profile_snapshot(&pool, &snapshot(kr, taken_at))
+                .await
+                .unwrap();
+        }
 
-        let verification = get_verification_by_code(&pool, "VERIFY-ABC123")
+        assert!(get_snapshot_at(&pool, "k1", 50).await.unwrap().is_none());
+        let at = get_snapshot_at(&pool, "k1", 250)
             .await
             .unwrap()
-            .expect("Verification should exist");
+            .expect("Snapshot should exist");
+        assert_eq!(at.kr, 1200);
 
-        assert_eq!(verification.discord_id, "discord123");
-        assert_eq!(verification.krunker_username, "KrunkerPlayer");
-        assert_eq!(verification.code, "VERIFY-ABC123");
-        assert_eq!(verification.attempts, 0);
+        let since = get_snapshots_since(&pool, "k1", 100).await.unwrap();
+        let kr: Vec<_> = since.iter().map(|s| s.kr).collect();
+        assert_eq!(kr, vec![1200, 1500]);
+        assert!(
+            get_snapshots_since(&pool, "k2", 0)
+                .await
+                .unwrap()
+                .is_empty()
+        );
     }
 
     #[tokio::test]
-    async fn test_expired_verification_not_returned() {
+    async fn test_snapshot_usernames() {
         let pool = setup_test_db().await;
 
-        let now = chrono::Utc::now().timestamp();
-        let expires = now - 100; // Expired 100 seconds ago
+        create_user(&pool, "Linked", "123", None).await.unwrap();
+        track_player(&pool, "k1", "Recent", 1000).await.unwrap();
+        track_player(&pool, "k2", "Old", 100).await.unwrap();
+        // a later lookup moves the player back into the window
+        track_player(&pool, "k3", "Again", 100).await.unwrap();
+        track_player(&pool, "k3", "Again", 900).await.unwrap();
+
+        let usernames = get_snapshot_usernames(&pool, 500).await.unwrap();
+        assert_eq!(usernames, vec!["Again", "Linked", "Recent"]);
+    }
+
+    #[tokio::test]
+    async fn test_clan_members() {
+        let pool = setup_test_db().await;
 
-        create_verification(&pool, "discord456", "Player", "VERIFY-EXPIRED", expires)
+        let member =
+            |krunker_id: &str, username: &str, level: i64, clan: &str, taken_at| ProfileSnapshot {
+                id: 0,
+                krunker_id: krunker_id.to_string(),
+                username: username.to_string(),
+                level,
+                kr: 1000,
+                games: 500,
+                kdr: 1.5,
+                clan: Some(clan.to_string()),
+                taken_at,
+            };
+        for snapshot in [
+            member("k1", "Linked", 50, "ABC", 100),
+            member("k2", "Ranked", 60, "abc", 100),
+            member("k3", "LookedUp", 70, "ABC", 100),
+            // left the clan since
+            member("k4", "Former", 40, "ABC", 100),
+            member("k4", "Former", 41, "XYZ", 200),
+        ] {
+            record_profile_snapshot(&pool, &snapshot).await.unwrap();
+        }
+        create_user(&pool, "Linked", "123", None).await.unwrap();
+        create_user(&pool, "Former", "456", None).await.unwrap();
+
+        let participant = |match_id, player_name: &str, victory| StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team: 1,
+            victory,
+            kills: 10,
+            deaths: 5,
+            assists: 2,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        };
+        let stored = |match_id| StoredMatch {
+            match_id,
+            map: "Sandstorm".to_string(),
+            duration_ms: 600_000,
+            played_at: "2024-01-01".to_string(),
+            fetched_at: 100,
+        };
+        store_match(&pool, &stored(1), &[participant(1, "ranked", 1)])
+            .await
+            .unwrap();
+        store_match(&pool, &stored(2), &[participant(2, "Ranked", 0)])
             .await
             .unwrap();
 
-        // Should not return expired verification
-        let result = get_verification_by_code(&pool, "VERIFY-EXPIRED")
+        let members = get_clan_members(&pool, "ABC").await.unwrap();
+        let names: Vec<_> = members.iter().map(|m| m.username.as_str()).collect();
+        assert_eq!(names, vec!["Ranked", "Linked"]);
+
+        assert_eq!(members[0].discord_id, None);
+        assert_eq!((members[0].ranked_games, members[0].ranked_wins), (2, 1));
+        assert_eq!(members[1].discord_id.as_deref(), Some("123"));
+        assert_eq!((members[1].ranked_games, members[1].ranked_wins), (0, 0));
+
+        assert!(get_clan_members(&pool, "NONE").await.unwrap().is_empty());
+
+        // match players without a snapshot aren't members until one is taken
+        store_match(&pool, &stored(3), &[participant(3, "Unseen", 1)])
+            .await
+            .unwrap();
+        store_match(&pool, &stored(4), &[participant(4, "Newer", 0)])
             .await
             .unwrap();
+        assert_eq!(
+            get_unsnapshotted_players(&pool, 10).await.unwrap(),
+            vec!["Newer", "Unseen"]
+        );
+        assert_eq!(
+            get_unsnapshotted_players(&pool, 1).await.unwrap(),
+            vec!["Newer"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_shared_match_ids() {
+        let pool = setup_test_db().await;
+
+        let participant = |match_id, player_name: &str| StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team: 1,
+            victory: 1,
+            kills: 10,
+            deaths: 5,
+            assists: 2,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        };
+        let stored = |match_id| StoredMatch {
+            match_id,
+            map: "Sandstorm".to_string(),
+            duration_ms: 600_000,
+            played_at: "2024-01-01".to_string(),
+            fetched_at: 100,
+        };
+        for (match_id, players) in [
+            (1, vec!["Alice", "Bob"]),
+            (2, vec!["Alice", "Carol"]),
+            (3, vec!["alice", "BOB", "Carol"]),
+        ] {
+            let participants: Vec<_> = players
+                .iter()
+                .map(|name| participant(match_id, name))
+                .collect();
+            store_match(&pool, &stored(match_id), &participants)
+                .await
+                .unwrap();
+        }
 
+        assert_eq!(
+            get_shared_match_ids(&pool, &["Alice", "Bob"])
+                .await
+                .unwrap(),
+            vec![3, 1]
+        );
+        assert_eq!(
+            get_shared_match_ids(&pool, &["ALICE", "bob", "carol"])
+                .await
+                .unwrap(),
+            vec![3]
+        );
         assert!(
-            result.is_none(),
-            "Expired verification should not be returned"
+            get_shared_match_ids(&pool, &["Bob", "Dave"])
+                .await
+                .unwrap()
+                .is_empty()
         );
+
+        let alongside = get_participants_alongside(&pool, "BOB").await.unwrap();
+        let match_ids: Vec<_> = alongside.iter().map(|p| p.match_id).collect();
+        assert_eq!(match_ids, vec![3, 3, 3, 1, 1]);
+        assert!(alongside.iter().any(|p| p.player_name == "Carol"));
     }
 
     #[tokio::test]
-    async fn test_delete_verification() {
+    async fn test_all_participants_in_match_order() {
         let pool = setup_test_db().await;
 
-        let now = chrono::Utc::now().timestamp();
-        let expires = now + 600;
+        let participant = |match_id, player_name: &str| StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team: 1,
+            victory: 1,
+            kills: 10,
+            deaths: 5,
+            assists: 2,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        };
+        // mixed formats put the later match first as text
+        for (match_id, played_at) in [(9, "2024-01-09T23:00:00Z"), (10, "2024-01-09 23:30:00")] {
+            let stored = StoredMatch {
+                match_id,
+                map: "Sandstorm".to_string(),
+                duration_ms: 600_000,
+                played_at: played_at.to_string(),
+                fetched_at: 100,
+            };
+            let participants = [participant(match_id, "Bob"), participant(match_id, "Alice")];
+            store_match(&pool, &stored, &participants).await.unwrap();
+        }
 
-        create_verification(&pool, "discord789", "Player", "VERIFY-DELETE", expires)
+        let order: Vec<_> = get_all_participants(&pool)
             .await
-            .unwrap();
+            .unwrap()
+            .into_iter()
+            .map(|p| (p.match_id, p.player_name))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (9, "Alice".to_string()),
+                (9, "Bob".to_string()),
+                (10, "Alice".to_string()),
+                (10, "Bob".to_string()),
+            ]
+        );
+    }
 
-        delete_verification(&pool, "VERIFY-DELETE").await.unwrap();
+    #[tokio::test]
+    async fn test_ratings() {
+        let pool = setup_test_db().await;
 
-        let result = get_verification_by_code(&pool, "VERIFY-DELETE")
+        let rating = |player_name: &str, rating, games| PlayerRating {
+            player_name: player_name.to_string(),
+            krunker_id: None,
+            rating,
+            games,
+            wins: games / 2,
+            peak: rating,
+            computed_at: 1000,
+        };
+        replace_ratings(&pool, &[rating("Stale", 1400.0, 20)])
             .await
             .unwrap();
+        replace_ratings(
+            &pool,
+            &[
+                rating("Alice", 1600.0, 20),
+                PlayerRating {
+                    krunker_id: Some("k2".to_string()),
+                    ..rating("Bob", 1550.0, 12)
+                },
+                rating("Newcomer", 1700.0, 2),
+            ],
+        )
+        .await
+        .unwrap();
 
-        assert!(result.is_none(), "Deleted verification should not exist");
+        assert!(get_rating(&pool, None, "Stale", 5).await.unwrap().is_none());
+        let (bob, place) = get_rating(&pool, None, "BOB", 5)
+            .await
+            .unwrap()
+            .expect("Rating should exist");
+        assert_eq!(bob.rating, 1550.0);
+        assert_eq!(place, Some(2));
+        // renamed since their last stored match
+        let (renamed, _) = get_rating(&pool, Some("k2"), "Robert", 5)
+            .await
+            .unwrap()
+            .expect("Rating should exist");
+        assert_eq!(renamed.player_name, "Bob");
+        assert!(
+            get_rating(&pool, Some("k3"), "Robert", 5)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let (_, place) = get_rating(&pool, None, "Newcomer", 5)
+            .await
+            .unwrap()
+            .expect("Rating should exist");
+        assert_eq!(place, None);
+
+        let top = get_top_ratings(&pool, 5, 10).await.unwrap();
+        let names: Vec<_> = top.iter().map(|r| r.player_name.as_str()).collect();
+        assert_eq!(names, vec!["Alice", "Bob"]);
+        assert_eq!(count_ratings(&pool, 5).await.unwrap(), (3, 2));
     }
 
     #[tokio::test]
-    async fn test_cleanup_expired_verifications() {
+    async fn test_known_player_ids() {
         let pool = setup_test_db().await;
 
-        let now = chrono::Utc::now().timestamp();
-
-        // Create expired verification
-        create_verification(&pool, "d1", "p1", "CODE1", now - 100)
+        let user_id = create_user(&pool, "OldName", "123", None).await.unwrap();
+        create_user(&pool, "Unresolved", "456", None).await.unwrap();
+        set_user_identity(&pool, user_id, "k9", 100).await.unwrap();
+        rename_user(&pool, user_id, "NewName", 200).await.unwrap();
+        record_profile_snapshot(&pool, &snapshot(1000, 50))
             .await
             .unwrap();
 
-        // Create valid verification
-        create_verification(&pool, "d2", "p2", "CODE2", now + 600)
-            .await
-            .unwrap();
+        let known = get_known_player_ids(&pool).await.unwrap();
+        assert_eq!(known[0], ("Player".to_string(), "k1".to_string()));
+        let mut names: Vec<_> = known
+            .iter()
+            .map(|(name, id)| (name.as_str(), id.as_str()))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(
+            names,
+            vec![("NewName", "k9"), ("OldName", "k9"), ("Player", "k1")]
+        );
+    }
 
-        // Cleanup expired
-        cleanup_expired_verifications(&pool).await.unwrap();
+    #[tokio::test]
+    async fn test_record_milestone_once() {
+        let pool = setup_test_db().await;
 
-        // Expired should be gone
         assert!(
-            get_verification_by_code(&pool, "CODE1")
+            record_milestone(&pool, "k1", MilestoneKind::Level, 50, 1000)
                 .await
                 .unwrap()
-                .is_none()
         );
-
-        // Valid should still exist
         assert!(
-            get_verification_by_code(&pool, "CODE2")
+            !record_milestone(&pool, "k1", MilestoneKind::Level, 50, 1100)
+                .await
+                .unwrap()
+        );
+        assert!(
+            record_milestone(&pool, "k1", MilestoneKind::Kr, 50, 1100)
                 .await
                 .unwrap()
-                .is_some()
         );
     }
 
     #[tokio::test]
-    async fn test_unique_verification_code() {
+    async fn test_milestone_baseline() {
         let pool = setup_test_db().await;
+        assert!(get_milestone_baseline(&pool, "k1").await.unwrap().is_none());
 
-        let now = chrono::Utc::now().timestamp();
-        let expires = now + 600;
+        let mut baseline = MilestoneBaseline {
+            krunker_id: "k1".to_string(),
+            level: 40,
+            kr: 9_000,
+            games: 90,
+            checked_at: 1000,
+        };
+        set_milestone_baseline(&pool, &baseline).await.unwrap();
+        baseline.level = 41;
+        baseline.checked_at = 2000;
+        set_milestone_baseline(&pool, &baseline).await.unwrap();
 
-        // Create first verification
-        create_verification(&pool, "d1", "p1", "SAME-CODE", expires)
+        // other snapshots don't move it
+        record_profile_snapshot(&pool, &snapshot(50_000, 3000))
             .await
             .unwrap();
-
-        // Try to create duplicate code - should fail
-        let result = create_verification(&pool, "d2", "p2", "SAME-CODE", expires).await;
-
-        assert!(result.is_err(), "Duplicate code should fail");
+        assert_eq!(
+            get_milestone_baseline(&pool, "k1").await.unwrap(),
+            Some(baseline)
+        );
     }
 }
@@ -0,0 +1,130 @@
+use crate::database::models::ClanMember;
+
+/// Totals over a clan's known members
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClanSummary {
+    pub members: usize,
+    pub linked: usize,
+    pub average_level: f64,
+    pub average_kdr: f64,
+    pub ranked_games: i64,
+    pub ranked_wins: i64,
+}
+
+impl ClanSummary {
+    /// Share of stored ranked games won, None without any
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.ranked_games > 0).then(|| self.ranked_wins as f64 / self.ranked_games as f64)
+    }
+}
+
+pub fn summarize(members: &[ClanMember]) -> ClanSummary {
+    let count = members.len().max(1) as f64;
+    ClanSummary {
+        members: members.len(),
+        linked: members.iter().filter(|m| m.discord_id.is_some()).count(),
+        average_level: members.iter().map(|m| m.level as f64).sum::<f64>() / count,
+        average_kdr: members.iter().map(|m| m.kdr).sum::<f64>() / count,
+        ranked_games: members.iter().map(|m| m.ranked_games).sum(),
+        ranked_wins: members.iter().map(|m| m.ranked_wins).sum(),
+    }
+}
+
+/// What the clan leaderboard is ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClanRanking {
+    Level,
+    Kr,
+    Kdr,
+}
+
+impl ClanRanking {
+    pub fn parse(arg: &str) -> Option<Self> {
+        match arg.to_lowercase().as_str() {
+            "level" => Some(ClanRanking::Level),
+            "kr" => Some(ClanRanking::Kr),
+            "kdr" | "kd" => Some(ClanRanking::Kdr),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClanRanking::Level => "level",
+            ClanRanking::Kr => "KR",
+            ClanRanking::Kdr => "K/D",
+        }
+    }
+}
+
+/// Sorts `members` best first. Ties go to the higher level, then by name.
+pub fn rank(members: &mut [ClanMember], by: ClanRanking) {
+    members.sort_by(|a, b| {
+        let primary = match by {
+            ClanRanking::Level => b.level.cmp(&a.level),
+            ClanRanking::Kr => b.kr.cmp(&a.kr),
+            ClanRanking::Kdr => b.kdr.total_cmp(&a.kdr),
+        };
+        primary
+            .then(b.level.cmp(&a.level))
+            .then_with(|| a.username.cmp(&b.username))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(username: &str, level: i64, kr: i64, kdr: f64, linked: bool) -> ClanMember {
+        ClanMember {
+            krunker_id: username.to_lowercase(),
+            username: username.to_string(),
+            level,
+            kr,
+            kdr,
+            discord_id: linked.then(|| "123".to_string()),
+            ranked_games: 10,
+            ranked_wins: 6,
+            taken_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let members = vec![
+            member("A", 40, 1000, 1.0, true),
+            member("B", 60, 5000, 2.0, false),
+        ];
+        let summary = summarize(&members);
+        assert_eq!(summary.members, 2);
+        assert_eq!(summary.linked, 1);
+        assert_eq!(summary.average_level, 50.0);
+        assert_eq!(summary.average_kdr, 1.5);
+        assert_eq!(summary.win_rate(), Some(0.6));
+
+        let empty = summarize(&[]);
+        assert_eq!(empty.average_level, 0.0);
+        assert_eq!(empty.win_rate(), None);
+    }
+
+    #[test]
+    fn test_rank() {
+        let mut members = vec![
+            member("A", 40, 9000, 1.0, true),
+            member("B", 60, 5000, 2.0, true),
+            member("C", 60, 1000, 3.0, true),
+        ];
+
+        rank(&mut members, ClanRanking::Level);
+        let names: Vec<_> = members.iter().map(|m| m.username.as_str()).collect();
+        assert_eq!(names, vec!["B", "C", "A"]);
+
+        rank(&mut members, ClanRanking::Kr);
+        let names: Vec<_> = members.iter().map(|m| m.username.as_str()).collect();
+        assert_eq!(names, vec!["A", "B", "C"]);
+
+        rank(&mut members, ClanRanking::Kdr);
+        let names: Vec<_> = members.iter().map(|m| m.username.as_str()).collect();
+        assert_eq!(names, vec!["C", "B", "A"]);
+    }
+}
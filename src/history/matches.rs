@@ -0,0 +1,102 @@
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use krunker_rs::MatchParticipant;
+use sqlx::AnyPool;
+
+use crate::database::models::{StoredMatch, StoredParticipant};
+use crate::database::queries;
+use crate::metrics::track_api;
+
+pub fn stored_participant(match_id: i64, participant: &MatchParticipant) -> StoredParticipant {
+    StoredParticipant {
+        match_id,
+        player_name: participant.mp_player_name.to_string(),
+        team: participant.mp_team as i64,
+        victory: participant.mp_victory as i64,
+        kills: participant.mp_kills as i64,
+        deaths: participant.mp_deaths as i64,
+        assists: participant.mp_assists as i64,
+        score: participant.mp_score as i64,
+        damage_done: participant.mp_damage_done as i64,
+        objective_score: participant.mp_objective_score as i64,
+    }
+}
+
+/// A ranked match from the local store, fetched with `get_match` and stored first
+/// if it isn't there yet
+pub async fn fetch_match(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    match_id: i64,
+) -> Result<(StoredMatch, Vec<StoredParticipant>), Box<dyn std::error::Error + Send + Sync>> {
+    if let Some(stored) = queries::get_stored_match(pool, match_id).await? {
+        let participants = queries::get_match_participants(pool, match_id).await?;
+        return Ok((stored, participants));
+    }
+
+    let data = track_api("get_match", krunker_api.get_match(match_id)).await?;
+    let stored = StoredMatch {
+        match_id,
+        map: data.match_map.to_string(),
+        duration_ms: data.match_duration as i64,
+        played_at: data.match_date.clone(),
+        fetched_at: Utc::now().timestamp(),
+    };
+    let participants: Vec<StoredParticipant> = data
+        .match_participants
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .map(|participant| stored_participant(match_id, participant))
+        .collect();
+
+    // another task may have stored it meanwhile, either copy is the same match
+    queries::store_match(pool, &stored, &participants).await?;
+    Ok((stored, participants))
+}
+
+/// Stores those of `match_ids` not stored yet, looking up at most `limit` in the
+/// order given. Lookup failures are logged and skipped. Returns how many were left
+/// for a later call.
+pub async fn store_missing(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    match_ids: &[i64],
+    limit: usize,
+) -> Result<usize, sqlx::Error> {
+    let mut missing = Vec::new();
+    for &match_id in match_ids {
+        if queries::get_stored_match(pool, match_id).await?.is_none() {
+            missing.push(match_id);
+        }
+    }
+
+    for &match_id in missing.iter().take(limit) {
+        if let Err(e) = fetch_match(pool, krunker_api, match_id).await {
+            tracing::warn!(match_id, "Failed to store match: {}", e);
+        }
+    }
+    Ok(missing.len().saturating_sub(limit))
+}
+
+/// Stores `username`'s recent ranked matches, newest first, see `store_missing`
+pub async fn store_recent(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    username: &str,
+    limit: usize,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let data = track_api(
+        "get_player_matches",
+        krunker_api.get_player_matches(username, None, None),
+    )
+    .await?;
+    let mut match_ids: Vec<i64> = data
+        .pmr_matches
+        .unwrap_or_default()
+        .iter()
+        .map(|m| m.pm_match_id as i64)
+        .collect();
+    match_ids.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(store_missing(pool, krunker_api, &match_ids, limit).await?)
+}
@@ -0,0 +1,9 @@
+// match data and profile snapshots kept locally, so features can look back past
+// what the API returns in one call
+
+pub mod clans;
+pub mod matches;
+pub mod pairings;
+pub mod ratings;
+pub mod snapshots;
+pub mod together;
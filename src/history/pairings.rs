@@ -0,0 +1,158 @@
+use krunker_rs::Client as KrunkerClient;
+use sqlx::AnyPool;
+use std::collections::HashMap;
+
+use super::matches::store_recent;
+use super::together::PlayerTotals;
+use crate::database::models::StoredParticipant;
+use crate::database::queries;
+
+/// Recent matches looked up per `load`, later calls pick up the rest
+const MAX_LOOKUPS: usize = 10;
+
+/// Who a player met in their stored matches. In `teammates` and `rivals` each
+/// entry is named after the other player but holds the player's own results in
+/// the games they met, most games first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pairings {
+    pub overall: PlayerTotals,
+    pub teammates: Vec<PlayerTotals>,
+    pub rivals: Vec<PlayerTotals>,
+}
+
+fn add_to(
+    entries: &mut Vec<PlayerTotals>,
+    index: &mut HashMap<String, usize>,
+    other: &str,
+    line: &StoredParticipant,
+) {
+    let slot = *index.entry(other.to_lowercase()).or_insert_with(|| {
+        entries.push(PlayerTotals::new(other));
+        entries.len() - 1
+    });
+    entries[slot].add(line);
+}
+
+fn most_games(entries: &mut [PlayerTotals]) {
+    entries.sort_by(|a, b| {
+        b.games
+            .cmp(&a.games)
+            .then(b.wins.cmp(&a.wins))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+/// Goes through `participants`, every line of the matches `player` was in, grouped
+/// by match as `get_participants_alongside` returns them
+pub fn pairings(player: &str, participants: &[StoredParticipant]) -> Pairings {
+    let mut overall = PlayerTotals::new(player);
+    let mut teammates = Vec::new();
+    let mut rivals = Vec::new();
+    let mut teammate_index = HashMap::new();
+    let mut rival_index = HashMap::new();
+
+    for lines in participants.chunk_by(|a, b| a.match_id == b.match_id) {
+        let Some(own) = lines
+            .iter()
+            .find(|line| line.player_name.eq_ignore_ascii_case(player))
+        else {
+            continue;
+        };
+        overall.add(own);
+
+        for other in lines.iter().filter(|line| !std::ptr::eq(*line, own)) {
+            if other.team == own.team {
+                add_to(&mut teammates, &mut teammate_index, &other.player_name, own);
+            } else {
+                add_to(&mut rivals, &mut rival_index, &other.player_name, own);
+            }
+        }
+    }
+
+    most_games(&mut teammates);
+    most_games(&mut rivals);
+    Pairings {
+        overall,
+        teammates,
+        rivals,
+    }
+}
+
+/// Stores `username`'s recent ranked matches, then works out their pairings from
+/// everything stored. If the API is down only what's already stored counts.
+pub async fn load(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    username: &str,
+) -> Result<Pairings, sqlx::Error> {
+    if let Err(e) = store_recent(pool, krunker_api, username, MAX_LOOKUPS).await {
+        tracing::warn!(%username, "Failed to store recent matches: {}", e);
+    }
+    let participants = queries::get_participants_alongside(pool, username).await?;
+    Ok(pairings(username, &participants))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(
+        match_id: i64,
+        player_name: &str,
+        team: i64,
+        victory: i64,
+        kills: i64,
+    ) -> StoredParticipant {
+        StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team,
+            victory,
+            kills,
+            deaths: 10,
+            assists: 0,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        }
+    }
+
+    #[test]
+    fn test_pairings() {
+        let participants = vec![
+            line(3, "alice", 1, 1, 30),
+            line(3, "Bob", 1, 1, 10),
+            line(3, "Carol", 2, 0, 10),
+            line(2, "Alice", 1, 0, 10),
+            line(2, "Bob", 1, 0, 10),
+            line(2, "Dave", 2, 1, 10),
+            line(1, "Alice", 1, 1, 20),
+            line(1, "Carol", 1, 1, 10),
+            line(1, "bob", 2, 0, 10),
+        ];
+
+        let pairings = pairings("Alice", &participants);
+        assert_eq!(pairings.overall.games, 3);
+        assert_eq!(pairings.overall.kdr(), 2.0);
+
+        let bob = &pairings.teammates[0];
+        assert_eq!(bob.name, "Bob");
+        assert_eq!((bob.games, bob.wins), (2, 1));
+        assert_eq!(bob.kdr(), 2.0);
+        assert_eq!(pairings.teammates[1].name, "Carol");
+        assert_eq!(pairings.teammates.len(), 2);
+
+        // one game each, so wins first and then by name
+        let names: Vec<_> = pairings.rivals.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["bob", "Carol", "Dave"]);
+        assert_eq!(pairings.rivals[2].wins, 0);
+    }
+
+    #[test]
+    fn test_pairings_without_player() {
+        let pairings = pairings("Alice", &[line(1, "Bob", 1, 1, 10)]);
+        assert_eq!(pairings.overall.games, 0);
+        assert!(pairings.teammates.is_empty());
+        assert!(pairings.rivals.is_empty());
+    }
+}
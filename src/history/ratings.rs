@@ -0,0 +1,378 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::AnyPool;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::database::models::{PlayerRating, StoredParticipant};
+use crate::database::queries;
+use crate::jobs::Job;
+
+/// Where every player starts
+pub const START_RATING: f64 = 1500.0;
+/// Games before a player shows on the leaderboard, their rating moves fast until then
+pub const PROVISIONAL_GAMES: i64 = 10;
+/// Rating points at stake per game
+const K_FACTOR: f64 = 32.0;
+const PROVISIONAL_K_FACTOR: f64 = 64.0;
+
+/// Team Elo over ranked matches. Each team is rated as its players' average and
+/// every player on a team gains or loses by that team's result. Matches must be
+/// applied in the order they were played; the same matches in the same order
+/// always give the same ratings. Players are keyed by krunker_id where the name
+/// they played under only ever belonged to that one id, so their games count
+/// together across renames. A name that changed hands stays keyed by name, as
+/// its matches can't be told apart by owner.
+#[derive(Debug, Default)]
+pub struct Ratings {
+    players: HashMap<String, PlayerRating>,
+    /// Lowercased names to their one krunker_id, names seen with several are left out
+    ids: HashMap<String, String>,
+}
+
+fn expected(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+impl Ratings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ratings that know the krunker_ids in `known_ids`, (username, krunker_id)
+    /// pairs. Names paired with more than one id are ignored.
+    pub fn with_ids(known_ids: &[(String, String)]) -> Self {
+        let mut owners: HashMap<String, Option<String>> = HashMap::new();
+        for (username, krunker_id) in known_ids {
+            owners
+                .entry(username.to_lowercase())
+                .and_modify(|owner| {
+                    if owner.as_deref() != Some(krunker_id.as_str()) {
+                        *owner = None;
+                    }
+                })
+                .or_insert_with(|| Some(krunker_id.clone()));
+        }
+
+        Self {
+            players: HashMap::new(),
+            ids: owners
+                .into_iter()
+                .filter_map(|(username, owner)| owner.map(|krunker_id| (username, krunker_id)))
+                .collect(),
+        }
+    }
+
+    fn krunker_id(&self, player_name: &str) -> Option<&str> {
+        self.ids
+            .get(&player_name.to_lowercase())
+            .map(String::as_str)
+    }
+
+    fn key(&self, player_name: &str) -> String {
+        PlayerRating::key_for(self.krunker_id(player_name), player_name)
+    }
+
+    fn rating(&self, player_name: &str) -> f64 {
+        self.players
+            .get(&self.key(player_name))
+            .map_or(START_RATING, |player| player.rating)
+    }
+
+    /// Rates one match from its participants. Only matches with exactly two teams,
+    /// one of which won, count. Returns whether the match counted.
+    pub fn apply(&mut self, participants: &[StoredParticipant]) -> bool {
+        let mut teams: Vec<i64> = participants.iter().map(|p| p.team).collect();
+        teams.sort_unstable();
+        teams.dedup();
+        let [first, second] = teams[..] else {
+            return false;
+        };
+        let won = |team| {
+            participants
+                .iter()
+                .any(|p| p.team == team && p.victory == 1)
+        };
+        if won(first) == won(second) {
+            return false;
+        }
+
+        let average = |team| {
+            let ratings: Vec<f64> = participants
+                .iter()
+                .filter(|p| p.team == team)
+                .map(|p| self.rating(&p.player_name))
+                .collect();
+            ratings.iter().sum::<f64>() / ratings.len() as f64
+        };
+        let (first_rating, second_rating) = (average(first), average(second));
+
+        // every change comes from the ratings before the match
+        let changes: Vec<(&StoredParticipant, f64, bool)> = participants
+            .iter()
+            .map(|p| {
+                let (own, opponent) = if p.team == first {
+                    (first_rating, second_rating)
+                } else {
+                    (second_rating, first_rating)
+                };
+                let won = won(p.team);
+                let games = self
+                    .players
+                    .get(&self.key(&p.player_name))
+                    .map_or(0, |player| player.games);
+                let k = if games < PROVISIONAL_GAMES {
+                    PROVISIONAL_K_FACTOR
+                } else {
+                    K_FACTOR
+                };
+                let actual = if won { 1.0 } else { 0.0 };
+                (p, k * (actual - expected(own, opponent)), won)
+            })
+            .collect();
+
+        for (participant, change, won) in changes {
+            let key = self.key(&participant.player_name);
+            let krunker_id = self.krunker_id(&participant.player_name).map(String::from);
+            let player = self.players.entry(key).or_insert_with(|| PlayerRating {
+                player_name: String::new(),
+                krunker_id,
+                rating: START_RATING,
+                games: 0,
+                wins: 0,
+                peak: START_RATING,
+                computed_at: 0,
+            });
+            // matches come oldest first, so this ends on the latest name
+            player.player_name = participant.player_name.clone();
+            player.rating += change;
+            player.peak = player.peak.max(player.rating);
+            player.games += 1;
+            player.wins += won as i64;
+        }
+        true
+    }
+
+    /// Everyone rated, highest first, stamped with `computed_at`
+    pub fn into_sorted(self, computed_at: i64) -> Vec<PlayerRating> {
+        let mut players: Vec<PlayerRating> = self
+            .players
+            .into_values()
+            .map(|player| PlayerRating {
+                computed_at,
+                ..player
+            })
+            .collect();
+        players.sort_by(|a, b| {
+            b.rating.total_cmp(&a.rating).then_with(|| {
+                a.player_name
+                    .to_lowercase()
+                    .cmp(&b.player_name.to_lowercase())
+            })
+        });
+        players
+    }
+}
+
+/// Rates every participant line, grouped by match in the order played as
+/// `get_all_participants` returns them, keying players by `known_ids` as
+/// `get_known_player_ids` returns them. Returns the ratings and how many matches
+/// counted.
+pub fn compute(
+    participants: &[StoredParticipant],
+    known_ids: &[(String, String)],
+    computed_at: i64,
+) -> (Vec<PlayerRating>, usize) {
+    let mut ratings = Ratings::with_ids(known_ids);
+    let rated = participants
+        .chunk_by(|a, b| a.match_id == b.match_id)
+        .filter(|lines| ratings.apply(lines))
+        .count();
+    (ratings.into_sorted(computed_at), rated)
+}
+
+/// Rebuilds every rating from the stored matches. Returns how many players were
+/// rated from how many matches.
+pub async fn recompute(pool: &AnyPool, computed_at: i64) -> Result<(usize, usize), sqlx::Error> {
+    let participants = queries::get_all_participants(pool).await?;
+    let known_ids = queries::get_known_player_ids(pool).await?;
+    let (ratings, rated) = compute(&participants, &known_ids, computed_at);
+    queries::replace_ratings(pool, &ratings).await?;
+    Ok((ratings.len(), rated))
+}
+
+/// Rebuilds every rating from the stored matches
+pub struct RecomputeRatings;
+
+#[async_trait]
+impl Job for RecomputeRatings {
+    fn name(&self) -> &'static str {
+        "ratings"
+    }
+
+    fn description(&self) -> &'static str {
+        "Recompute player ratings from the stored ranked matches"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let (players, rated) = recompute(pool, Utc::now().timestamp()).await?;
+        Ok(format!("rated {} players from {} matches", players, rated))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(match_id: i64, player_name: &str, team: i64, victory: i64) -> StoredParticipant {
+        StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team,
+            victory,
+            kills: 10,
+            deaths: 10,
+            assists: 0,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        }
+    }
+
+    fn rating<'a>(ratings: &'a [PlayerRating], name: &str) -> &'a PlayerRating {
+        ratings
+            .iter()
+            .find(|r| r.player_name.eq_ignore_ascii_case(name))
+            .expect("Player should be rated")
+    }
+
+    #[test]
+    fn test_even_match() {
+        let (ratings, rated) = compute(
+            &[
+                line(1, "A", 1, 1),
+                line(1, "B", 1, 1),
+                line(1, "C", 2, 0),
+                line(1, "D", 2, 0),
+            ],
+            &[],
+            100,
+        );
+        assert_eq!(rated, 1);
+        // evenly matched, so half the provisional K either way
+        assert_eq!(rating(&ratings, "A").rating, START_RATING + 32.0);
+        assert_eq!(rating(&ratings, "C").rating, START_RATING - 32.0);
+        assert_eq!(rating(&ratings, "C").peak, START_RATING);
+        assert_eq!(rating(&ratings, "A").wins, 1);
+        assert_eq!(ratings[0].computed_at, 100);
+        assert_eq!(ratings.last().unwrap().player_name, "D");
+    }
+
+    #[test]
+    fn test_upset_moves_more() {
+        let mut participants = Vec::new();
+        for match_id in 1..=3 {
+            participants.push(line(match_id, "Strong", 1, 1));
+            participants.push(line(match_id, "Weak", 2, 0));
+        }
+        participants.push(line(4, "Strong", 1, 0));
+        participants.push(line(4, "weak", 2, 1));
+
+        let mut ratings = Ratings::new();
+        let mut gains = Vec::new();
+        for lines in participants.chunk_by(|a, b| a.match_id == b.match_id) {
+            let before = ratings.rating("Weak");
+            ratings.apply(lines);
+            gains.push(ratings.rating("Weak") - before);
+        }
+        // each loss to the favourite costs less, the upset win pays more than 32
+        assert!(gains[0] < gains[1] && gains[1] < gains[2] && gains[2] < 0.0);
+        assert!(gains[3] > 32.0);
+        assert_eq!(rating(&ratings.into_sorted(0), "Weak").games, 4);
+    }
+
+    #[test]
+    fn test_skips_unratable_matches() {
+        let mut ratings = Ratings::new();
+        // one team
+        assert!(!ratings.apply(&[line(1, "A", 1, 1), line(1, "B", 1, 1)]));
+        // no winner
+        assert!(!ratings.apply(&[line(2, "A", 1, 0), line(2, "B", 2, 0)]));
+        // three teams
+        assert!(!ratings.apply(&[line(3, "A", 1, 1), line(3, "B", 2, 0), line(3, "C", 3, 0)]));
+        assert!(ratings.into_sorted(0).is_empty());
+    }
+
+    #[test]
+    fn test_renamed_player_keeps_rating() {
+        let known_ids = vec![
+            ("OldName".to_string(), "k9".to_string()),
+            ("NewName".to_string(), "k9".to_string()),
+        ];
+        let (ratings, rated) = compute(
+            &[
+                line(1, "OldName", 1, 1),
+                line(1, "B", 2, 0),
+                line(2, "newname", 1, 1),
+                line(2, "B", 2, 0),
+            ],
+            &known_ids,
+            0,
+        );
+        assert_eq!(rated, 2);
+        assert_eq!(ratings.len(), 2);
+        let renamed = rating(&ratings, "newname");
+        assert_eq!(renamed.games, 2);
+        assert_eq!(renamed.krunker_id.as_deref(), Some("k9"));
+        assert_eq!(rating(&ratings, "B").krunker_id, None);
+    }
+
+    #[test]
+    fn test_recycled_name_is_not_merged() {
+        // "Taken" went from k1 to k2, so its matches can't be credited to either
+        let known_ids = vec![
+            ("Taken".to_string(), "k1".to_string()),
+            ("Original".to_string(), "k1".to_string()),
+            ("Taken".to_string(), "k2".to_string()),
+        ];
+        let (ratings, _) = compute(
+            &[
+                line(1, "Taken", 1, 1),
+                line(1, "B", 2, 0),
+                line(2, "Original", 1, 1),
+                line(2, "B", 2, 0),
+            ],
+            &known_ids,
+            0,
+        );
+        assert_eq!(ratings.len(), 3);
+        assert_eq!(rating(&ratings, "Taken").krunker_id, None);
+        assert_eq!(rating(&ratings, "Taken").games, 1);
+        let original = rating(&ratings, "Original");
+        assert_eq!(original.krunker_id.as_deref(), Some("k1"));
+        assert_eq!(original.games, 1);
+    }
+
+    #[test]
+    fn test_compute_is_deterministic() {
+        let participants = vec![
+            line(1, "A", 1, 1),
+            line(1, "B", 2, 0),
+            line(2, "B", 1, 1),
+            line(2, "C", 2, 0),
+            line(3, "A", 1, 0),
+            line(3, "C", 2, 1),
+        ];
+        assert_eq!(
+            compute(&participants, &[], 0),
+            compute(&participants, &[], 0)
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use sqlx::AnyPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database::models::ProfileSnapshot;
+use crate::database::queries;
+use crate::jobs::Job;
+use crate::metrics::track_api;
+
+/// How long after a lookup an unlinked player keeps being snapshotted
+pub const LOOKUP_TRACKING_DAYS: i64 = 30;
+/// Players from stored ranked matches snapshotted for the first time per run
+const MATCH_PLAYERS_PER_RUN: i64 = 100;
+/// Pause between players, to stay well inside the API rate limit
+const SNAPSHOT_DELAY_MILLIS: u64 = 1000;
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Stores `snapshot` unless the player's numbers are the same as in their last one.
+/// Returns that last snapshot, None for a player seen for the first time.
+pub async fn record(
+    pool: &AnyPool,
+    snapshot: &ProfileSnapshot,
+) -> Result<Option<ProfileSnapshot>, sqlx::Error> {
+    let previous = queries::get_latest_snapshot(pool, &snapshot.krunker_id).await?;
+    if previous
+        .as_ref()
+        .is_none_or(|previous| !previous.same_profile(snapshot))
+    {
+        queries::record_profile_snapshot(pool, snapshot).await?;
+    }
+    Ok(previous)
+}
+
+/// Fetches `username` with `get_player` and records the result. Returns the fresh
+/// snapshot along with the one before it.
+pub async fn take(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    username: &str,
+) -> Result<(ProfileSnapshot, Option<ProfileSnapshot>), Box<dyn std::error::Error + Send + Sync>> {
+    let player = track_api("get_player", krunker_api.get_player(username)).await?;
+    let snapshot = ProfileSnapshot {
+        id: 0,
+        krunker_id: player.player_id.to_string(),
+        username: player.player_name.to_string(),
+        level: player.player_level as i64,
+        kr: player.player_kr as i64,
+        games: player.player_games as i64,
+        kdr: player.player_kdr as f64,
+        clan: Some(player.player_clan.to_string()).filter(|clan| !clan.is_empty()),
+        taken_at: Utc::now().timestamp(),
+    };
+    let previous = record(pool, &snapshot).await?;
+    Ok((snapshot, previous))
+}
+
+/// The last snapshot of each UTC day, for plotting a trend
+pub fn daily(snapshots: &[ProfileSnapshot]) -> Vec<&ProfileSnapshot> {
+    let mut days: Vec<&ProfileSnapshot> = Vec::new();
+    for snapshot in snapshots {
+        let day = snapshot.taken_at.div_euclid(SECONDS_PER_DAY);
+        match days.last_mut() {
+            Some(last) if last.taken_at.div_euclid(SECONDS_PER_DAY) == day => *last = snapshot,
+            _ => days.push(snapshot),
+        }
+    }
+    days
+}
+
+/// `values` as a line of block characters, lowest to highest
+pub fn sparkline(values: &[f64]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|&value| {
+            if max > min {
+                let step = ((value - min) / (max - min) * 7.0).round() as usize;
+                BARS[step.min(7)]
+            } else {
+                BARS[3]
+            }
+        })
+        .collect()
+}
+
+/// Snapshots every linked account, and recently looked up players, once a day.
+/// Players from stored ranked matches get a first snapshot too, so &clan knows
+/// their clan.
+pub struct SnapshotProfiles {
+    pub krunker_api: Arc<KrunkerClient>,
+}
+
+#[async_trait]
+impl Job for SnapshotProfiles {
+    fn name(&self) -> &'static str {
+        "profile_snapshots"
+    }
+
+    fn description(&self) -> &'static str {
+        "Snapshot linked, recently looked up and ranked match profiles for &progress and &clan"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(SECONDS_PER_DAY as u64)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let since = Utc::now().timestamp() - LOOKUP_TRACKING_DAYS * SECONDS_PER_DAY;
+        let mut usernames = queries::get_snapshot_usernames(pool, since).await?;
+        for player in queries::get_unsnapshotted_players(pool, MATCH_PLAYERS_PER_RUN).await? {
+            if !usernames.iter().any(|u| u.eq_ignore_ascii_case(&player)) {
+                usernames.push(player);
+            }
+        }
+
+        let mut taken = 0;
+        for username in &usernames {
+            match take(pool, &self.krunker_api, username).await {
+                Ok(_) => taken += 1,
+                Err(e) => tracing::warn!(%username, "Failed to snapshot profile: {}", e),
+            }
+            tokio::time::sleep(Duration::from_millis(SNAPSHOT_DELAY_MILLIS)).await;
+        }
+
+        Ok(format!(
+            "snapshotted {} of {} players",
+            taken,
+            usernames.len()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(kr: i64, taken_at: i64) -> ProfileSnapshot {
+        ProfileSnapshot {
+            id: 0,
+            krunker_id: "k1".to_string(),
+            username: "Player".to_string(),
+            level: 40,
+            kr,
+            games: 900,
+            kdr: 1.5,
+            clan: None,
+            taken_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_skips_unchanged() {
+        let pool = crate::database::test_pool().await;
+
+        assert!(record(&pool, &snapshot(1000, 100)).await.unwrap().is_none());
+        let previous = record(&pool, &snapshot(1000, 200)).await.unwrap();
+        assert_eq!(previous.map(|p| p.taken_at), Some(100));
+        record(&pool, &snapshot(1200, 300)).await.unwrap();
+
+        let stored = queries::get_snapshots_since(&pool, "k1", 0).await.unwrap();
+        let taken_at: Vec<_> = stored.iter().map(|s| s.taken_at).collect();
+        assert_eq!(taken_at, vec![100, 300]);
+    }
+
+    #[test]
+    fn test_daily() {
+        let day = SECONDS_PER_DAY;
+        let snapshots = vec![
+            snapshot(1000, 10),
+            snapshot(1100, 20),
+            snapshot(1200, day + 5),
+            snapshot(1300, 3 * day),
+        ];
+        let kr: Vec<_> = daily(&snapshots).iter().map(|s| s.kr).collect();
+        assert_eq!(kr, vec![1100, 1200, 1300]);
+        assert!(daily(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[1.0, 1.5, 2.0]), "▁▅█");
+        assert_eq!(sparkline(&[2.0, 2.0]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
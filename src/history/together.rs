@@ -0,0 +1,186 @@
+use crate::database::models::StoredParticipant;
+
+/// One player's totals over a set of matches
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayerTotals {
+    pub name: String,
+    pub games: i64,
+    pub wins: i64,
+    pub kills: i64,
+    pub deaths: i64,
+}
+
+impl PlayerTotals {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, participant: &StoredParticipant) {
+        self.games += 1;
+        self.wins += participant.victory;
+        self.kills += participant.kills;
+        self.deaths += participant.deaths;
+    }
+
+    pub fn kdr(&self) -> f64 {
+        if self.deaths > 0 {
+            self.kills as f64 / self.deaths as f64
+        } else {
+            self.kills as f64
+        }
+    }
+
+    /// Share of games won, None without any
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.games > 0).then(|| self.wins as f64 / self.games as f64)
+    }
+}
+
+/// A match all the players were in
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedMatch {
+    pub match_id: i64,
+    /// Whether they were all on one team
+    pub same_team: bool,
+    /// The players, of those asked about, on the winning team
+    pub winners: Vec<String>,
+}
+
+/// How a group of players did in the matches they shared, split by whether they
+/// played together or against each other. The totals follow the order the names
+/// were given in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Together {
+    pub same_team: Vec<PlayerTotals>,
+    pub opposite: Vec<PlayerTotals>,
+    pub matches: Vec<SharedMatch>,
+}
+
+impl Together {
+    pub fn same_team_games(&self) -> i64 {
+        self.same_team.first().map_or(0, |totals| totals.games)
+    }
+
+    pub fn opposite_games(&self) -> i64 {
+        self.opposite.first().map_or(0, |totals| totals.games)
+    }
+}
+
+/// Goes through each match's participants. Names match in any case, and a match
+/// missing any of the players is skipped.
+pub fn analyze(names: &[&str], matches: &[Vec<StoredParticipant>]) -> Together {
+    let mut together = Together {
+        same_team: names.iter().map(|name| PlayerTotals::new(name)).collect(),
+        opposite: names.iter().map(|name| PlayerTotals::new(name)).collect(),
+        matches: Vec::new(),
+    };
+
+    for participants in matches {
+        let lines: Option<Vec<&StoredParticipant>> = names
+            .iter()
+            .map(|name| {
+                participants
+                    .iter()
+                    .find(|p| p.player_name.eq_ignore_ascii_case(name))
+            })
+            .collect();
+        let Some(lines) = lines else {
+            continue;
+        };
+        let Some(first) = lines.first() else {
+            continue;
+        };
+
+        let same_team = lines.iter().all(|line| line.team == first.team);
+        let totals = if same_team {
+            &mut together.same_team
+        } else {
+            &mut together.opposite
+        };
+        for (totals, line) in totals.iter_mut().zip(&lines) {
+            totals.add(line);
+        }
+
+        together.matches.push(SharedMatch {
+            match_id: first.match_id,
+            same_team,
+            winners: names
+                .iter()
+                .zip(&lines)
+                .filter(|(_, line)| line.victory == 1)
+                .map(|(name, _)| name.to_string())
+                .collect(),
+        });
+    }
+
+    together
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(match_id: i64, player_name: &str, team: i64, victory: i64) -> StoredParticipant {
+        StoredParticipant {
+            match_id,
+            player_name: player_name.to_string(),
+            team,
+            victory,
+            kills: 10,
+            deaths: 5,
+            assists: 0,
+            score: 1000,
+            damage_done: 1500,
+            objective_score: 0,
+        }
+    }
+
+    #[test]
+    fn test_analyze() {
+        let matches = vec![
+            // together, won
+            vec![
+                line(1, "alice", 1, 1),
+                line(1, "Bob", 1, 1),
+                line(1, "Other", 2, 0),
+            ],
+            // together, lost
+            vec![line(2, "Alice", 2, 0), line(2, "Bob", 2, 0)],
+            // against each other, Bob won
+            vec![line(3, "Alice", 1, 0), line(3, "Bob", 2, 1)],
+            // Bob wasn't there
+            vec![line(4, "Alice", 1, 1), line(4, "Other", 2, 0)],
+        ];
+
+        let together = analyze(&["Alice", "Bob"], &matches);
+        assert_eq!(together.same_team_games(), 2);
+        assert_eq!(together.opposite_games(), 1);
+        assert_eq!(together.same_team[0].win_rate(), Some(0.5));
+        assert_eq!(together.same_team[1].name, "Bob");
+        assert_eq!(together.same_team[0].kdr(), 2.0);
+        assert_eq!(together.opposite[0].wins, 0);
+        assert_eq!(together.opposite[1].wins, 1);
+
+        assert_eq!(together.matches.len(), 3);
+        assert_eq!(
+            together.matches[2],
+            SharedMatch {
+                match_id: 3,
+                same_team: false,
+                winners: vec!["Bob".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_analyze_nothing_shared() {
+        let together = analyze(&["Alice", "Bob"], &[vec![line(1, "Alice", 1, 1)]]);
+        assert_eq!(together.same_team_games(), 0);
+        assert_eq!(together.opposite_games(), 0);
+        assert_eq!(together.opposite[0].win_rate(), None);
+        assert!(together.matches.is_empty());
+    }
+}
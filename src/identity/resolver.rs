@@ -0,0 +1,269 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use sqlx::AnyPool;
+
+use crate::config::IdentityConfig;
+use crate::database::models::{LinkEventKind, SYSTEM_ACTOR, User};
+use crate::database::queries;
+use crate::metrics::track_api;
+
+/// Default age after which a link is re-checked, overridable in config
+pub const RESOLVE_MAX_AGE_SECONDS: i64 = 24 * 60 * 60;
+/// Default links checked per run, small enough to stay well inside the API rate limit
+pub const RESOLVE_BATCH_SIZE: i64 = 25;
+/// Default pause between API calls within a batch
+pub const RESOLVE_DELAY_MILLIS: u64 = 1500;
+
+/// What a fresh lookup of a linked account's stored name tells us
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdentityCheck {
+    /// The link had no identity stored yet
+    Backfill,
+    /// The name still belongs to the linked account
+    Unchanged,
+    /// The name now belongs to a different Krunker account, so ours was renamed
+    NameTaken,
+    /// The name doesn't resolve to any account anymore
+    Missing,
+    /// The stored identity was found under a newer name and the link now follows it
+    Renamed,
+}
+
+pub fn check_identity(stored: Option<&str>, resolved: Option<&str>) -> IdentityCheck {
+    match (stored, resolved) {
+        (_, None) => IdentityCheck::Missing,
+        (None, Some(_)) => IdentityCheck::Backfill,
+        (Some(stored), Some(resolved)) if stored == resolved => IdentityCheck::Unchanged,
+        (Some(_), Some(_)) => IdentityCheck::NameTaken,
+    }
+}
+
+fn is_not_found(message: &str) -> bool {
+    message.contains("404") || message.to_lowercase().contains("not found")
+}
+
+/// Looks up the Krunker id currently behind `username`, or None if no such player exists
+pub async fn lookup_krunker_id(
+    krunker_api: &KrunkerClient,
+    username: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    match track_api("get_player", krunker_api.get_player(username)).await {
+        Ok(player) => Ok(Some(player.player_id.to_string())),
+        Err(e) if is_not_found(&e.to_string()) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Looks for the linked account under the newest name stored for its identity, e.g.
+/// from a snapshot of a match it played. The name is only followed once the API
+/// confirms it still belongs to the same identity.
+async fn follow_identity(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    user: &User,
+    krunker_id: &str,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let Some(snapshot) = queries::get_latest_snapshot(pool, krunker_id).await? else {
+        return Ok(false);
+    };
+    if snapshot.username.eq_ignore_ascii_case(&user.username) {
+        return Ok(false);
+    }
+
+    let resolved = lookup_krunker_id(krunker_api, &snapshot.username).await?;
+    if resolved.as_deref() != Some(krunker_id) {
+        return Ok(false);
+    }
+    Ok(observe_player(pool, &snapshot.username, krunker_id)
+        .await?
+        .is_some())
+}
+
+/// Re-resolves one linked account, storing its identity, following it to a new name
+/// or flagging the link as stale
+pub async fn resolve_user(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    user: &User,
+) -> Result<IdentityCheck, Box<dyn std::error::Error + Send + Sync>> {
+    let resolved = lookup_krunker_id(krunker_api, &user.username).await?;
+    let check = check_identity(user.krunker_id.as_deref(), resolved.as_deref());
+    let now = Utc::now().timestamp();
+
+    let followed = match (check, user.krunker_id.as_deref()) {
+        (IdentityCheck::NameTaken | IdentityCheck::Missing, Some(krunker_id)) => {
+            follow_identity(pool, krunker_api, user, krunker_id).await?
+        }
+        _ => false,
+    };
+    if followed {
+        return Ok(IdentityCheck::Renamed);
+    }
+
+    match (check, resolved.as_deref()) {
+        (IdentityCheck::Backfill | IdentityCheck::Unchanged, Some(id)) => {
+            queries::set_user_identity(pool, user.id, id, now).await?;
+        }
+        _ => {
+            if queries::mark_user_stale(pool, user.id, now).await? {
+                let details = match check {
+                    IdentityCheck::NameTaken => "name now belongs to a different account",
+                    _ => "name no longer resolves",
+                };
+                queries::record_link_event(
+                    pool,
+                    &user.discord_id,
+                    &user.username,
+                    LinkEventKind::Flagged,
+                    SYSTEM_ACTOR,
+                    Some(details),
+                )
+                .await?;
+            }
+        }
+    }
+
+    Ok(check)
+}
+
+/// Called whenever the bot sees a player's current name and identity. If the identity
+/// belongs to a link stored under another name, the account was renamed and the link
+/// is updated. Returns the link as it was before the rename.
+pub async fn observe_player(
+    pool: &AnyPool,
+    username: &str,
+    krunker_id: &str,
+) -> Result<Option<User>, Box<dyn std::error::Error + Send + Sync>> {
+    let user = match queries::get_user_by_krunker_id(pool, krunker_id).await? {
+        Some(user) if user.username != username => user,
+        _ => return Ok(None),
+    };
+
+    queries::rename_user(pool, user.id, username, Utc::now().timestamp()).await?;
+
+    let details = format!("{} -> {}", user.username, username);
+    queries::record_link_event(
+        pool,
+        &user.discord_id,
+        username,
+        LinkEventKind::Renamed,
+        SYSTEM_ACTOR,
+        Some(&details),
+    )
+    .await?;
+
+    tracing::info!(
+        discord_id = %user.discord_id,
+        old_username = %user.username,
+        new_username = %username,
+        "Linked account renamed"
+    );
+
+    Ok(Some(user))
+}
+
+/// Re-resolves a batch of the least recently checked links, returning how many
+/// were checked
+pub async fn resolve_batch(
+    pool: &AnyPool,
+    krunker_api: &KrunkerClient,
+    config: &IdentityConfig,
+) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let cutoff = Utc::now().timestamp() - config.max_age_seconds;
+    let users = queries::get_users_to_resolve(pool, cutoff, config.batch_size).await?;
+    let checked = users.len();
+
+    for user in users {
+        match resolve_user(pool, krunker_api, &user).await {
+            Ok(IdentityCheck::NameTaken | IdentityCheck::Missing) => {
+                tracing::warn!(
+                    discord_id = %user.discord_id,
+                    username = %user.username,
+                    "Linked account no longer resolves"
+                );
+            }
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(username = %user.username, "Failed to resolve linked account: {}", e);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(config.delay_millis)).await;
+    }
+
+    Ok(checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_identity() {
+        assert_eq!(check_identity(None, Some("1")), IdentityCheck::Backfill);
+        assert_eq!(
+            check_identity(Some("1"), Some("1")),
+            IdentityCheck::Unchanged
+        );
+        assert_eq!(
+            check_identity(Some("1"), Some("2")),
+            IdentityCheck::NameTaken
+        );
+        assert_eq!(check_identity(Some("1"), None), IdentityCheck::Missing);
+        assert_eq!(check_identity(None, None), IdentityCheck::Missing);
+    }
+
+    #[test]
+    fn test_is_not_found() {
+        assert!(is_not_found("HTTP 404"));
+        assert!(is_not_found("Player Not Found"));
+        assert!(!is_not_found("HTTP 403: Not allowed"));
+    }
+
+    #[tokio::test]
+    async fn test_observe_player_renames_link() {
+        let pool = crate::database::test_pool().await;
+
+        let id = queries::create_user(&pool, "OldName", "123", None)
+            .await
+            .unwrap();
+        queries::set_user_identity(&pool, id, "krunker-1", 1000)
+            .await
+            .unwrap();
+
+        // same name, nothing to do
+        assert!(
+            observe_player(&pool, "OldName", "krunker-1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let before = observe_player(&pool, "NewName", "krunker-1")
+            .await
+            .unwrap()
+            .expect("Link should be renamed");
+        assert_eq!(before.username, "OldName");
+
+        let user = queries::get_user_by_discord_id(&pool, "123")
+            .await
+            .unwrap()
+            .expect("User should exist");
+        assert_eq!(user.username, "NewName");
+
+        let events = queries::get_link_events_by_discord_id(&pool, "123", 10)
+            .await
+            .unwrap();
+        assert_eq!(events[0].event, LinkEventKind::Renamed.as_str());
+
+        // unknown identities are ignored
+        assert!(
+            observe_player(&pool, "Someone", "krunker-2")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
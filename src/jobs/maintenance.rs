@@ -0,0 +1,132 @@
+// the jobs main registers with the scheduler
+
+use async_trait::async_trait;
+use chrono::Utc;
+use krunker_rs::Client as KrunkerClient;
+use serenity::prelude::{RwLock, TypeMap};
+use sqlx::AnyPool;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::Job;
+use crate::config::IdentityConfig;
+use crate::database::queries;
+use crate::identity::resolver;
+use crate::verification::flow;
+
+/// Marks overdue pending verifications as expired. The verification flow also
+/// does this as it goes, this catches sessions nobody comes back to.
+pub struct ExpireVerifications {
+    /// The client's shared data, read for the live lockout settings
+    pub data: Arc<RwLock<TypeMap>>,
+}
+
+#[async_trait]
+impl Job for ExpireVerifications {
+    fn name(&self) -> &'static str {
+        "expire_verifications"
+    }
+
+    fn description(&self) -> &'static str {
+        "Mark overdue pending verifications as expired"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let settings = crate::bot::state::config_from(&self.data)
+            .await
+            .verification_settings();
+        let expired =
+            flow::expire_stale_verifications(pool, settings, Utc::now().timestamp()).await?;
+        Ok(format!("expired {} verifications", expired))
+    }
+}
+
+/// Deletes command usage older than `analytics.retention_days`
+pub struct UsageRetention {
+    pub retention_days: i64,
+}
+
+#[async_trait]
+impl Job for UsageRetention {
+    fn name(&self) -> &'static str {
+        "usage_retention"
+    }
+
+    fn description(&self) -> &'static str {
+        "Delete command usage past the retention window"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let cutoff = Utc::now().timestamp() - self.retention_days * 24 * 60 * 60;
+        let purged = queries::purge_command_usage(pool, cutoff).await?;
+        Ok(format!("purged {} usage rows", purged))
+    }
+}
+
+/// Re-checks the least recently resolved links, flagging stale ones
+pub struct ResolveIdentities {
+    pub krunker_api: Arc<KrunkerClient>,
+    pub config: IdentityConfig,
+}
+
+#[async_trait]
+impl Job for ResolveIdentities {
+    fn name(&self) -> &'static str {
+        "resolve_identities"
+    }
+
+    fn description(&self) -> &'static str {
+        "Re-check linked usernames against the Krunker API"
+    }
+
+    fn default_every(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    async fn run(
+        &self,
+        pool: &AnyPool,
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let checked = resolver::resolve_batch(pool, &self.krunker_api, &self.config).await?;
+        Ok(format!("checked {} links", checked))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_expire_verifications_job() {
+        let pool = crate::database::test_pool().await;
+        let now = Utc::now().timestamp();
+        queries::create_verification(&pool, "123", "Player", "VERIFY-OLD", now - 600, now - 300)
+            .await
+            .unwrap();
+        queries::create_verification(&pool, "456", "Other", "VERIFY-NEW", now, now + 300)
+            .await
+            .unwrap();
+
+        let job = ExpireVerifications {
+            data: Arc::new(RwLock::new(TypeMap::new())),
+        };
+        let summary = job.run(&pool).await.unwrap();
+        assert_eq!(summary, "expired 1 verifications");
+        let summary = job.run(&pool).await.unwrap();
+        assert_eq!(summary, "expired 0 verifications");
+    }
+}
@@ -0,0 +1,363 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::AnyPool;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinSet;
+use tokio::time::MissedTickBehavior;
+
+use crate::config::{ConfigError, JobConfig, JobsConfig};
+use crate::lifecycle::Lifecycle;
+use crate::metrics::metrics;
+
+pub mod maintenance;
+
+/// A maintenance task the scheduler runs on an interval
+#[async_trait]
+pub trait Job: Send + Sync {
+    /// Also the job's key under `[jobs]` in the config
+    fn name(&self) -> &'static str;
+    fn description(&self) -> &'static str;
+    /// Used when the config doesn't set `every`
+    fn default_every(&self) -> Duration;
+    /// Returns a short summary of what was done, shown by &jobs
+    async fn run(&self, pool: &AnyPool)
+    -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// How the most recent run of a job went
+#[derive(Debug, Clone)]
+pub struct JobRun {
+    pub started_at: i64,
+    pub elapsed: Duration,
+    /// The job's summary, or its error
+    pub result: Result<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TriggerError {
+    UnknownJob,
+    /// A run was already in progress, this one was skipped
+    AlreadyRunning,
+}
+
+impl fmt::Display for TriggerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerError::UnknownJob => write!(f, "no such job"),
+            TriggerError::AlreadyRunning => write!(f, "the job is already running"),
+        }
+    }
+}
+
+impl std::error::Error for TriggerError {}
+
+/// A job as &jobs lists it
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub every: Duration,
+    pub enabled: bool,
+    pub running: bool,
+    pub last_run: Option<JobRun>,
+}
+
+struct ScheduledJob {
+    job: Arc<dyn Job>,
+    every: Duration,
+    enabled: bool,
+    running: AtomicBool,
+    last_run: Mutex<Option<JobRun>>,
+}
+
+/// Clears the running flag however the run ends, including being cancelled at shutdown
+struct RunningGuard<'a>(&'a AtomicBool);
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Runs each registered job on its own interval. A job never overlaps with itself:
+/// a tick or manual trigger that finds it still running is skipped.
+pub struct Scheduler {
+    pool: AnyPool,
+    jobs: Vec<ScheduledJob>,
+}
+
+impl Scheduler {
+    /// Jobs missing from `config` run on their default interval. Fails if any job's
+    /// `every` isn't a valid interval.
+    pub fn new(
+        pool: AnyPool,
+        jobs: Vec<Arc<dyn Job>>,
+        config: &JobsConfig,
+    ) -> Result<Self, ConfigError> {
+        let jobs = jobs
+            .into_iter()
+            .map(|job| {
+                let config = config.get(job.name()).cloned().unwrap_or_default();
+                (job, config)
+            })
+            .collect();
+        Self::with_configs(pool, jobs)
+    }
+
+    /// Each job with its own config, for jobs that aren't under `[jobs]`
+    pub fn with_configs(
+        pool: AnyPool,
+        jobs: Vec<(Arc<dyn Job>, JobConfig)>,
+    ) -> Result<Self, ConfigError> {
+        let mut scheduled = Vec::new();
+        let mut problems = Vec::new();
+        for (job, config) in jobs {
+            let every = match config.interval(job.name()) {
+                Ok(every) => every.unwrap_or_else(|| job.default_every()),
+                Err(problem) => {
+                    problems.push(problem);
+                    continue;
+                }
+            };
+            scheduled.push(ScheduledJob {
+                job,
+                every,
+                enabled: config.enabled,
+                running: AtomicBool::new(false),
+                last_run: Mutex::new(None),
+            });
+        }
+        if !problems.is_empty() {
+            return Err(ConfigError::Invalid(problems));
+        }
+
+        Ok(Self {
+            pool,
+            jobs: scheduled,
+        })
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        self.jobs
+            .iter()
+            .map(|scheduled| JobStatus {
+                name: scheduled.job.name(),
+                description: scheduled.job.description(),
+                every: scheduled.every,
+                enabled: scheduled.enabled,
+                running: scheduled.running.load(Ordering::SeqCst),
+                last_run: scheduled.last_run.lock().unwrap().clone(),
+            })
+            .collect()
+    }
+
+    /// Runs a job now, whether or not it's enabled
+    pub async fn trigger(&self, name: &str) -> Result<JobRun, TriggerError> {
+        let scheduled = self
+            .jobs
+            .iter()
+            .find(|scheduled| scheduled.job.name() == name)
+            .ok_or(TriggerError::UnknownJob)?;
+        self.run_job(scheduled).await
+    }
+
+    async fn run_job(&self, scheduled: &ScheduledJob) -> Result<JobRun, TriggerError> {
+        let name = scheduled.job.name();
+        if scheduled
+            .running
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            tracing::warn!(job = name, "Job still running, skipping");
+            metrics().record_job_skipped(name);
+            return Err(TriggerError::AlreadyRunning);
+        }
+        let _running = RunningGuard(&scheduled.running);
+
+        let started_at = Utc::now().timestamp();
+        let started = Instant::now();
+        let result = scheduled.job.run(&self.pool).await;
+        let elapsed = started.elapsed();
+        metrics().record_job(name, result.is_ok(), elapsed);
+
+        match &result {
+            Ok(summary) => tracing::info!(
+                job = name,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "Job finished: {}",
+                summary
+            ),
+            Err(e) => tracing::error!(job = name, "Job failed: {}", e),
+        }
+
+        let run = JobRun {
+            started_at,
+            elapsed,
+            result: result.map_err(|e| e.to_string()),
+        };
+        *scheduled.last_run.lock().unwrap() = Some(run.clone());
+        Ok(run)
+    }
+
+    /// Runs the enabled jobs on their intervals until shutdown. Each job first runs
+    /// right away.
+    pub async fn run(self: Arc<Self>, lifecycle: Lifecycle) {
+        let mut loops = JoinSet::new();
+        for index in 0..self.jobs.len() {
+            if !self.jobs[index].enabled {
+                tracing::info!(job = self.jobs[index].job.name(), "Job disabled");
+                continue;
+            }
+            loops.spawn(Arc::clone(&self).job_loop(index, lifecycle.clone()));
+        }
+
+        while loops.join_next().await.is_some() {}
+        tracing::info!("Job scheduler stopped");
+    }
+
+    async fn job_loop(self: Arc<Self>, index: usize, lifecycle: Lifecycle) {
+        let scheduled = &self.jobs[index];
+        let mut interval = tokio::time::interval(scheduled.every);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = lifecycle.stopped() => break,
+            }
+
+            // jobs are safe to interrupt, whatever's left is picked up on the next start
+            tokio::select! {
+                _ = self.run_job(scheduled) => {}
+                _ = lifecycle.stopped() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::sync::Notify;
+
+    /// Counts its runs and, when `hold` is set, waits on it before finishing
+    struct TestJob {
+        runs: AtomicUsize,
+        hold: Option<Arc<Notify>>,
+    }
+
+    #[async_trait]
+    impl Job for TestJob {
+        fn name(&self) -> &'static str {
+            "test"
+        }
+
+        fn description(&self) -> &'static str {
+            "test job"
+        }
+
+        fn default_every(&self) -> Duration {
+            Duration::from_secs(60)
+        }
+
+        async fn run(
+            &self,
+            _pool: &AnyPool,
+        ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+            let runs = self.runs.fetch_add(1, Ordering::SeqCst) + 1;
+            if let Some(hold) = &self.hold {
+                hold.notified().await;
+            }
+            Ok(format!("run {}", runs))
+        }
+    }
+
+    fn test_job(hold: Option<Arc<Notify>>) -> Arc<TestJob> {
+        Arc::new(TestJob {
+            runs: AtomicUsize::new(0),
+            hold,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_trigger_records_last_run() {
+        let pool = crate::database::test_pool().await;
+        let job = test_job(None);
+        let config = JobConfig {
+            enabled: false,
+            every: Some("5m".to_string()),
+        };
+        let scheduler =
+            Scheduler::with_configs(pool, vec![(job.clone() as Arc<dyn Job>, config)]).unwrap();
+
+        let status = &scheduler.statuses()[0];
+        assert_eq!(status.every, Duration::from_secs(5 * 60));
+        assert!(!status.enabled);
+        assert!(status.last_run.is_none());
+
+        let run = scheduler.trigger("test").await.unwrap();
+        assert_eq!(run.result, Ok("run 1".to_string()));
+        assert_eq!(
+            scheduler.statuses()[0].last_run.as_ref().unwrap().result,
+            Ok("run 1".to_string())
+        );
+
+        assert_eq!(
+            scheduler.trigger("missing").await.unwrap_err(),
+            TriggerError::UnknownJob
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_interval_is_an_error() {
+        let pool = crate::database::test_pool().await;
+        let config = JobConfig {
+            enabled: true,
+            every: Some("often".to_string()),
+        };
+
+        let Err(ConfigError::Invalid(problems)) =
+            Scheduler::with_configs(pool, vec![(test_job(None) as Arc<dyn Job>, config)])
+        else {
+            panic!("Scheduler should reject the interval");
+        };
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("jobs.test.every"));
+    }
+
+    #[tokio::test]
+    async fn test_overlapping_run_is_skipped() {
+        let pool = crate::database::test_pool().await;
+        let hold = Arc::new(Notify::new());
+        let job = test_job(Some(hold.clone()));
+        let scheduler = Arc::new(
+            Scheduler::with_configs(
+                pool,
+                vec![(job.clone() as Arc<dyn Job>, JobConfig::default())],
+            )
+            .unwrap(),
+        );
+
+        let first = tokio::spawn({
+            let scheduler = Arc::clone(&scheduler);
+            async move { scheduler.trigger("test").await }
+        });
+        while !scheduler.statuses()[0].running {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(
+            scheduler.trigger("test").await.unwrap_err(),
+            TriggerError::AlreadyRunning
+        );
+
+        hold.notify_one();
+        assert!(first.await.unwrap().is_ok());
+        assert_eq!(job.runs.load(Ordering::SeqCst), 1);
+        assert!(!scheduler.statuses()[0].running);
+    }
+}
@@ -0,0 +1,37 @@
+// shared by the bot and the krunker-admin tool
+
+// discord handler, commands and shared state
+pub mod bot;
+
+// typed settings from config.toml and the environment
+pub mod config;
+
+// database submodule
+pub mod database;
+
+// verification submodule
+pub mod verification;
+
+// linked account identity tracking
+pub mod identity;
+
+// shutdown handling
+pub mod lifecycle;
+
+// scheduled maintenance jobs
+pub mod jobs;
+
+// locally stored match and profile history
+pub mod history;
+
+// match feed and other unprompted posts
+pub mod announcements;
+
+// in-house queues and team balancing
+pub mod matchmaking;
+
+// prometheus metrics and health checks
+pub mod metrics;
+
+// subscriber setup and secret redaction
+pub mod logging;
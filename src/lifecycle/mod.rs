@@ -0,0 +1,217 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use tokio::sync::{Notify, watch};
+use tokio::task::JoinHandle;
+
+/// How long shutdown waits for in-flight commands and background tasks
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(30);
+
+/// Tracks whether the bot is shutting down and how much work is still running.
+/// Cheap to clone, every clone shares the same state.
+#[derive(Clone)]
+pub struct Lifecycle {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    shutting_down: watch::Sender<bool>,
+    in_flight: AtomicUsize,
+    idle: Notify,
+}
+
+/// Held for as long as a command runs
+pub struct WorkGuard {
+    inner: Arc<Inner>,
+}
+
+impl Drop for WorkGuard {
+    fn drop(&mut self) {
+        if self.inner.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                shutting_down: watch::Sender::new(false),
+                in_flight: AtomicUsize::new(0),
+                idle: Notify::new(),
+            }),
+        }
+    }
+
+    /// Registers a new unit of work, or None once shutdown has started
+    pub fn begin(&self) -> Option<WorkGuard> {
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = WorkGuard {
+            inner: Arc::clone(&self.inner),
+        };
+
+        // checked after registering, so drain() can't miss work that slipped in
+        if self.is_shutting_down() {
+            return None;
+        }
+        Some(guard)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.inner.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        *self.inner.shutting_down.borrow()
+    }
+
+    pub fn begin_shutdown(&self) {
+        self.inner.shutting_down.send_replace(true);
+    }
+
+    /// Resolves once shutdown has started, for background loops to select on
+    pub async fn stopped(&self) {
+        let mut rx = self.inner.shutting_down.subscribe();
+        // the sender lives in `self`, so this can't fail
+        let _ = rx.wait_for(|stopping| *stopping).await;
+    }
+
+    async fn wait_idle(&self) {
+        loop {
+            let idle = self.inner.idle.notified();
+            if self.in_flight() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Waits for in-flight work and the given tasks, giving up after `deadline`.
+    /// Returns whether everything finished in time.
+    pub async fn drain(&self, tasks: Vec<JoinHandle<()>>, deadline: Duration) -> bool {
+        let pending = self.in_flight();
+        if pending > 0 {
+            tracing::info!(commands = pending, "Waiting for in-flight commands");
+        }
+
+        let aborts: Vec<_> = tasks.iter().map(|task| task.abort_handle()).collect();
+        let all_done = async {
+            self.wait_idle().await;
+            for task in tasks {
+                if let Err(e) = task.await {
+                    tracing::warn!("Background task ended badly: {}", e);
+                }
+            }
+        };
+
+        match tokio::time::timeout(deadline, all_done).await {
+            Ok(()) => {
+                tracing::info!("All work finished");
+                true
+            }
+            Err(_) => {
+                tracing::warn!(
+                    commands = self.in_flight(),
+                    "Shutdown deadline of {}s passed, abandoning remaining work",
+                    deadline.as_secs()
+                );
+                for abort in aborts {
+                    abort.abort();
+                }
+                false
+            }
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on unix
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C"),
+        _ = terminate => tracing::info!("Received SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_no_new_work_after_shutdown() {
+        let lifecycle = Lifecycle::new();
+        let guard = lifecycle.begin();
+        assert!(guard.is_some());
+        assert_eq!(lifecycle.in_flight(), 1);
+
+        lifecycle.begin_shutdown();
+        assert!(lifecycle.begin().is_none());
+        // the refused attempt doesn't count as in flight
+        assert_eq!(lifecycle.in_flight(), 1);
+
+        drop(guard);
+        assert_eq!(lifecycle.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_waits_for_commands_and_tasks() {
+        let lifecycle = Lifecycle::new();
+        let guard = lifecycle.begin().unwrap();
+
+        let command = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+        let background = {
+            let lifecycle = lifecycle.clone();
+            tokio::spawn(async move { lifecycle.stopped().await })
+        };
+
+        lifecycle.begin_shutdown();
+        assert!(lifecycle.drain(vec![background], SHUTDOWN_DEADLINE).await);
+        assert_eq!(lifecycle.in_flight(), 0);
+        command.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_gives_up_at_deadline() {
+        let lifecycle = Lifecycle::new();
+        let stuck = tokio::spawn(std::future::pending::<()>());
+
+        lifecycle.begin_shutdown();
+        let finished = lifecycle
+            .drain(vec![stuck], Duration::from_millis(50))
+            .await;
+        assert!(!finished);
+    }
+}
@@ -0,0 +1,114 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+use crate::config::{LogFormat, LoggingConfig};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Sets up the global subscriber. Every line is scrubbed of `secrets` before it's
+/// written, so tokens can't leak through error messages or request URLs.
+pub fn init(config: &LoggingConfig, secrets: Vec<String>) {
+    let level: tracing::Level = config.level.parse().unwrap_or(tracing::Level::INFO);
+    let writer = RedactingWriter::new(secrets);
+
+    let builder = tracing_subscriber::fmt()
+        .with_max_level(level)
+        // span closes carry how long each command, API and database call took
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(writer);
+
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .init(),
+    }
+}
+
+/// Writes to stdout with every secret replaced by `[REDACTED]`
+#[derive(Clone)]
+pub struct RedactingWriter {
+    secrets: Arc<Vec<String>>,
+}
+
+impl RedactingWriter {
+    pub fn new(secrets: Vec<String>) -> Self {
+        // empty strings would "match" everywhere
+        let secrets = secrets.into_iter().filter(|s| !s.is_empty()).collect();
+        Self {
+            secrets: Arc::new(secrets),
+        }
+    }
+
+    pub fn redact(&self, line: &str) -> String {
+        self.secrets.iter().fold(line.to_string(), |line, secret| {
+            line.replace(secret.as_str(), REDACTED)
+        })
+    }
+}
+
+pub struct RedactingHandle<W> {
+    secrets: RedactingWriter,
+    inner: W,
+}
+
+impl<W: Write> Write for RedactingHandle<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // the fmt layer hands over a whole formatted event per call, so secrets
+        // aren't split across writes
+        let line = String::from_utf8_lossy(buf);
+        self.inner
+            .write_all(self.secrets.redact(&line).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a> MakeWriter<'a> for RedactingWriter {
+    type Writer = RedactingHandle<io::Stdout>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingHandle {
+            secrets: self.clone(),
+            inner: io::stdout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redacts_every_secret() {
+        let writer = RedactingWriter::new(vec!["discord-token".into(), "api-key".into()]);
+        assert_eq!(
+            writer.redact("GET /player?key=api-key failed, token discord-token, api-key again"),
+            "GET /player?key=[REDACTED] failed, token [REDACTED], [REDACTED] again"
+        );
+    }
+
+    #[test]
+    fn test_empty_secrets_are_ignored() {
+        let writer = RedactingWriter::new(vec![String::new()]);
+        assert_eq!(writer.redact("nothing to hide"), "nothing to hide");
+    }
+
+    #[test]
+    fn test_handle_redacts_written_bytes() {
+        let mut handle = RedactingHandle {
+            secrets: RedactingWriter::new(vec!["hunter2".into()]),
+            inner: Vec::new(),
+        };
+        handle.write_all(b"password is hunter2\n").unwrap();
+        assert_eq!(handle.inner, b"password is [REDACTED]\n");
+    }
+}
@@ -5,54 +5,155 @@ use serenity::prelude::*;
 // std modules
 use std::sync::Arc;
 
-// bot submodule
-mod bot;
-use crate::bot::handler::Handler;
-
-// database submodule
-mod database;
-
-// verification submodule
-mod verification;
+// everything else lives in the library, shared with krunker-admin
+use krunker_bot::announcements::matches::MatchFeed;
+use krunker_bot::announcements::milestones::Milestones;
+use krunker_bot::bot::handler::Handler;
+use krunker_bot::bot::state::{ConfigKey, LifecycleKey, SchedulerKey, VerificationServiceKey};
+use krunker_bot::history::ratings::RecomputeRatings;
+use krunker_bot::history::snapshots::SnapshotProfiles;
+use krunker_bot::jobs::maintenance::{ExpireVerifications, ResolveIdentities, UsageRetention};
+use krunker_bot::jobs::{Job, Scheduler};
+use krunker_bot::lifecycle::Lifecycle;
+use krunker_bot::verification::service::VerificationService;
+use krunker_bot::{config, database, lifecycle, logging, metrics};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
 
-    // initialize logging
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
-
-    // initialize database
-    // for now this does absolute nothing lmfao
-    let pool = database::init_db().await?;
-    // println!("Database initialized!");
+    let config = match config::Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    };
 
     // env vars
-    tracing::info!("Grabbing tokens...");
     let krunker_key = std::env::var("KRUNKER_API")?;
     let discord_token = std::env::var("DISCORD_TOKEN")?;
 
+    // initialize logging, keeping both tokens out of the output
+    logging::init(
+        &config.logging,
+        vec![krunker_key.clone(), discord_token.clone()],
+    );
+
+    // initialize database
+    // for now this does absolute nothing lmfao
+    let pool = database::init_db(&config.database.url).await?;
+    // println!("Database initialized!");
+
     // debug flags for this later pls lol
     // println!("discord token: {}", discord_token);
 
     let krunker_api = Arc::new(KrunkerClient::new(krunker_key)?);
 
+    let lifecycle = Lifecycle::new();
+
     let intents = GatewayIntents::GUILD_MESSAGES
         | GatewayIntents::DIRECT_MESSAGES
         | GatewayIntents::MESSAGE_CONTENT;
 
+    let config = Arc::new(config);
+
     tracing::info!("Building client...");
     let mut client = Client::builder(&discord_token, intents)
-        .event_handler(Handler::new(krunker_api, pool))
+        .event_handler(Handler::new(
+            Arc::clone(&krunker_api),
+            pool.clone(),
+            lifecycle.clone(),
+        ))
+        .type_map_insert::<VerificationServiceKey>(
+            VerificationService::default().with_settings(config.verification_settings()),
+        )
+        .type_map_insert::<ConfigKey>(Arc::clone(&config))
+        .type_map_insert::<LifecycleKey>(lifecycle.clone())
         .await
         .expect("Failure to create client");
 
+    // maintenance: expire verifications, trim usage, keep linked accounts
+    // pointing at the right players, post the match feed and milestones,
+    // snapshot profiles for &progress and recompute ratings
+    let jobs: Vec<Arc<dyn Job>> = vec![
+        Arc::new(ExpireVerifications {
+            data: client.data.clone(),
+        }),
+        Arc::new(UsageRetention {
+            retention_days: config.analytics.retention_days,
+        }),
+        Arc::new(ResolveIdentities {
+            krunker_api: Arc::clone(&krunker_api),
+            config: config.identity.clone(),
+        }),
+        Arc::new(MatchFeed {
+            krunker_api: Arc::clone(&krunker_api),
+            http: client.http.clone(),
+            data: client.data.clone(),
+        }),
+        Arc::new(Milestones {
+            krunker_api: Arc::clone(&krunker_api),
+            http: client.http.clone(),
+            data: client.data.clone(),
+        }),
+        Arc::new(SnapshotProfiles {
+            krunker_api: Arc::clone(&krunker_api),
+        }),
+        Arc::new(RecomputeRatings),
+    ];
+    let scheduler = Arc::new(Scheduler::new(pool.clone(), jobs, &config.jobs)?);
+    client
+        .data
+        .write()
+        .await
+        .insert::<SchedulerKey>(Arc::clone(&scheduler));
+    let mut background = vec![tokio::spawn(scheduler.run(lifecycle.clone()))];
+
+    if let Some(listen) = config.metrics_listen() {
+        background.push(tokio::spawn(metrics::server::serve(
+            listen,
+            client.shard_manager.clone(),
+            pool.clone(),
+            lifecycle.clone(),
+        )));
+    }
+
+    // on a signal (or the client dying): stop taking commands, let running work
+    // finish, then take the shards down so client.start() returns
+    let shutdown = {
+        let lifecycle = lifecycle.clone();
+        let shard_manager = client.shard_manager.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = lifecycle::wait_for_signal() => {}
+                _ = lifecycle.stopped() => {}
+            }
+            tracing::info!("Shutting down, no longer accepting commands");
+            lifecycle.begin_shutdown();
+
+            lifecycle
+                .drain(background, lifecycle::SHUTDOWN_DEADLINE)
+                .await;
+
+            tracing::info!("Stopping shards...");
+            shard_manager.shutdown_all().await;
+        })
+    };
+
     tracing::info!("Starting Bot...");
     if let Err(why) = client.start().await {
-        eprintln!("Client error: {why:?}");
+        tracing::error!(error = %why, "Client error");
     }
 
+    lifecycle.begin_shutdown();
+    if let Err(e) = shutdown.await {
+        tracing::error!("Shutdown task failed: {e}");
+    }
+
+    tracing::info!("Closing database...");
+    pool.close().await;
+    tracing::info!("Shutdown complete");
+
     Ok(())
 }
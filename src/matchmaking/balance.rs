@@ -0,0 +1,141 @@
+use crate::database::models::PlayerRating;
+use crate::history::ratings::{PROVISIONAL_GAMES, START_RATING};
+
+/// The biggest team a lobby can have, every split of twice this many players is tried
+pub const MAX_TEAM_SIZE: usize = 5;
+/// Rating points per point of K/D above or below 1, for the guess from a profile
+const KDR_WEIGHT: f64 = 200.0;
+/// K/Ds past this all count the same, so one outlier doesn't decide the teams
+const MAX_KDR: f64 = 4.0;
+
+/// A player's strength on the rating scale. Players with enough rated ranked games
+/// are their rating; before that it's blended with a guess from their profile K/D,
+/// or from the starting rating if the profile couldn't be fetched.
+pub fn skill(kdr: Option<f64>, rating: Option<&PlayerRating>) -> f64 {
+    let guess = kdr.map_or(START_RATING, |kdr| {
+        START_RATING + KDR_WEIGHT * (kdr.clamp(0.0, MAX_KDR) - 1.0)
+    });
+    match rating {
+        Some(rating) if rating.games >= PROVISIONAL_GAMES => rating.rating,
+        Some(rating) => {
+            let weight = rating.games as f64 / PROVISIONAL_GAMES as f64;
+            rating.rating * weight + guess * (1.0 - weight)
+        }
+        None => guess,
+    }
+}
+
+/// One way to split the players into two teams, by index into the skills it
+/// was worked out from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    pub teams: [Vec<usize>; 2],
+    /// Team 1's total skill minus team 2's
+    pub difference: f64,
+}
+
+/// Every way to split `skills` into two teams of the same size, most balanced
+/// first. Mirror images are left out, the first player is always on team 1. Empty
+/// for an odd number of players or more than twice `MAX_TEAM_SIZE`.
+pub fn splits(skills: &[f64]) -> Vec<Split> {
+    let players = skills.len();
+    if players == 0 || players % 2 == 1 || players > 2 * MAX_TEAM_SIZE {
+        return Vec::new();
+    }
+
+    let total = |team: &[usize]| team.iter().map(|&i| skills[i]).sum::<f64>();
+    let mut splits: Vec<(u32, Split)> = (0u32..1 << players)
+        .filter(|mask| mask & 1 == 1 && mask.count_ones() as usize == players / 2)
+        .map(|mask| {
+            let (first, second): (Vec<usize>, Vec<usize>) =
+                (0..players).partition(|&i| mask & (1 << i) != 0);
+            let difference = total(&first) - total(&second);
+            (
+                mask,
+                Split {
+                    teams: [first, second],
+                    difference,
+                },
+            )
+        })
+        .collect();
+
+    // ties go the same way every time
+    splits.sort_by(|(a_mask, a), (b_mask, b)| {
+        a.difference
+            .abs()
+            .total_cmp(&b.difference.abs())
+            .then(a_mask.cmp(b_mask))
+    });
+    splits.into_iter().map(|(_, split)| split).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rating(rating: f64, games: i64) -> PlayerRating {
+        PlayerRating {
+            player_name: "Player".to_string(),
+            krunker_id: None,
+            rating,
+            games,
+            wins: 0,
+            peak: rating,
+            computed_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_skill() {
+        assert_eq!(skill(None, None), START_RATING);
+        assert_eq!(skill(Some(2.0), None), START_RATING + 200.0);
+        assert_eq!(skill(Some(0.5), None), START_RATING - 100.0);
+        assert_eq!(skill(Some(25.0), None), skill(Some(MAX_KDR), None));
+
+        // enough games, the rating alone
+        assert_eq!(skill(Some(3.0), Some(&rating(1400.0, 30))), 1400.0);
+        // halfway there, halfway between the rating and the guess
+        assert_eq!(
+            skill(Some(2.0), Some(&rating(1600.0, PROVISIONAL_GAMES / 2))),
+            1650.0
+        );
+    }
+
+    #[test]
+    fn test_splits() {
+        let skills = [
+            1800.0, 1700.0, 1600.0, 1550.0, 1450.0, 1400.0, 1300.0, 1200.0,
+        ];
+        let splits = splits(&skills);
+        // 8 choose 4, halved for the mirror images
+        assert_eq!(splits.len(), 35);
+        assert_eq!(splits[0].difference, 0.0);
+        assert!(splits.iter().all(|split| split.teams[0][0] == 0));
+        assert!(splits.iter().all(|split| split.teams[0].len() == 4));
+        assert!(
+            splits
+                .windows(2)
+                .all(|pair| pair[0].difference.abs() <= pair[1].difference.abs())
+        );
+
+        // the strongest four together is the worst split
+        let worst = splits.last().unwrap();
+        assert_eq!(worst.teams, [vec![0, 1, 2, 3], vec![4, 5, 6, 7]]);
+        assert_eq!(worst.difference, 1300.0);
+    }
+
+    #[test]
+    fn test_splits_need_even_teams() {
+        assert_eq!(
+            splits(&[1500.0, 1400.0]),
+            vec![Split {
+                teams: [vec![0], vec![1]],
+                difference: 100.0,
+            }]
+        );
+        assert!(splits(&[]).is_empty());
+        assert!(splits(&[1500.0; 3]).is_empty());
+        assert!(splits(&[1500.0; 2 * MAX_TEAM_SIZE + 2]).is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+// in-house queues, one per channel, and the team balancing for the lobbies they fill
+
+pub mod balance;
+
+use std::collections::HashMap;
+
+/// Someone waiting in a channel's queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuedPlayer {
+    pub discord_id: u64,
+    /// Their primary linked account when they joined
+    pub username: String,
+    pub joined_at: i64,
+}
+
+/// Every channel's queue, in the order players joined. Only kept in memory, so
+/// the queues are empty after a restart.
+#[derive(Debug, Default)]
+pub struct Queues {
+    channels: HashMap<u64, Vec<QueuedPlayer>>,
+}
+
+impl Queues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn players(&self, channel_id: u64) -> &[QueuedPlayer] {
+        self.channels.get(&channel_id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `player` to the back of the queue. Returns how many are queued after,
+    /// None if they were already in it.
+    pub fn join(&mut self, channel_id: u64, player: QueuedPlayer) -> Option<usize> {
+        let queue = self.channels.entry(channel_id).or_default();
+        if queue.iter().any(|p| p.discord_id == player.discord_id) {
+            return None;
+        }
+        queue.push(player);
+        Some(queue.len())
+    }
+
+    /// Returns whether they were queued
+    pub fn leave(&mut self, channel_id: u64, discord_id: u64) -> bool {
+        let Some(queue) = self.channels.get_mut(&channel_id) else {
+            return false;
+        };
+        let before = queue.len();
+        queue.retain(|p| p.discord_id != discord_id);
+        let left = queue.len() < before;
+        if queue.is_empty() {
+            self.channels.remove(&channel_id);
+        }
+        left
+    }
+
+    /// Takes the first `count` players out of the channel's queue, and out of any
+    /// other queue they're in. None if fewer than `count` are waiting.
+    pub fn take(&mut self, channel_id: u64, count: usize) -> Option<Vec<QueuedPlayer>> {
+        let queue = self.channels.get_mut(&channel_id)?;
+        if count == 0 || queue.len() < count {
+            return None;
+        }
+        let taken: Vec<QueuedPlayer> = queue.drain(..count).collect();
+        for queue in self.channels.values_mut() {
+            queue.retain(|p| !taken.iter().any(|t| t.discord_id == p.discord_id));
+        }
+        self.channels.retain(|_, queue| !queue.is_empty());
+        Some(taken)
+    }
+
+    /// Puts taken players back at the front of the channel's queue in their old
+    /// order, for when their lobby couldn't be made. Anyone who queued again in
+    /// the meantime gets their old place back.
+    pub fn requeue(&mut self, channel_id: u64, players: Vec<QueuedPlayer>) {
+        let queue = self.channels.entry(channel_id).or_default();
+        queue.retain(|p| !players.iter().any(|r| r.discord_id == p.discord_id));
+        queue.splice(0..0, players);
+        self.channels.retain(|_, queue| !queue.is_empty());
+    }
+
+    /// Empties the channel's queue, returning how many were in it
+    pub fn clear(&mut self, channel_id: u64) -> usize {
+        self.channels
+            .remove(&channel_id)
+            .map_or(0, |queue| queue.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(discord_id: u64) -> QueuedPlayer {
+        QueuedPlayer {
+            discord_id,
+            username: format!("Player{}", discord_id),
+            joined_at: 1000,
+        }
+    }
+
+    #[test]
+    fn test_join_and_leave() {
+        let mut queues = Queues::new();
+        assert_eq!(queues.join(1, player(10)), Some(1));
+        assert_eq!(queues.join(1, player(11)), Some(2));
+        assert_eq!(queues.join(1, player(10)), None);
+        assert_eq!(queues.join(2, player(10)), Some(1));
+
+        assert!(queues.leave(1, 10));
+        assert!(!queues.leave(1, 10));
+        assert!(!queues.leave(3, 10));
+        assert_eq!(queues.players(1), &[player(11)]);
+        assert!(queues.players(3).is_empty());
+    }
+
+    #[test]
+    fn test_take_in_join_order() {
+        let mut queues = Queues::new();
+        for discord_id in 10..13 {
+            queues.join(1, player(discord_id));
+        }
+        queues.join(2, player(10));
+        queues.join(2, player(20));
+
+        assert_eq!(queues.take(1, 4), None);
+        let taken = queues.take(1, 2).unwrap();
+        assert_eq!(taken, vec![player(10), player(11)]);
+        assert_eq!(queues.players(1), &[player(12)]);
+        // taken players leave every queue
+        assert_eq!(queues.players(2), &[player(20)]);
+
+        assert_eq!(queues.clear(1), 1);
+        assert_eq!(queues.clear(1), 0);
+    }
+
+    #[test]
+    fn test_requeue_restores_order() {
+        let mut queues = Queues::new();
+        for discord_id in 10..13 {
+            queues.join(1, player(discord_id));
+        }
+        let taken = queues.take(1, 2).unwrap();
+        queues.join(1, player(13));
+        // rejoined while the lobby was being made
+        queues.join(1, player(11));
+
+        queues.requeue(1, taken);
+        assert_eq!(
+            queues.players(1),
+            &[player(10), player(11), player(12), player(13)]
+        );
+
+        queues.requeue(2, vec![]);
+        assert!(queues.players(2).is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use std::fmt;
+use std::future::Future;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+pub mod server;
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// The process-wide metrics, recorded whether or not the HTTP server is enabled
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    commands: IntCounterVec,
+    command_duration: HistogramVec,
+    api_calls: IntCounterVec,
+    api_duration: HistogramVec,
+    verifications: IntCounterVec,
+    jobs: IntCounterVec,
+    job_duration: HistogramVec,
+}
+
+/// How a verification attempt ended, as counted in `krunker_bot_verifications_total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerificationOutcome {
+    Started,
+    NotFound,
+    Verified,
+    Failed,
+    Rejected,
+    Expired,
+}
+
+impl VerificationOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationOutcome::Started => "started",
+            VerificationOutcome::NotFound => "not_found",
+            VerificationOutcome::Verified => "verified",
+            VerificationOutcome::Failed => "failed",
+            VerificationOutcome::Rejected => "rejected",
+            VerificationOutcome::Expired => "expired",
+        }
+    }
+}
+
+fn outcome(ok: bool) -> &'static str {
+    if ok { "ok" } else { "error" }
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("krunker_bot".to_string()), None)
+            .expect("metric prefix is valid");
+
+        let commands = IntCounterVec::new(
+            Opts::new("commands_total", "Commands handled, by command and outcome"),
+            &["command", "outcome"],
+        )
+        .expect("metric is valid");
+        let command_duration = HistogramVec::new(
+            HistogramOpts::new("command_duration_seconds", "Time spent running commands")
+                .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            &["command"],
+        )
+        .expect("metric is valid");
+        let api_calls = IntCounterVec::new(
+            Opts::new(
+                "krunker_api_calls_total",
+                "Krunker API calls, by endpoint and outcome",
+            ),
+            &["endpoint", "outcome"],
+        )
+        .expect("metric is valid");
+        let api_duration = HistogramVec::new(
+            HistogramOpts::new("krunker_api_duration_seconds", "Krunker API call latency")
+                .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["endpoint"],
+        )
+        .expect("metric is valid");
+        let verifications = IntCounterVec::new(
+            Opts::new("verifications_total", "Verification events, by outcome"),
+            &["outcome"],
+        )
+        .expect("metric is valid");
+        let jobs = IntCounterVec::new(
+            Opts::new("jobs_total", "Maintenance job runs, by job and outcome"),
+            &["job", "outcome"],
+        )
+        .expect("metric is valid");
+        let job_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "job_duration_seconds",
+                "Time spent running maintenance jobs",
+            )
+            .buckets(vec![0.01, 0.1, 0.5, 1.0, 5.0, 15.0, 60.0, 300.0]),
+            &["job"],
+        )
+        .expect("metric is valid");
+
+        for collector in [&commands, &api_calls, &verifications, &jobs] {
+            registry
+                .register(Box::new(collector.clone()))
+                .expect("metric names are unique");
+        }
+        for collector in [&command_duration, &api_duration, &job_duration] {
+            registry
+                .register(Box::new(collector.clone()))
+                .expect("metric names are unique");
+        }
+
+        Self {
+            registry,
+            commands,
+            command_duration,
+            api_calls,
+            api_duration,
+            verifications,
+            jobs,
+            job_duration,
+        }
+    }
+
+    /// `command` is the command's `CommandMetadata::name`, never an alias
+    pub fn record_command(&self, command: &str, ok: bool, elapsed: Duration) {
+        self.commands
+            .with_label_values(&[command, outcome(ok)])
+            .inc();
+        self.command_duration
+            .with_label_values(&[command])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_api_call(&self, endpoint: &str, ok: bool, elapsed: Duration) {
+        self.api_calls
+            .with_label_values(&[endpoint, outcome(ok)])
+            .inc();
+        self.api_duration
+            .with_label_values(&[endpoint])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub fn record_verification(&self, outcome: VerificationOutcome) {
+        self.record_verifications(outcome, 1);
+    }
+
+    pub fn record_verifications(&self, outcome: VerificationOutcome, count: u64) {
+        self.verifications
+            .with_label_values(&[outcome.as_str()])
+            .inc_by(count);
+    }
+
+    pub fn record_job(&self, job: &str, ok: bool, elapsed: Duration) {
+        self.jobs.with_label_values(&[job, outcome(ok)]).inc();
+        self.job_duration
+            .with_label_values(&[job])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// A run that didn't start because the previous one was still going
+    pub fn record_job_skipped(&self, job: &str) {
+        self.jobs.with_label_values(&[job, "skipped"]).inc();
+    }
+
+    /// Everything in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Runs a Krunker API call in its own span, counting it under `endpoint`
+pub async fn track_api<T, E: fmt::Display>(
+    endpoint: &str,
+    call: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let span = tracing::info_span!("krunker_api", endpoint, outcome = tracing::field::Empty);
+
+    async {
+        let started = Instant::now();
+        let result = call.await;
+        let elapsed = started.elapsed();
+        metrics().record_api_call(endpoint, result.is_ok(), elapsed);
+
+        match &result {
+            Ok(_) => {
+                tracing::Span::current().record("outcome", "ok");
+            }
+            Err(e) => {
+                tracing::Span::current().record("outcome", "error");
+                tracing::debug!(
+                    elapsed_ms = elapsed.as_millis() as u64,
+                    error = %e,
+                    "Krunker API call failed"
+                );
+            }
+        }
+        result
+    }
+    .instrument(span)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_includes_recorded_values() {
+        let metrics = Metrics::new();
+        metrics.record_command("stats", true, Duration::from_millis(120));
+        metrics.record_command("stats", false, Duration::from_millis(30));
+        metrics.record_api_call("get_player", false, Duration::from_millis(80));
+        metrics.record_verifications(VerificationOutcome::Expired, 3);
+        metrics.record_job("usage_retention", true, Duration::from_millis(40));
+        metrics.record_job_skipped("usage_retention");
+
+        let text = metrics.render();
+        assert!(text.contains(r#"krunker_bot_commands_total{command="stats",outcome="ok"} 1"#));
+        assert!(text.contains(r#"krunker_bot_commands_total{command="stats",outcome="error"} 1"#));
+        assert!(text.contains(r#"krunker_bot_command_duration_seconds_count{command="stats"} 2"#));
+        assert!(text.contains(
+            r#"krunker_bot_krunker_api_calls_total{endpoint="get_player",outcome="error"} 1"#
+        ));
+        assert!(text.contains(r#"krunker_bot_verifications_total{outcome="expired"} 3"#));
+        assert!(text.contains(r#"krunker_bot_jobs_total{job="usage_retention",outcome="ok"} 1"#));
+        assert!(
+            text.contains(r#"krunker_bot_jobs_total{job="usage_retention",outcome="skipped"} 1"#)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_track_api_passes_result_through() {
+        let ok: Result<i32, String> = track_api("test_ok", async { Ok(5) }).await;
+        assert_eq!(ok, Ok(5));
+
+        let err: Result<i32, String> = track_api("test_err", async { Err("boom".into()) }).await;
+        assert_eq!(err, Err("boom".to_string()));
+
+        let text = metrics().render();
+        assert!(text.contains(r#"endpoint="test_err",outcome="error"} 1"#));
+    }
+}
@@ -0,0 +1,111 @@
+use axum::Router;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use serenity::gateway::{ConnectionStage, ShardManager};
+use sqlx::AnyPool;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::metrics;
+use crate::lifecycle::Lifecycle;
+
+/// How long /healthz waits on the database before calling it down
+const DATABASE_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+struct HealthState {
+    shard_manager: Arc<ShardManager>,
+    pool: AnyPool,
+}
+
+/// Serves `/healthz` and `/metrics` on `listen` until shutdown
+pub async fn serve(
+    listen: SocketAddr,
+    shard_manager: Arc<ShardManager>,
+    pool: AnyPool,
+    lifecycle: Lifecycle,
+) {
+    let listener = match tokio::net::TcpListener::bind(listen).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(%listen, "Failed to start metrics server: {}", e);
+            return;
+        }
+    };
+    tracing::info!(%listen, "Metrics server listening");
+
+    let app = Router::new()
+        .route("/healthz", get(healthz))
+        .route("/metrics", get(metrics_text))
+        .with_state(HealthState {
+            shard_manager,
+            pool,
+        });
+
+    let result = axum::serve(listener, app)
+        .with_graceful_shutdown(async move { lifecycle.stopped().await })
+        .await;
+    if let Err(e) = result {
+        tracing::error!("Metrics server failed: {}", e);
+    }
+
+    tracing::info!("Metrics server stopped");
+}
+
+async fn healthz(State(state): State<HealthState>) -> impl IntoResponse {
+    let gateway = gateway_connected(&state.shard_manager).await;
+    let database = database_reachable(&state.pool).await;
+
+    let status = if gateway && database {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let up = |ok: bool| if ok { "up" } else { "down" };
+
+    (
+        status,
+        format!("gateway: {}\ndatabase: {}\n", up(gateway), up(database)),
+    )
+}
+
+async fn metrics_text() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics().render(),
+    )
+}
+
+/// Every shard has finished connecting to the gateway
+async fn gateway_connected(shard_manager: &ShardManager) -> bool {
+    let runners = shard_manager.runners.lock().await;
+    !runners.is_empty()
+        && runners
+            .values()
+            .all(|runner| matches!(runner.stage, ConnectionStage::Connected))
+}
+
+async fn database_reachable(pool: &AnyPool) -> bool {
+    let ping = sqlx::query("SELECT 1").execute(pool);
+    matches!(
+        tokio::time::timeout(DATABASE_CHECK_TIMEOUT, ping).await,
+        Ok(Ok(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_database_reachable() {
+        let pool = crate::database::connect("sqlite::memory:").await.unwrap();
+        assert!(database_reachable(&pool).await);
+
+        pool.close().await;
+        assert!(!database_reachable(&pool).await);
+    }
+}
@@ -1,74 +1,212 @@
+use super::posts::{PostSource, scan_posts_for_code};
+use super::service::{VerificationService, VerificationSettings};
+use crate::database::models::{LinkEventKind, VerificationStatus};
 use crate::database::queries;
-use chrono::Utc;
-use krunker_rs::Client as KrunkerClient;
-use rand::distr::{Alphanumeric, SampleString};
-use sqlx::SqlitePool;
+use crate::metrics::{VerificationOutcome, metrics};
+use sqlx::AnyPool;
 
-const VERIFICATION_EXPIRY_SECONDS: i64 = 120; // 2mins?
+/// Default session length, overridable in config
+pub const VERIFICATION_EXPIRY_SECONDS: i64 = 120; // 2mins?
+pub const MAX_LINKED_ACCOUNTS: usize = 5;
+/// Default checks per session, overridable in config
+pub const MAX_VERIFICATION_ATTEMPTS: i32 = 5;
 
-fn generate_code() -> String {
-    let random_string = Alphanumeric.sample_string(&mut rand::rng(), 8);
+/// Default minimum time between two `&link` calls from the same user
+pub const LINK_COOLDOWN_SECONDS: i64 = 60;
+/// Default failed sessions allowed before the user is locked out
+pub const MAX_FAILED_SESSIONS: i32 = 3;
+pub const LOCKOUT_SECONDS: i64 = 60 * 60;
+
+fn generate_code(service: &VerificationService) -> String {
+    let random_string = service.random_code();
 
     format!("VERIFY-{}", random_string.to_uppercase())
 }
 
+/// Expires overdue sessions and counts them. A session that was checked and then
+/// left to expire counts as failed, the same as one replaced by a new &link.
+pub async fn expire_stale_verifications(
+    pool: &AnyPool,
+    settings: VerificationSettings,
+    now: i64,
+) -> Result<u64, sqlx::Error> {
+    let (expired, attempted) = queries::cleanup_expired_verifications(pool, now).await?;
+    for discord_id in &attempted {
+        queries::record_failed_session(
+            pool,
+            discord_id,
+            now,
+            settings.max_failed_sessions,
+            settings.lockout_seconds,
+        )
+        .await?;
+    }
+    metrics().record_verifications(VerificationOutcome::Expired, expired);
+    Ok(expired)
+}
+
 pub async fn start_verification(
-    pool: &SqlitePool,
+    service: &VerificationService,
+    pool: &AnyPool,
     discord_id: &str,
     krunker_username: &str,
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    if queries::user_exists(pool, discord_id).await? {
-        return Err("You have already linked to a Krunker account. Use /unlink first.".into());
+    let accounts = queries::get_users_by_discord_id(pool, discord_id).await?;
+    if accounts
+        .iter()
+        .any(|a| a.username.eq_ignore_ascii_case(krunker_username))
+    {
+        return Err("You have already linked this Krunker account.".into());
+    }
+    if accounts.len() >= MAX_LINKED_ACCOUNTS {
+        return Err(format!(
+            "You can link at most {} Krunker accounts. Use &unlink <username> first.",
+            MAX_LINKED_ACCOUNTS
+        )
+        .into());
     }
 
-    let code = generate_code();
+    let now = service.now();
+    let settings = service.settings();
 
-    let now = Utc::now().timestamp();
-    let expires_at = now + VERIFICATION_EXPIRY_SECONDS;
+    // expiring first means a checked session that ran out is counted as failed
+    // before the lockout is looked at
+    expire_stale_verifications(pool, settings, now).await?;
 
-    sqlx::query!("DELETE FROM verifications WHERE discord_id = ?", discord_id)
-        .execute(pool)
-        .await?;
+    if let Some(limits) = queries::get_verification_limits(pool, discord_id).await? {
+        if let Some(locked_until) = limits.locked_until.filter(|until| *until > now) {
+            return Err(format!(
+                "Too many failed verifications. You can try again <t:{}:R>.",
+                locked_until
+            )
+            .into());
+        }
+
+        let ready_at = limits.last_started_at + settings.cooldown_seconds;
+        if ready_at > now {
+            return Err(format!(
+                "Please wait {} seconds before starting another verification.",
+                ready_at - now
+            )
+            .into());
+        }
+    }
+
+    if let Some(owner) = queries::get_user_by_username(pool, krunker_username).await? {
+        if owner.discord_id != discord_id {
+            return Err(
+                "This Krunker username is already linked to another Discord account.".into(),
+            );
+        }
+    }
+
+    // first come, first served: only one user may be verifying a name at a time
+    let claims = queries::get_pending_claims(pool, krunker_username, discord_id, now).await?;
+    if let Some(free_at) = claims.iter().map(|c| c.expires_at).max() {
+        return Err(format!(
+            "Someone else is currently verifying this Krunker account. Try again <t:{}:R>.",
+            free_at
+        )
+        .into());
+    }
+
+    // restarting after a failed check counts against the user, otherwise the
+    // attempt limit could be dodged by running &link again
+    let mut locked_until = None;
+    if let Some(previous) = queries::get_verification_by_discord_id(pool, discord_id, now).await? {
+        if previous.attempts > 0 {
+            locked_until = queries::record_failed_session(
+                pool,
+                discord_id,
+                now,
+                settings.max_failed_sessions,
+                settings.lockout_seconds,
+            )
+            .await?;
+        }
+    }
 
-    queries::create_verification(pool, discord_id, krunker_username, &code, expires_at).await?;
+    let mut tx = pool.begin().await?;
+
+    // the old session goes first, there can only be one pending per user
+    queries::finish_pending_verification(
+        &mut tx,
+        discord_id,
+        VerificationStatus::Cancelled,
+        Some(if locked_until.is_some() {
+            "locked out"
+        } else {
+            "replaced by a new session"
+        }),
+        now,
+    )
+    .await?;
+
+    if let Some(locked_until) = locked_until {
+        tx.commit().await?;
+        return Err(format!(
+            "Too many failed verifications. You can try again <t:{}:R>.",
+            locked_until
+        )
+        .into());
+    }
+
+    let code = generate_code(service);
+    let expires_at = now + settings.expiry_seconds;
+
+    queries::insert_verification(
+        &mut tx,
+        discord_id,
+        krunker_username,
+        &code,
+        now,
+        expires_at,
+    )
+    .await?;
+
+    queries::record_link_event(
+        &mut *tx,
+        discord_id,
+        krunker_username,
+        LinkEventKind::LinkStarted,
+        discord_id,
+        None,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    queries::record_verification_start(pool, discord_id, now).await?;
+    metrics().record_verification(VerificationOutcome::Started);
 
     Ok(code)
 }
 
 pub async fn check_verification(
-    pool: &SqlitePool,
-    krunker_api: &KrunkerClient,
+    service: &VerificationService,
+    pool: &AnyPool,
+    posts: &dyn PostSource,
     discord_id: &str,
 ) -> Result<VerificationResult, Box<dyn std::error::Error + Send + Sync>> {
-    let expr = Utc::now().timestamp();
-    let verification = sqlx::query_as::<_, crate::database::models::Verification>(
-        "SELECT id, discord_id, krunker_username, code, created_at, expires_at, attempts
-         FROM verifications 
-         WHERE discord_id = ? AND expires_at > ?",
-    )
-    .bind(discord_id)
-    .bind(expr)
-    .fetch_optional(pool)
-    .await?;
+    let now = service.now();
+    let verification = queries::get_verification_by_discord_id(pool, discord_id, now).await?;
 
     let verification = match verification {
         Some(v) => v,
-        None => return Ok(VerificationResult::NoVerification),
+        None => {
+            expire_stale_verifications(pool, service.settings(), now).await?;
+            return Ok(VerificationResult::NoVerification);
+        }
     };
 
-    // Fetch Krunker social posts and check for code
-    let response = krunker_api
-        .get_player_posts(&verification.krunker_username, Some(1))
-        .await?;
-
-    // Check if any post contains the verification code
-    let found = if let Some(posts) = response.posts_posts {
-        posts
-            .iter()
-            .any(|post| post.post_text.contains(&verification.code))
-    } else {
-        false
-    };
+    // Only posts written after the code was issued count
+    let found = scan_posts_for_code(
+        posts,
+        &verification.krunker_username,
+        &verification.code,
+        verification.created_at,
+    )
+    .await?;
 
     if found {
         return Ok(VerificationResult::Success {
@@ -76,54 +214,228 @@ pub async fn check_verification(
         });
     }
 
-    sqlx::query!(
-        "UPDATE verifications SET attempts = attempts + 1 WHERE discord_id = ?",
-        discord_id
+    let settings = service.settings();
+    let max_attempts = settings.max_attempts;
+    let mut tx = pool.begin().await?;
+
+    let new_attempts =
+        match queries::increment_verification_attempts(&mut tx, verification.id).await? {
+            Some(attempts) => attempts,
+            // finished by a concurrent request while we were checking posts
+            None => return Ok(VerificationResult::NoVerification),
+        };
+
+    let details = format!("attempt {}/{}", new_attempts, max_attempts);
+    queries::record_link_event(
+        &mut *tx,
+        discord_id,
+        &verification.krunker_username,
+        LinkEventKind::VerifyAttempt,
+        discord_id,
+        Some(&details),
     )
-    .execute(pool)
     .await?;
 
-    let new_attempts = verification.attempts + 1;
+    if new_attempts < max_attempts {
+        tx.commit().await?;
+        metrics().record_verification(VerificationOutcome::NotFound);
 
-    if new_attempts >= 5 {
-        sqlx::query!("DELETE FROM verifications WHERE discord_id = ?", discord_id)
-            .execute(pool)
-            .await?;
+        return Ok(VerificationResult::NotFound {
+            code: verification.code,
+            krunker_username: verification.krunker_username,
+            attempts: new_attempts,
+        });
+    }
+
+    queries::finish_pending_verification(
+        &mut tx,
+        discord_id,
+        VerificationStatus::Failed,
+        Some("too many attempts"),
+        now,
+    )
+    .await?;
+
+    queries::record_link_event(
+        &mut *tx,
+        discord_id,
+        &verification.krunker_username,
+        LinkEventKind::VerifyFailed,
+        discord_id,
+        Some("too many attempts"),
+    )
+    .await?;
+
+    tx.commit().await?;
+    metrics().record_verification(VerificationOutcome::Failed);
+
+    let locked = queries::record_failed_session(
+        pool,
+        discord_id,
+        now,
+        settings.max_failed_sessions,
+        settings.lockout_seconds,
+    )
+    .await?;
 
-        return Err("Too many verification attempts (5). Please start over with /link.".into());
+    Err(match locked {
+        Some(locked_until) => format!(
+            "Too many verification attempts ({}). You can try again <t:{}:R>.",
+            max_attempts, locked_until
+        ),
+        None => format!(
+            "Too many verification attempts ({}). Please start over with &link.",
+            max_attempts
+        ),
     }
+    .into())
+}
 
-    Ok(VerificationResult::NotFound {
-        code: verification.code,
-        krunker_username: verification.krunker_username,
-        attempts: new_attempts,
-    })
+/// Why a verified session couldn't be turned into a link
+enum CompletionError {
+    /// Nothing pending for this user, e.g. a concurrent &verify already finished it
+    NotPending,
+    /// The session can never succeed, so it gets marked failed
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for CompletionError {
+    fn from(e: sqlx::Error) -> Self {
+        CompletionError::Database(e)
+    }
 }
 
 /// Complete the verification and link the account
 pub async fn complete_verification(
-    pool: &SqlitePool,
+    service: &VerificationService,
+    pool: &AnyPool,
     discord_id: &str,
     krunker_username: &str,
+    krunker_id: Option<&str>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let existing = queries::get_user_by_username(pool, krunker_username).await?;
-    if let Some(res) = existing {
-        if res.discord_id != discord_id {
-            return Err(
-                "This Krunker username is already linked to another Discord account.".into(),
-            );
+    let now = service.now();
+
+    match link_verified_account(pool, discord_id, krunker_username, krunker_id, now).await {
+        Ok(()) => {
+            metrics().record_verification(VerificationOutcome::Verified);
+            Ok(())
+        }
+        Err(CompletionError::NotPending) => Err(
+            "You don't have an active verification session. Use `&link <username>` first.".into(),
+        ),
+        Err(CompletionError::Rejected(reason)) => {
+            let mut tx = pool.begin().await?;
+            queries::finish_pending_verification(
+                &mut tx,
+                discord_id,
+                VerificationStatus::Failed,
+                Some(&reason),
+                now,
+            )
+            .await?;
+            queries::record_link_event(
+                &mut *tx,
+                discord_id,
+                krunker_username,
+                LinkEventKind::VerifyFailed,
+                discord_id,
+                Some(&reason),
+            )
+            .await?;
+            tx.commit().await?;
+            metrics().record_verification(VerificationOutcome::Rejected);
+
+            Err(reason.into())
+        }
+        Err(CompletionError::Database(e)) => Err(e.into()),
+    }
+}
+
+/// Marks the session verified and creates the link in one transaction. Nothing is
+/// written unless both succeed, and the unique index on usernames settles races with
+/// other completions or a moderator's &forcelink.
+async fn link_verified_account(
+    pool: &AnyPool,
+    discord_id: &str,
+    krunker_username: &str,
+    krunker_id: Option<&str>,
+    now: i64,
+) -> Result<(), CompletionError> {
+    let mut tx = pool.begin().await?;
+
+    // claiming the session is the first write, so concurrent completions queue up here
+    let verification = queries::finish_pending_verification(
+        &mut tx,
+        discord_id,
+        VerificationStatus::Verified,
+        None,
+        now,
+    )
+    .await?
+    .ok_or(CompletionError::NotPending)?;
+
+    if verification.expires_at <= now
+        || !verification
+            .krunker_username
+            .eq_ignore_ascii_case(krunker_username)
+    {
+        return Err(CompletionError::NotPending);
+    }
+
+    if let Some(res) = queries::get_user_by_username(&mut *tx, krunker_username).await? {
+        return Err(CompletionError::Rejected(if res.discord_id != discord_id {
+            "This Krunker username is already linked to another Discord account.".to_string()
+        } else {
+            "This Krunker account is already linked to your Discord account.".to_string()
+        }));
+    }
+
+    // the same account may be stored under an older name
+    if let Some(krunker_id) = krunker_id {
+        if let Some(res) = queries::get_user_by_krunker_id(&mut *tx, krunker_id).await? {
+            return Err(CompletionError::Rejected(if res.discord_id != discord_id {
+                "This Krunker account is already linked to another Discord account.".to_string()
+            } else {
+                format!(
+                    "This Krunker account is already linked to you as **{}**. Use &renamed {} to update it.",
+                    res.username, krunker_username
+                )
+            }));
         }
     }
 
     let country = None;
 
     // Create user in database
-    queries::create_user(pool, krunker_username, discord_id, country).await?;
+    let user_id = match queries::create_user(&mut *tx, krunker_username, discord_id, country).await
+    {
+        Ok(id) => id,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Err(CompletionError::Rejected(
+                "This Krunker username is already linked to another Discord account.".to_string(),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
 
-    sqlx::query!("DELETE FROM verifications WHERE discord_id = ?", discord_id)
-        .execute(pool)
-        .await?;
+    if let Some(krunker_id) = krunker_id {
+        queries::set_user_identity(&mut *tx, user_id, krunker_id, now).await?;
+    }
+
+    queries::reset_failed_sessions(&mut *tx, discord_id).await?;
+
+    queries::record_link_event(
+        &mut *tx,
+        discord_id,
+        krunker_username,
+        LinkEventKind::Verified,
+        discord_id,
+        None,
+    )
+    .await?;
 
+    tx.commit().await?;
     Ok(())
 }
 
@@ -144,21 +456,38 @@ pub enum VerificationResult {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sqlx::SqlitePool;
+    use crate::verification::posts::fixtures::FixturePosts;
+    use crate::verification::service::fixtures::{self, ManualClock};
+    use crate::verification::service::{Clock, RandomCodes};
+    use krunker_rs::Client as KrunkerClient;
+    use sqlx::AnyPool;
+    use std::sync::Arc;
 
-    async fn setup_test_db() -> SqlitePool {
-        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
-        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
-        pool
+    const START: i64 = 1_700_000_000;
+
+    /// Random codes on a clock that only moves when the test says so
+    fn clocked_service() -> (VerificationService, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(START));
+        let service = VerificationService::new(clock.clone(), Arc::new(RandomCodes));
+        (service, clock)
+    }
+
+    fn test_service() -> VerificationService {
+        clocked_service().0
+    }
+
+    async fn setup_test_db() -> AnyPool {
+        crate::database::test_pool().await
     }
 
     #[tokio::test]
     async fn test_start_verification_success() {
         let pool = setup_test_db().await;
+        let service = test_service();
         let discord_id = "12345";
         let krunker_username = "Player1";
 
-        let result = start_verification(&pool, discord_id, krunker_username).await;
+        let result = start_verification(&service, &pool, discord_id, krunker_username).await;
         assert!(result.is_ok());
 
         let code = result.unwrap();
@@ -166,9 +495,28 @@ mod tests {
         assert_eq!(code.len(), 15); // "VERIFY-" (7) + 8 chars
     }
 
+    #[tokio::test]
+    async fn test_start_verification_logs_event() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+
+        start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+
+        let events = queries::get_link_events_by_discord_id(&pool, "12345", 10)
+            .await
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, LinkEventKind::LinkStarted.as_str());
+        assert_eq!(events[0].krunker_username, "Player1");
+        assert_eq!(events[0].actor_id, "12345");
+    }
+
     #[tokio::test]
     async fn test_start_verification_already_linked() {
         let pool = setup_test_db().await;
+        let service = test_service();
         let discord_id = "12345";
 
         // Pre-create user
@@ -176,14 +524,39 @@ mod tests {
             .await
             .unwrap();
 
-        let result = start_verification(&pool, discord_id, "NewPlayer").await;
+        let result = start_verification(&service, &pool, discord_id, "existingplayer").await;
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("already linked"));
+
+        // A different account can still be added
+        assert!(
+            start_verification(&service, &pool, discord_id, "NewPlayer")
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_start_verification_account_limit() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+        let discord_id = "12345";
+
+        for i in 0..MAX_LINKED_ACCOUNTS {
+            queries::create_user(&pool, &format!("Player{}", i), discord_id, None)
+                .await
+                .unwrap();
+        }
+
+        let result = start_verification(&service, &pool, discord_id, "OneTooMany").await;
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("at most"));
     }
 
     #[tokio::test]
     async fn test_complete_verification_already_exists() {
         let pool = setup_test_db().await;
+        let service = test_service();
         let discord_id = "12345";
 
         // Pre-create user
@@ -191,14 +564,573 @@ mod tests {
             .await
             .unwrap();
 
-        // Try to complete verification for same discord_id
-        let result = complete_verification(&pool, discord_id, "Player2").await;
+        let now = service.now();
+        let expires_at = now + 600;
+        queries::create_verification(&pool, discord_id, "Player1", "VERIFY-AAAA", now, expires_at)
+            .await
+            .unwrap();
+        queries::create_verification(&pool, "67890", "Player1", "VERIFY-BBBB", now, expires_at)
+            .await
+            .unwrap();
+
+        // Completing the same account again should fail
+        let result = complete_verification(&service, &pool, discord_id, "Player1", None).await;
+        assert!(result.is_err());
+
+        // Linked to someone else
+        let result = complete_verification(&service, &pool, "67890", "Player1", None).await;
+        assert!(result.is_err());
+
+        // both sessions are closed out as failed
+        for id in [discord_id, "67890"] {
+            assert!(
+                queries::get_verification_by_discord_id(&pool, id, now)
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_complete_verification_without_session() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+
+        let result = complete_verification(&service, &pool, "12345", "Player1", None).await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("active verification")
+        );
+        assert!(
+            queries::get_user_by_username(&pool, "Player1")
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_complete_verification_marks_session_verified() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+        let now = service.now();
+        let expires_at = now + 600;
+
+        let id =
+            queries::create_verification(&pool, "12345", "Player1", "VERIFY-AAAA", now, expires_at)
+                .await
+                .unwrap();
+        complete_verification(&service, &pool, "12345", "Player1", None)
+            .await
+            .unwrap();
+
+        let history = queries::get_verification_history(&pool, id).await.unwrap();
+        let last = history.last().unwrap();
+        assert_eq!(last.to_status, VerificationStatus::Verified.as_str());
+
+        // the same session can't be used twice
+        let result = complete_verification(&service, &pool, "12345", "Player1", None).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_complete_verification_same_identity_under_old_name() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+
+        let id = queries::create_user(&pool, "OldName", "12345", None)
+            .await
+            .unwrap();
+        queries::set_user_identity(&pool, id, "krunker-1", 1000)
+            .await
+            .unwrap();
+
+        let now = service.now();
+        let expires_at = now + 600;
+        for id in ["12345", "67890"] {
+            queries::create_verification(&pool, id, "NewName", "VERIFY-AAAA", now, expires_at)
+                .await
+                .unwrap();
+        }
+
+        // someone else can't claim the renamed account under its new name
+        let result =
+            complete_verification(&service, &pool, "67890", "NewName", Some("krunker-1")).await;
         assert!(result.is_err());
+
+        let result =
+            complete_verification(&service, &pool, "12345", "NewName", Some("krunker-1")).await;
+        assert!(result.unwrap_err().to_string().contains("&renamed"));
+    }
+
+    #[tokio::test]
+    async fn test_complete_verification_adds_secondary_account() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+        let discord_id = "12345";
+
+        queries::create_user(&pool, "Player1", discord_id, None)
+            .await
+            .unwrap();
+
+        let now = service.now();
+        let expires_at = now + 600;
+        queries::create_verification(&pool, discord_id, "Player2", "VERIFY-AAAA", now, expires_at)
+            .await
+            .unwrap();
+        complete_verification(&service, &pool, discord_id, "Player2", None)
+            .await
+            .unwrap();
+
+        let accounts = queries::get_users_by_discord_id(&pool, discord_id)
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].username, "Player1");
+        assert!(!accounts[1].is_primary);
+    }
+
+    #[tokio::test]
+    async fn test_check_verification_with_fixture_posts() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+        let discord_id = "12345";
+
+        let code = start_verification(&service, &pool, discord_id, "Player1")
+            .await
+            .unwrap();
+        let now = service.now();
+
+        // code posted before this session started doesn't count
+        let stale = FixturePosts::new(vec![vec![(code.as_str(), now - 3600)]]);
+        match check_verification(&service, &pool, &stale, discord_id)
+            .await
+            .unwrap()
+        {
+            VerificationResult::NotFound { attempts, .. } => assert_eq!(attempts, 1),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        let fresh = FixturePosts::new(vec![vec![(code.as_str(), now + 5)]]);
+        match check_verification(&service, &pool, &fresh, discord_id)
+            .await
+            .unwrap()
+        {
+            VerificationResult::Success { krunker_username } => {
+                assert_eq!(krunker_username, "Player1")
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_start_verification_cooldown() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+
+        start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+
+        let result = start_verification(&service, &pool, "12345", "Player1").await;
+        assert!(result.unwrap_err().to_string().contains("Please wait"));
+    }
+
+    #[tokio::test]
+    async fn test_start_verification_conflicting_claim() {
+        let pool = setup_test_db().await;
+        let service = test_service();
+
+        start_verification(&service, &pool, "first", "Contested")
+            .await
+            .unwrap();
+
+        let result = start_verification(&service, &pool, "second", "contested").await;
+        assert!(result.unwrap_err().to_string().contains("Someone else"));
+
+        // a name linked to someone else can't be claimed either
+        queries::create_user(&pool, "Taken", "owner", None)
+            .await
+            .unwrap();
+        let result = start_verification(&service, &pool, "second", "Taken").await;
+        assert!(result.unwrap_err().to_string().contains("already linked"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_sessions_lock_out_across_link_calls() {
+        let pool = setup_test_db().await;
+        let (service, clock) = clocked_service();
+        let discord_id = "12345";
+        let no_posts = FixturePosts::new(vec![]);
+
+        for session in 1..=MAX_FAILED_SESSIONS {
+            // wait out the cooldown between sessions
+            clock.advance(LINK_COOLDOWN_SECONDS);
+
+            start_verification(&service, &pool, discord_id, "Player1")
+                .await
+                .unwrap();
+
+            for _ in 1..MAX_VERIFICATION_ATTEMPTS {
+                check_verification(&service, &pool, &no_posts, discord_id)
+                    .await
+                    .unwrap();
+            }
+            let result = check_verification(&service, &pool, &no_posts, discord_id).await;
+            let message = result.unwrap_err().to_string();
+
+            if session == MAX_FAILED_SESSIONS {
+                assert!(message.contains("try again"));
+            } else {
+                assert!(message.contains("start over"));
+            }
+        }
+
+        clock.advance(LINK_COOLDOWN_SECONDS);
+        let result = start_verification(&service, &pool, discord_id, "Player1").await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Too many failed verifications")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_session_counts_as_failed() {
+        let pool = setup_test_db().await;
+        let (service, clock) = clocked_service();
+        let discord_id = "12345";
+        let no_posts = FixturePosts::new(vec![]);
+
+        start_verification(&service, &pool, discord_id, "Player1")
+            .await
+            .unwrap();
+        check_verification(&service, &pool, &no_posts, discord_id)
+            .await
+            .unwrap();
+
+        clock.advance(LINK_COOLDOWN_SECONDS);
+        start_verification(&service, &pool, discord_id, "Player1")
+            .await
+            .unwrap();
+
+        let limits = queries::get_verification_limits(&pool, discord_id)
+            .await
+            .unwrap()
+            .expect("Limits should exist");
+        assert_eq!(limits.failed_sessions, 1);
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions_count_as_failed() {
+        let pool = setup_test_db().await;
+        let (service, clock) = clocked_service();
+        let discord_id = "12345";
+        let no_posts = FixturePosts::new(vec![]);
+
+        // check once, then wait the session out instead of replacing it
+        for _ in 0..MAX_FAILED_SESSIONS {
+            start_verification(&service, &pool, discord_id, "Player1")
+                .await
+                .unwrap();
+            check_verification(&service, &pool, &no_posts, discord_id)
+                .await
+                .unwrap();
+            clock.advance(VERIFICATION_EXPIRY_SECONDS + 1);
+        }
+
+        let result = start_verification(&service, &pool, discord_id, "Player1").await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Too many failed verifications")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unchecked_expired_session_is_not_a_failure() {
+        let pool = setup_test_db().await;
+        let (service, clock) = clocked_service();
+        let discord_id = "12345";
+
+        start_verification(&service, &pool, discord_id, "Player1")
+            .await
+            .unwrap();
+        clock.advance(VERIFICATION_EXPIRY_SECONDS + 1);
+        start_verification(&service, &pool, discord_id, "Player1")
+            .await
+            .unwrap();
+
+        let limits = queries::get_verification_limits(&pool, discord_id)
+            .await
+            .unwrap()
+            .expect("Limits should exist");
+        assert_eq!(limits.failed_sessions, 0);
+    }
+
+    #[test]
+    fn test_generate_code_uses_injected_generator() {
+        let (service, _) = fixtures::service(START, &["abcd1234"]);
+        assert_eq!(generate_code(&service), "VERIFY-ABCD1234");
+    }
+
+    #[tokio::test]
+    async fn test_start_verification_issues_exact_codes() {
+        let pool = setup_test_db().await;
+        let (service, clock) = fixtures::service(START, &["first001", "second02"]);
+
+        let code = start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+        assert_eq!(code, "VERIFY-FIRST001");
+
+        clock.advance(LINK_COOLDOWN_SECONDS);
+        let code = start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+        assert_eq!(code, "VERIFY-SECOND02");
+
+        let verification = queries::get_verification_by_discord_id(&pool, "12345", clock.now())
+            .await
+            .unwrap()
+            .expect("Verification should be pending");
+        assert_eq!(verification.code, "VERIFY-SECOND02");
+        assert_eq!(verification.created_at, START + LINK_COOLDOWN_SECONDS);
+        assert_eq!(
+            verification.expires_at,
+            START + LINK_COOLDOWN_SECONDS + VERIFICATION_EXPIRY_SECONDS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verification_expires_in_virtual_time() {
+        let pool = setup_test_db().await;
+        let (service, clock) = fixtures::service(START, &["expiring"]);
+        let no_posts = FixturePosts::new(vec![]);
+
+        start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+
+        clock.advance(VERIFICATION_EXPIRY_SECONDS - 1);
+        match check_verification(&service, &pool, &no_posts, "12345")
+            .await
+            .unwrap()
+        {
+            VerificationResult::NotFound { code, attempts, .. } => {
+                assert_eq!(code, "VERIFY-EXPIRING");
+                assert_eq!(attempts, 1);
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+
+        clock.advance(1);
+        assert!(matches!(
+            check_verification(&service, &pool, &no_posts, "12345")
+                .await
+                .unwrap(),
+            VerificationResult::NoVerification
+        ));
+
+        let latest = queries::get_latest_verification(&pool, "12345")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest.status, VerificationStatus::Expired.as_str());
+
+        let history = queries::get_verification_history(&pool, latest.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            history.last().unwrap().changed_at,
+            START + VERIFICATION_EXPIRY_SECONDS
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cooldown_ends_in_virtual_time() {
+        let pool = setup_test_db().await;
+        let (service, clock) = fixtures::service(START, &["aaaaaaaa", "bbbbbbbb"]);
+
+        start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+
+        clock.advance(LINK_COOLDOWN_SECONDS - 1);
+        let result = start_verification(&service, &pool, "12345", "Player1").await;
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("Please wait 1 seconds")
+        );
+
+        clock.advance(1);
+        let code = start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+        assert_eq!(code, "VERIFY-BBBBBBBB");
+    }
+
+    #[tokio::test]
+    async fn test_settings_override_defaults() {
+        let pool = setup_test_db().await;
+        let (service, clock) = fixtures::service(START, &["settings"]);
+        let service = service.with_settings(VerificationSettings {
+            expiry_seconds: 30,
+            max_attempts: 2,
+            cooldown_seconds: 0,
+            max_failed_sessions: 1,
+            lockout_seconds: 600,
+        });
+        let no_posts = FixturePosts::new(vec![]);
+
+        start_verification(&service, &pool, "12345", "Player1")
+            .await
+            .unwrap();
+        let verification = queries::get_verification_by_discord_id(&pool, "12345", clock.now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(verification.expires_at, START + 30);
+
+        check_verification(&service, &pool, &no_posts, "12345")
+            .await
+            .unwrap();
+        let result = check_verification(&service, &pool, &no_posts, "12345").await;
+        let message = result.unwrap_err().to_string();
+        assert!(message.contains("(2)"));
+        // one failed session is enough to be locked out for the configured time
+        assert!(message.contains(&format!("<t:{}:R>", START + 600)));
+    }
+
+    /// A file-backed database so several connections can race each other
+    async fn setup_shared_db(name: &str) -> AnyPool {
+        use sqlx::any::AnyPoolOptions;
+
+        // a Postgres test server already handles concurrent connections properly
+        if std::env::var("TEST_DATABASE_URL").is_ok() {
+            return setup_test_db().await;
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("krunker-bot-{}-{}.db", name, std::process::id()));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        sqlx::any::install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .max_connections(4)
+            .after_connect(|conn, _| {
+                Box::pin(async move {
+                    sqlx::query("PRAGMA journal_mode = WAL")
+                        .execute(&mut *conn)
+                        .await?;
+                    sqlx::query("PRAGMA busy_timeout = 10000")
+                        .execute(&mut *conn)
+                        .await?;
+                    Ok(())
+                })
+            })
+            .connect(&format!("sqlite://{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        crate::database::migrate(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_completions_link_once() {
+        let pool = setup_shared_db("concurrent-complete").await;
+        let service = test_service();
+        let now = service.now();
+        let expires_at = now + 600;
+        queries::create_verification(&pool, "12345", "Racer", "VERIFY-AAAA", now, expires_at)
+            .await
+            .unwrap();
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let pool = pool.clone();
+                let service = service.clone();
+                tokio::spawn(async move {
+                    complete_verification(&service, &pool, "12345", "Racer", None)
+                        .await
+                        .is_ok()
+                })
+            })
+            .collect();
+
+        let mut successes = 0;
+        for handle in handles {
+            if handle.await.unwrap() {
+                successes += 1;
+            }
+        }
+        assert_eq!(successes, 1);
+
+        let accounts = queries::get_users_by_discord_id(&pool, "12345")
+            .await
+            .unwrap();
+        assert_eq!(accounts.len(), 1);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_completion_races_direct_link() {
+        let pool = setup_shared_db("complete-vs-link").await;
+        let service = test_service();
+        let now = service.now();
+        let expires_at = now + 600;
+        queries::create_verification(&pool, "12345", "Contested", "VERIFY-AAAA", now, expires_at)
+            .await
+            .unwrap();
+
+        let verify = {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                complete_verification(&service, &pool, "12345", "Contested", None)
+                    .await
+                    .is_ok()
+            })
+        };
+        // what &forcelink does
+        let force = {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                queries::create_user(&pool, "contested", "67890", None)
+                    .await
+                    .is_ok()
+            })
+        };
+
+        let verified = verify.await.unwrap();
+        let forced = force.await.unwrap();
+        assert!(verified ^ forced);
+
+        // whichever casing won, the lookup finds it
+        let owner = queries::get_user_by_username(&pool, "Contested")
+            .await
+            .unwrap()
+            .expect("One of the two links should exist");
+        assert_eq!(owner.discord_id, if verified { "12345" } else { "67890" });
+        assert!(
+            queries::get_verification_by_discord_id(&pool, "12345", now)
+                .await
+                .unwrap()
+                .is_none()
+        );
     }
 
     #[tokio::test]
     async fn test_ishaq_ayubi_verification_pull() {
         let pool = setup_test_db().await;
+        let service = VerificationService::default();
 
         // Pull API key from .env (KRUNKER_API)
         dotenvy::dotenv().ok();
@@ -211,8 +1143,8 @@ mod tests {
 
         // Create the verification record manually for this test
         // Using Pepsi's account and just a random string for the verification
-        let now = Utc::now().timestamp();
-        queries::create_verification(&pool, discord_id, krunker_username, code, now + 600)
+        let now = service.now();
+        queries::create_verification(&pool, discord_id, krunker_username, code, now, now + 600)
             .await
             .unwrap();
 
@@ -222,7 +1154,7 @@ mod tests {
         );
 
         // Pull posts and check verification
-        match check_verification(&pool, &krunker_api, discord_id).await {
+        match check_verification(&service, &pool, &krunker_api, discord_id).await {
             Ok(result) => match result {
                 VerificationResult::Success { krunker_username } => {
                     println!("Success! Found verification for {}", krunker_username);
@@ -1 +1,3 @@
 pub mod flow;
+pub mod posts;
+pub mod service;
@@ -0,0 +1,296 @@
+use async_trait::async_trait;
+use chrono::DateTime;
+use krunker_rs::Client as KrunkerClient;
+
+use crate::metrics::track_api;
+
+/// Upper bound on pages fetched per check, in case post dates can't be read
+pub const MAX_POST_PAGES: u32 = 5;
+
+/// Krunker's clock and ours may disagree slightly, so allow posts dated a little
+/// before the code was issued
+pub const POST_CLOCK_SKEW_SECONDS: i64 = 30;
+
+/// A social post reduced to what verification needs
+#[derive(Debug, Clone)]
+pub struct PostSnapshot {
+    pub text: String,
+    /// Unix timestamp, None if the date couldn't be parsed
+    pub posted_at: Option<i64>,
+}
+
+/// Anything that can list a player's social posts page by page, newest first.
+/// An empty page means there are no more posts.
+#[async_trait]
+pub trait PostSource: Send + Sync {
+    async fn fetch_posts(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<PostSnapshot>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+#[async_trait]
+impl PostSource for KrunkerClient {
+    async fn fetch_posts(
+        &self,
+        username: &str,
+        page: u32,
+    ) -> Result<Vec<PostSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+        let response = track_api(
+            "get_player_posts",
+            self.get_player_posts(username, Some(page.try_into()?)),
+        )
+        .await?;
+
+        Ok(response
+            .posts_posts
+            .unwrap_or_default()
+            .into_iter()
+            .map(|post| PostSnapshot {
+                posted_at: parse_post_time(&post.post_date),
+                text: post.post_text,
+            })
+            .collect())
+    }
+}
+
+/// Reads a post's `post_date`, which the API sends as an RFC 3339 timestamp in UTC
+pub fn parse_post_time(post_date: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(post_date)
+        .ok()
+        .map(|dt| dt.timestamp())
+}
+
+/// Pages through a player's posts looking for one that contains `code` and was written
+/// after `issued_at`. Stops once a page reaches posts older than the code, since
+/// nothing further back can count. Posts without a readable date are ignored.
+pub async fn scan_posts_for_code(
+    source: &dyn PostSource,
+    username: &str,
+    code: &str,
+    issued_at: i64,
+) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+    let earliest = issued_at - POST_CLOCK_SKEW_SECONDS;
+
+    for page in 1..=MAX_POST_PAGES {
+        let posts = source.fetch_posts(username, page).await?;
+        if posts.is_empty() {
+            break;
+        }
+
+        let mut reached_older = false;
+        for post in &posts {
+            let posted_at = match post.posted_at {
+                Some(t) => t,
+                None => continue,
+            };
+
+            if posted_at < earliest {
+                reached_older = true;
+                continue;
+            }
+
+            if post.text.contains(code) {
+                return Ok(true);
+            }
+        }
+
+        if reached_older {
+            break;
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Serves canned pages of posts and counts how many were requested
+    pub struct FixturePosts {
+        pub pages: Vec<Vec<PostSnapshot>>,
+        pub fetched: AtomicU32,
+    }
+
+    impl FixturePosts {
+        pub fn new(pages: Vec<Vec<(&str, i64)>>) -> Self {
+            Self {
+                pages: pages
+                    .into_iter()
+                    .map(|page| {
+                        page.into_iter()
+                            .map(|(text, posted_at)| PostSnapshot {
+                                text: text.to_string(),
+                                posted_at: Some(posted_at),
+                            })
+                            .collect()
+                    })
+                    .collect(),
+                fetched: AtomicU32::new(0),
+            }
+        }
+
+        pub fn pages_fetched(&self) -> u32 {
+            self.fetched.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl PostSource for FixturePosts {
+        async fn fetch_posts(
+            &self,
+            _username: &str,
+            page: u32,
+        ) -> Result<Vec<PostSnapshot>, Box<dyn std::error::Error + Send + Sync>> {
+            self.fetched.fetch_add(1, Ordering::SeqCst);
+            Ok(self
+                .pages
+                .get(page as usize - 1)
+                .cloned()
+                .unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixtures::FixturePosts;
+    use super::*;
+
+    const ISSUED: i64 = 1_700_000_000;
+    const CODE: &str = "VERIFY-ABCD1234";
+
+    #[tokio::test]
+    async fn test_code_in_newest_post() {
+        let posts = FixturePosts::new(vec![vec![
+            ("hello VERIFY-ABCD1234", ISSUED + 60),
+            ("older post", ISSUED - 3600),
+        ]]);
+
+        assert!(
+            scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_in_post_older_than_issue_rejected() {
+        // e.g. a code that was posted for an earlier, abandoned session
+        let posts = FixturePosts::new(vec![vec![
+            ("unrelated", ISSUED + 60),
+            ("VERIFY-ABCD1234", ISSUED - 3600),
+        ]]);
+
+        assert!(
+            !scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_small_clock_skew_allowed() {
+        let posts = FixturePosts::new(vec![vec![("VERIFY-ABCD1234", ISSUED - 10)]]);
+
+        assert!(
+            scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_split_across_posts_rejected() {
+        let posts = FixturePosts::new(vec![vec![
+            ("ABCD1234", ISSUED + 120),
+            ("VERIFY-", ISSUED + 60),
+        ]]);
+
+        assert!(
+            !scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_code_on_second_page() {
+        let posts = FixturePosts::new(vec![
+            vec![("spam", ISSUED + 300), ("spam", ISSUED + 200)],
+            vec![("my code VERIFY-ABCD1234", ISSUED + 100)],
+            vec![("never fetched", ISSUED - 100)],
+        ]);
+
+        assert!(
+            scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+        assert_eq!(posts.pages_fetched(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stops_paging_at_older_posts() {
+        let posts = FixturePosts::new(vec![
+            vec![("new", ISSUED + 100), ("old", ISSUED - 100)],
+            vec![("VERIFY-ABCD1234", ISSUED - 200)],
+        ]);
+
+        assert!(
+            !scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+        assert_eq!(posts.pages_fetched(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_page_limit() {
+        let pages = (0..MAX_POST_PAGES + 2)
+            .map(|_| vec![("spam", ISSUED + 100)])
+            .collect();
+        let posts = FixturePosts::new(pages);
+
+        assert!(
+            !scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+        assert_eq!(posts.pages_fetched(), MAX_POST_PAGES);
+    }
+
+    #[tokio::test]
+    async fn test_undated_posts_ignored() {
+        let posts = FixturePosts {
+            pages: vec![vec![PostSnapshot {
+                text: CODE.to_string(),
+                posted_at: None,
+            }]],
+            fetched: Default::default(),
+        };
+
+        assert!(
+            !scan_posts_for_code(&posts, "p", CODE, ISSUED)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_post_time() {
+        // `post_date` the way the API sends it
+        assert_eq!(
+            parse_post_time("2023-11-14T22:13:20.000Z"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(parse_post_time("2023-11-14T22:13:20Z"), Some(1_700_000_000));
+        // anything else is treated as undated rather than guessed at
+        assert_eq!(parse_post_time("1700000000"), None);
+        assert_eq!(parse_post_time("2023-11-14 22:13:20"), None);
+        assert_eq!(parse_post_time(""), None);
+    }
+}
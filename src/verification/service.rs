@@ -0,0 +1,159 @@
+use chrono::Utc;
+use rand::distr::{Alphanumeric, SampleString};
+use std::sync::Arc;
+
+use super::flow::{
+    LINK_COOLDOWN_SECONDS, LOCKOUT_SECONDS, MAX_FAILED_SESSIONS, MAX_VERIFICATION_ATTEMPTS,
+    VERIFICATION_EXPIRY_SECONDS,
+};
+
+/// Source of the current time for the verification flow
+pub trait Clock: Send + Sync {
+    /// Current unix timestamp in seconds
+    fn now(&self) -> i64;
+}
+
+/// Source of the random part of verification codes
+pub trait CodeGenerator: Send + Sync {
+    fn generate(&self) -> String;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        Utc::now().timestamp()
+    }
+}
+
+/// 8 random alphanumeric characters
+pub struct RandomCodes;
+
+impl CodeGenerator for RandomCodes {
+    fn generate(&self) -> String {
+        Alphanumeric.sample_string(&mut rand::rng(), 8)
+    }
+}
+
+/// The parts of the flow that come from config and can be reloaded while running
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VerificationSettings {
+    pub expiry_seconds: i64,
+    pub max_attempts: i32,
+    pub cooldown_seconds: i64,
+    pub max_failed_sessions: i32,
+    pub lockout_seconds: i64,
+}
+
+impl Default for VerificationSettings {
+    fn default() -> Self {
+        Self {
+            expiry_seconds: VERIFICATION_EXPIRY_SECONDS,
+            max_attempts: MAX_VERIFICATION_ATTEMPTS,
+            cooldown_seconds: LINK_COOLDOWN_SECONDS,
+            max_failed_sessions: MAX_FAILED_SESSIONS,
+            lockout_seconds: LOCKOUT_SECONDS,
+        }
+    }
+}
+
+/// Everything in the verification flow that isn't deterministic, plus its settings.
+/// The bot uses the system clock and real randomness, tests swap in the fixtures below.
+#[derive(Clone)]
+pub struct VerificationService {
+    clock: Arc<dyn Clock>,
+    codes: Arc<dyn CodeGenerator>,
+    settings: VerificationSettings,
+}
+
+impl VerificationService {
+    pub fn new(clock: Arc<dyn Clock>, codes: Arc<dyn CodeGenerator>) -> Self {
+        Self {
+            clock,
+            codes,
+            settings: VerificationSettings::default(),
+        }
+    }
+
+    pub fn with_settings(self, settings: VerificationSettings) -> Self {
+        Self { settings, ..self }
+    }
+
+    pub fn settings(&self) -> VerificationSettings {
+        self.settings
+    }
+
+    pub fn now(&self) -> i64 {
+        self.clock.now()
+    }
+
+    pub fn random_code(&self) -> String {
+        self.codes.generate()
+    }
+}
+
+impl Default for VerificationService {
+    fn default() -> Self {
+        Self::new(Arc::new(SystemClock), Arc::new(RandomCodes))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicI64, Ordering};
+
+    /// A clock that only moves when told to
+    pub struct ManualClock {
+        now: AtomicI64,
+    }
+
+    impl ManualClock {
+        pub fn new(start: i64) -> Self {
+            Self {
+                now: AtomicI64::new(start),
+            }
+        }
+
+        pub fn advance(&self, seconds: i64) {
+            self.now.fetch_add(seconds, Ordering::SeqCst);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> i64 {
+            self.now.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Hands out the given codes in order, panicking once they run out
+    pub struct FixedCodes {
+        codes: Mutex<Vec<String>>,
+    }
+
+    impl FixedCodes {
+        pub fn new(codes: &[&str]) -> Self {
+            Self {
+                codes: Mutex::new(codes.iter().rev().map(|c| c.to_string()).collect()),
+            }
+        }
+    }
+
+    impl CodeGenerator for FixedCodes {
+        fn generate(&self) -> String {
+            self.codes
+                .lock()
+                .unwrap()
+                .pop()
+                .expect("FixedCodes ran out of codes")
+        }
+    }
+
+    /// A service on a manual clock, returned alongside the clock so tests can move it
+    pub fn service(start: i64, codes: &[&str]) -> (VerificationService, Arc<ManualClock>) {
+        let clock = Arc::new(ManualClock::new(start));
+        let service = VerificationService::new(clock.clone(), Arc::new(FixedCodes::new(codes)));
+        (service, clock)
+    }
+}